@@ -208,9 +208,9 @@ impl CheckpointManager {
                 let entry = entry?;
                 let path = entry.path();
                 if path.is_dir() {
-                    // Skip hidden directories like .git
+                    // Skip hidden, dependency and build directories
                     if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if name.starts_with('.') {
+                        if super::is_skipped_dir(name) {
                             continue;
                         }
                     }
@@ -465,9 +465,9 @@ impl CheckpointManager {
                 let entry = entry?;
                 let path = entry.path();
                 if path.is_dir() {
-                    // Skip hidden directories like .git
+                    // Skip hidden, dependency and build directories
                     if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if name.starts_with('.') {
+                        if super::is_skipped_dir(name) {
                             continue;
                         }
                     }
@@ -260,3 +260,13 @@ impl CheckpointPaths {
             .join(format!("{}.json", safe_filename))
     }
 }
+
+/// Whether a project directory is left out of checkpoints: hidden directories such as .git,
+/// and dependency or build output directories that are regenerated rather than edited
+pub fn is_skipped_dir(name: &str) -> bool {
+    name.starts_with('.')
+        || matches!(
+            name,
+            "node_modules" | "target" | "dist" | "build" | "__pycache__"
+        )
+}
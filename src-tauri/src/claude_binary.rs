@@ -365,7 +365,7 @@ fn find_standard_installations() -> Vec<ClaudeInstallation> {
 }
 
 /// Get Claude version by running --version command
-fn get_claude_version(path: &str) -> Result<Option<String>, String> {
+pub(crate) fn get_claude_version(path: &str) -> Result<Option<String>, String> {
     match Command::new(path).arg("--version").output() {
         Ok(output) => {
             if output.status.success() {
@@ -443,7 +443,7 @@ fn select_best_installation(installations: Vec<ClaudeInstallation>) -> Option<Cl
 }
 
 /// Compare two version strings
-fn compare_versions(a: &str, b: &str) -> Ordering {
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
     // Simple semantic version comparison
     let a_parts: Vec<u32> = a
         .split('.')
@@ -0,0 +1,362 @@
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::agents::AgentDb;
+
+/// Key used to encrypt secret values, stored next to the database
+const SECRET_KEY_FILE: &str = "secrets.key";
+const NONCE_LEN: usize = 12;
+const REDACTED: &str = "[REDACTED]";
+
+/// An environment variable defined on an agent. Secret values never leave the backend.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentEnvVar {
+    pub id: Option<i64>,
+    pub agent_id: i64,
+    pub key: String,
+    /// Plain value; always None for secrets
+    pub value: Option<String>,
+    pub is_secret: bool,
+    /// False for secrets imported from a file that still need a value
+    pub has_value: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Environment variable in an agent export; secret values are never exported
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedEnvVar {
+    pub key: String,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// Decrypted environment of an agent, used to configure its process and scrub its output
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AgentEnv {
+    vars: Vec<(String, String)>,
+    secrets: Vec<String>,
+}
+
+impl AgentEnv {
+    pub(crate) fn vars(&self) -> &[(String, String)] {
+        &self.vars
+    }
+
+    /// Replace every secret value in `text`, including its JSON-escaped form
+    pub(crate) fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for secret in &self.secrets {
+            redacted = redacted.replace(secret.as_str(), REDACTED);
+            if let Ok(escaped) = serde_json::to_string(secret) {
+                let escaped = &escaped[1..escaped.len() - 1];
+                if escaped != secret {
+                    redacted = redacted.replace(escaped, REDACTED);
+                }
+            }
+        }
+        redacted
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), String> {
+    let valid = regex::Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$")
+        .map(|re| re.is_match(key))
+        .unwrap_or(false);
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid environment variable name '{}': use letters, digits and underscores, not starting with a digit",
+            key
+        ))
+    }
+}
+
+fn read_secret_key(path: &Path) -> Result<Option<Key<Aes256Gcm>>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) if bytes.len() == 32 => Ok(Some(*Key::<Aes256Gcm>::from_slice(&bytes))),
+        Ok(_) => Err(format!("Secret key at {} is corrupt", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read secret key: {}", e)),
+    }
+}
+
+/// Load the encryption key, creating it with owner-only permissions on first use
+pub(crate) fn secret_key(app: &AppHandle) -> Result<Key<Aes256Gcm>, String> {
+    let app_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let path = app_dir.join(SECRET_KEY_FILE);
+
+    if let Some(key) = read_secret_key(&path)? {
+        return Ok(key);
+    }
+
+    // Write the key to a temporary file and link it into place, so that a concurrent
+    // caller never sees a partially written key
+    let key = Aes256Gcm::generate_key(OsRng);
+    std::fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
+    let temp_path = app_dir.join(format!("{}.{}.tmp", SECRET_KEY_FILE, uuid::Uuid::new_v4()));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options
+        .open(&temp_path)
+        .and_then(|mut file| file.write_all(&key))
+        .map_err(|e| format!("Failed to write secret key: {}", e))
+        .and_then(|_| match std::fs::hard_link(&temp_path, &path) {
+            Ok(()) => {
+                info!("Created secret key at {:?}", path);
+                Ok(key)
+            }
+            // Another caller created the key first; use theirs
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => read_secret_key(&path)?
+                .ok_or_else(|| format!("Secret key at {} disappeared", path.display())),
+            Err(e) => Err(format!("Failed to create secret key: {}", e)),
+        });
+    let _ = std::fs::remove_file(&temp_path);
+    written
+}
+
+/// Encrypt a value as base64 of nonce followed by ciphertext
+pub(crate) fn encrypt(key: &Key<Aes256Gcm>, value: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, value.as_bytes())
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+pub(crate) fn decrypt(key: &Key<Aes256Gcm>, sealed: &str) -> Result<String, String> {
+    let sealed = STANDARD.decode(sealed).map_err(|e| e.to_string())?;
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted value is too short".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let plaintext = Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt secret; the secret key may have changed".to_string())?;
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+fn row_to_env_var(row: &rusqlite::Row) -> rusqlite::Result<AgentEnvVar> {
+    let is_secret: bool = row.get(4)?;
+    let value: Option<String> = row.get(3)?;
+    Ok(AgentEnvVar {
+        id: Some(row.get(0)?),
+        agent_id: row.get(1)?,
+        key: row.get(2)?,
+        has_value: value.is_some(),
+        value: if is_secret { None } else { value },
+        is_secret,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn list_env_vars(conn: &Connection, agent_id: i64) -> Result<Vec<AgentEnvVar>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, agent_id, key, value, is_secret, created_at, updated_at
+             FROM agent_env_vars WHERE agent_id = ?1 ORDER BY key",
+        )
+        .map_err(|e| e.to_string())?;
+    let vars = stmt
+        .query_map(params![agent_id], row_to_env_var)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(vars)
+}
+
+/// Decrypt an agent's environment. Secrets that were never given a value are left out.
+pub(crate) fn load_agent_env(
+    app: &AppHandle,
+    conn: &Connection,
+    agent_id: i64,
+) -> Result<AgentEnv, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value, is_secret FROM agent_env_vars WHERE agent_id = ?1 AND value IS NOT NULL ORDER BY key")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![agent_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut env = AgentEnv::default();
+    if rows.is_empty() {
+        return Ok(env);
+    }
+    let key = if rows.iter().any(|(_, _, is_secret)| *is_secret) {
+        Some(secret_key(app)?)
+    } else {
+        None
+    };
+
+    for (name, value, is_secret) in rows {
+        let value = match &key {
+            Some(key) if is_secret => decrypt(key, &value)
+                .map_err(|e| format!("Environment variable {}: {}", name, e))?,
+            _ => value,
+        };
+        if is_secret && !value.is_empty() {
+            env.secrets.push(value.clone());
+        }
+        env.vars.push((name, value));
+    }
+    // Longest first so a secret containing another is not partially redacted
+    env.secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    Ok(env)
+}
+
+/// The agent's environment for redacting output; a failure to decrypt only disables redaction
+pub(crate) fn load_redaction(app: &AppHandle, conn: &Connection, agent_id: i64) -> AgentEnv {
+    load_agent_env(app, conn, agent_id).unwrap_or_else(|e| {
+        warn!("Failed to load environment of agent {} for redaction: {}", agent_id, e);
+        AgentEnv::default()
+    })
+}
+
+/// Environment variables of an agent for its export, without secret values
+pub(crate) fn export_env_vars(conn: &Connection, agent_id: i64) -> Result<Vec<ExportedEnvVar>, String> {
+    Ok(list_env_vars(conn, agent_id)?
+        .into_iter()
+        .map(|var| ExportedEnvVar {
+            key: var.key,
+            value: var.value,
+            secret: var.is_secret,
+        })
+        .collect())
+}
+
+/// Store the variables of an imported agent. Secrets are created without a value.
+pub(crate) fn import_env_vars(
+    conn: &Connection,
+    agent_id: i64,
+    vars: &[ExportedEnvVar],
+) -> Result<(), String> {
+    for var in vars {
+        validate_key(&var.key)?;
+        if var.secret && var.value.is_some() {
+            warn!("Ignoring the value of imported secret {}", var.key);
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO agent_env_vars (agent_id, key, value, is_secret) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                agent_id,
+                var.key,
+                if var.secret { None } else { var.value.clone() },
+                var.secret
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// List an agent's environment variables with secret values hidden
+#[tauri::command]
+pub async fn list_agent_env_vars(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<Vec<AgentEnvVar>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    list_env_vars(&conn, agent_id)
+}
+
+/// Create or replace an environment variable; secret values are encrypted before storage
+#[tauri::command]
+pub async fn set_agent_env_var(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    key: String,
+    value: String,
+    is_secret: bool,
+) -> Result<AgentEnvVar, String> {
+    let key = key.trim().to_string();
+    validate_key(&key)?;
+
+    let stored = if is_secret {
+        encrypt(&secret_key(&app)?, &value)?
+    } else {
+        value
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO agent_env_vars (agent_id, key, value, is_secret) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(agent_id, key) DO UPDATE SET value = ?3, is_secret = ?4, updated_at = CURRENT_TIMESTAMP",
+        params![agent_id, key, stored, is_secret],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id, agent_id, key, value, is_secret, created_at, updated_at
+         FROM agent_env_vars WHERE agent_id = ?1 AND key = ?2",
+        params![agent_id, key],
+        row_to_env_var,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Delete an environment variable
+#[tauri::command]
+pub async fn delete_agent_env_var(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM agent_env_vars WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let sealed = encrypt(&key, "ghp_secret").unwrap();
+
+        assert!(!sealed.contains("ghp_secret"));
+        assert_eq!(decrypt(&key, &sealed).unwrap(), "ghp_secret");
+        assert!(decrypt(&Aes256Gcm::generate_key(OsRng), &sealed).is_err());
+    }
+
+    #[test]
+    fn test_redact() {
+        let env = AgentEnv {
+            vars: vec![("TOKEN".to_string(), "abc\"123".to_string())],
+            secrets: vec!["abc\"123".to_string()],
+        };
+
+        assert_eq!(env.redact("token=abc\"123"), "token=[REDACTED]");
+        assert_eq!(
+            env.redact(r#"{"text":"abc\"123"}"#),
+            r#"{"text":"[REDACTED]"}"#
+        );
+    }
+}
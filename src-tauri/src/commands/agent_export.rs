@@ -0,0 +1,406 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use log::{info, warn};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::State;
+
+use crate::commands::agents::{load_agent, Agent, AgentDb};
+use crate::commands::slash_commands::command_file_content;
+
+/// Version written by `export_agent`. Version 1 files are still imported.
+pub const AGENT_EXPORT_VERSION: u32 = 2;
+
+/// Tool permissions of an agent, carried by v2 exports
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AgentPermissions {
+    pub file_read: bool,
+    pub file_write: bool,
+    pub network: bool,
+}
+
+/// An MCP server the agent expects to be configured. Environment values are
+/// not exported; only the names of the variables the server needs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RequiredMcpServer {
+    pub name: String,
+    /// "stdio" or "sse"
+    pub transport: String,
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub env_keys: Vec<String>,
+}
+
+/// A slash command shipped with the agent and installed into the project it runs in
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BundledSlashCommand {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    pub content: String,
+}
+
+/// JSON schema of `.claudia.json` files (versions 1 and 2)
+pub fn export_schema() -> Value {
+    let string_array = json!({ "type": "array", "items": { "type": "string" } });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Claudia agent export",
+        "type": "object",
+        "required": ["version", "agent"],
+        "properties": {
+            "version": { "type": "integer", "enum": [1, 2] },
+            "exported_at": { "type": "string" },
+            "agent": {
+                "type": "object",
+                "required": ["name", "icon", "system_prompt", "model"],
+                "properties": {
+                    "name": { "type": "string", "minLength": 1 },
+                    "icon": { "type": "string" },
+                    "system_prompt": { "type": "string" },
+                    "default_task": { "type": ["string", "null"] },
+                    "model": { "type": "string", "minLength": 1 },
+                    "hooks": { "type": ["string", "null"] },
+                    "task_variables": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["name"],
+                            "properties": {
+                                "name": { "type": "string", "pattern": "^[A-Za-z0-9_-]+$" },
+                                "description": { "type": ["string", "null"] },
+                                "default_value": { "type": ["string", "null"] }
+                            }
+                        }
+                    },
+                    "permissions": {
+                        "type": ["object", "null"],
+                        "required": ["file_read", "file_write", "network"],
+                        "properties": {
+                            "file_read": { "type": "boolean" },
+                            "file_write": { "type": "boolean" },
+                            "network": { "type": "boolean" }
+                        },
+                        "additionalProperties": false
+                    },
+                    "required_mcp_servers": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["name", "transport"],
+                            "properties": {
+                                "name": { "type": "string", "minLength": 1 },
+                                "transport": { "type": "string", "enum": ["stdio", "sse"] },
+                                "command": { "type": ["string", "null"] },
+                                "args": string_array,
+                                "url": { "type": ["string", "null"] },
+                                "env_keys": string_array
+                            }
+                        }
+                    },
+                    "slash_commands": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["name", "content"],
+                            "properties": {
+                                "name": { "type": "string", "pattern": "^[A-Za-z0-9_-]+$" },
+                                "namespace": { "type": ["string", "null"], "pattern": "^[A-Za-z0-9_-]+(:[A-Za-z0-9_-]+)*$" },
+                                "description": { "type": ["string", "null"] },
+                                "allowed_tools": string_array,
+                                "content": { "type": "string" }
+                            }
+                        }
+                    },
+                    "min_claude_version": {
+                        "type": ["string", "null"],
+                        "pattern": "^\\d+\\.\\d+\\.\\d+"
+                    },
+                    "output_schema": { "type": ["string", "null"] },
+                    "tags": string_array,
+                    "env": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["key"],
+                            "properties": {
+                                "key": { "type": "string", "pattern": "^[A-Za-z_][A-Za-z0-9_]*$" },
+                                "value": { "type": ["string", "null"] },
+                                "secret": { "type": "boolean" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+/// Validate `value` against the subset of JSON schema used by `export_schema`,
+/// collecting one message per violation prefixed with its location
+fn validate_value(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "document" } else { path };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.iter().any(|t| matches_type(value, t)) {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                at,
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            let options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+            errors.push(format!("{}: must be one of {}", at, options.join(", ")));
+        }
+    }
+
+    if let Value::String(s) = value {
+        if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+            if (s.chars().count() as u64) < min {
+                errors.push(format!("{}: must not be empty", at));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+            if regex::Regex::new(pattern).map(|re| !re.is_match(s)).unwrap_or(false) {
+                errors.push(format!("{}: '{}' does not match {}", at, s, pattern));
+            }
+        }
+    }
+
+    if let Value::Array(items) = value {
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                validate_value(item_schema, item, &format!("{}[{}]", path, i), errors);
+            }
+        }
+    }
+
+    if let Value::Object(object) = value {
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                if !object.contains_key(key) {
+                    errors.push(format!("{}: missing required field '{}'", at, key));
+                }
+            }
+        }
+        for (key, child) in object {
+            let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            match properties.and_then(|p| p.get(key)) {
+                Some(child_schema) => validate_value(child_schema, child, &child_path, errors),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{}: unknown field", child_path));
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Validate `value` against a schema using the same subset of JSON schema as the export
+/// format (type, enum, minLength, pattern, items, required, properties, additionalProperties)
+pub(crate) fn validate_against_schema(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_value(schema, value, "", &mut errors);
+    errors
+}
+
+/// Check an exported agent document against the export schema
+pub fn validate_export(value: &Value) -> Result<(), String> {
+    let mut errors = Vec::new();
+    validate_value(&export_schema(), value, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Invalid agent file:\n- {}", errors.join("\n- ")))
+    }
+}
+
+/// Parse a JSON array column, treating NULL or invalid JSON as empty
+pub(crate) fn parse_json_list<T: serde::de::DeserializeOwned>(json: Option<String>) -> Vec<T> {
+    json.and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Serialize a list for storage; empty lists are stored as NULL
+pub(crate) fn serialize_json_list<T: Serialize>(items: &[T]) -> Option<String> {
+    if items.is_empty() {
+        None
+    } else {
+        serde_json::to_string(items).ok()
+    }
+}
+
+/// Fail if the Claude Code binary is older than an agent requires.
+/// Binaries whose version can't be determined (e.g. the sidecar) are allowed.
+pub(crate) fn check_min_claude_version(claude_path: &str, min_version: &str) -> Result<(), String> {
+    match crate::claude_binary::get_claude_version(claude_path) {
+        Ok(Some(version))
+            if crate::claude_binary::compare_versions(&version, min_version) == Ordering::Less =>
+        {
+            Err(format!(
+                "This agent requires Claude Code {} or newer, but {} is installed",
+                min_version, version
+            ))
+        }
+        Ok(Some(_)) => Ok(()),
+        _ => {
+            warn!("Could not determine the Claude Code version to check against {}", min_version);
+            Ok(())
+        }
+    }
+}
+
+/// Write an agent's bundled slash commands into the project it runs in.
+/// Commands that already exist in the project are left alone.
+pub(crate) fn install_bundled_commands(
+    project_path: &str,
+    commands: &[BundledSlashCommand],
+) -> Result<(), String> {
+    let commands_dir = Path::new(project_path).join(".claude").join("commands");
+    for command in commands {
+        let mut dir = commands_dir.clone();
+        for component in command.namespace.iter().flat_map(|ns| ns.split(':')) {
+            dir = dir.join(component);
+        }
+        let file_path = dir.join(format!("{}.md", command.name));
+        if file_path.exists() {
+            continue;
+        }
+
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create commands directory: {}", e))?;
+        let content = command_file_content(
+            command.description.as_deref(),
+            &command.allowed_tools,
+            &command.content,
+        );
+        std::fs::write(&file_path, content)
+            .map_err(|e| format!("Failed to write slash command: {}", e))?;
+        info!("Installed bundled slash command at {:?}", file_path);
+    }
+    Ok(())
+}
+
+/// Get the JSON schema of `.claudia.json` files, e.g. for editor tooling
+#[tauri::command]
+pub async fn get_agent_export_schema() -> Result<Value, String> {
+    Ok(export_schema())
+}
+
+/// Set the MCP servers, bundled slash commands and minimum Claude Code version of an agent
+#[tauri::command]
+pub async fn update_agent_requirements(
+    db: State<'_, AgentDb>,
+    id: i64,
+    required_mcp_servers: Vec<RequiredMcpServer>,
+    slash_commands: Vec<BundledSlashCommand>,
+    min_claude_version: Option<String>,
+) -> Result<Agent, String> {
+    let min_claude_version = min_claude_version.filter(|v| !v.trim().is_empty());
+    let document = json!({
+        "version": AGENT_EXPORT_VERSION,
+        "agent": {
+            "name": "-",
+            "icon": "-",
+            "system_prompt": "",
+            "model": "-",
+            "required_mcp_servers": required_mcp_servers,
+            "slash_commands": slash_commands,
+            "min_claude_version": min_claude_version
+        }
+    });
+    validate_export(&document)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agents SET required_mcp_servers = ?1, slash_commands = ?2, min_claude_version = ?3 WHERE id = ?4",
+        params![
+            serialize_json_list(&required_mcp_servers),
+            serialize_json_list(&slash_commands),
+            min_claude_version,
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    load_agent(&conn, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_v1_export() {
+        let v1 = json!({
+            "version": 1,
+            "exported_at": "2025-01-01T00:00:00Z",
+            "agent": {
+                "name": "Reviewer",
+                "icon": "bot",
+                "system_prompt": "Review the code.",
+                "default_task": null,
+                "model": "sonnet",
+                "hooks": null
+            }
+        });
+        assert!(validate_export(&v1).is_ok());
+    }
+
+    #[test]
+    fn test_validation_errors_name_the_field() {
+        let invalid = json!({
+            "version": 3,
+            "agent": {
+                "name": "",
+                "system_prompt": "Review the code.",
+                "model": "sonnet",
+                "permissions": { "file_read": "yes", "file_write": true, "network": false },
+                "slash_commands": [{ "name": "fix it", "content": "Fix $ARGUMENTS" }]
+            }
+        });
+        let error = validate_export(&invalid).unwrap_err();
+
+        assert!(error.contains("version: must be one of 1, 2"));
+        assert!(error.contains("agent: missing required field 'icon'"));
+        assert!(error.contains("agent.name: must not be empty"));
+        assert!(error.contains("agent.permissions.file_read: expected boolean, found string"));
+        assert!(error.contains("agent.slash_commands[0].name"));
+    }
+}
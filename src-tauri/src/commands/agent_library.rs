@@ -0,0 +1,193 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agent_export::serialize_json_list;
+use crate::commands::agents::{load_agent, Agent, AgentDb};
+use crate::commands::run_annotations::normalize_tags;
+
+/// Order of the agent list
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentSort {
+    /// Newest first
+    #[default]
+    Created,
+    Name,
+    /// Most recently used first, never used last
+    LastUsed,
+    /// Most runs first
+    RunCount,
+}
+
+impl AgentSort {
+    pub(crate) fn order_clause(self) -> &'static str {
+        match self {
+            AgentSort::Created => "created_at DESC",
+            AgentSort::Name => "name COLLATE NOCASE, created_at DESC",
+            AgentSort::LastUsed => {
+                "agent_library.last_used_at IS NULL, agent_library.last_used_at DESC, name COLLATE NOCASE"
+            }
+            AgentSort::RunCount => {
+                "COALESCE(agent_library.run_count, 0) DESC, name COLLATE NOCASE"
+            }
+        }
+    }
+}
+
+/// Trim the segments of a folder path such as " Reviews / Security "; None for no folder
+pub(crate) fn normalize_folder(folder: &str) -> Option<String> {
+    let segments: Vec<&str> = folder
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+/// LIKE pattern matching `search` anywhere, with `\` escaping wildcards in the search text
+pub(crate) fn search_pattern(search: &str) -> Option<String> {
+    let search = search.trim();
+    if search.is_empty() {
+        return None;
+    }
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    Some(format!("%{}%", escaped))
+}
+
+/// Count a new run of the agent and mark it as used now
+pub(crate) fn record_use(conn: &Connection, agent_id: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO agent_library (agent_id, last_used_at, run_count) VALUES (?1, CURRENT_TIMESTAMP, 1)
+         ON CONFLICT(agent_id) DO UPDATE SET last_used_at = CURRENT_TIMESTAMP, run_count = run_count + 1",
+        params![agent_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Replace the tags of an agent with their normalized form
+pub(crate) fn set_tags(conn: &Connection, agent_id: i64, tags: &[String]) -> Result<(), String> {
+    let tags = normalize_tags(tags.to_vec());
+    conn.execute(
+        "INSERT INTO agent_library (agent_id, tags) VALUES (?1, ?2)
+         ON CONFLICT(agent_id) DO UPDATE SET tags = ?2",
+        params![agent_id, serialize_json_list(&tags)],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Move an agent into a folder, or out of all folders with None or an empty name
+#[tauri::command]
+pub async fn set_agent_folder(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    folder: Option<String>,
+) -> Result<Agent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let folder = folder.as_deref().and_then(normalize_folder);
+    load_agent(&conn, agent_id)?;
+    conn.execute(
+        "INSERT INTO agent_library (agent_id, folder) VALUES (?1, ?2)
+         ON CONFLICT(agent_id) DO UPDATE SET folder = ?2",
+        params![agent_id, folder],
+    )
+    .map_err(|e| e.to_string())?;
+    load_agent(&conn, agent_id)
+}
+
+/// Replace the tags of an agent
+#[tauri::command]
+pub async fn set_agent_tags(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    tags: Vec<String>,
+) -> Result<Agent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_agent(&conn, agent_id)?;
+    set_tags(&conn, agent_id, &tags)?;
+    load_agent(&conn, agent_id)
+}
+
+/// Add an agent to or remove it from the favourites
+#[tauri::command]
+pub async fn set_agent_favorite(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    favorite: bool,
+) -> Result<Agent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_agent(&conn, agent_id)?;
+    conn.execute(
+        "INSERT INTO agent_library (agent_id, favorite) VALUES (?1, ?2)
+         ON CONFLICT(agent_id) DO UPDATE SET favorite = ?2",
+        params![agent_id, favorite],
+    )
+    .map_err(|e| e.to_string())?;
+    load_agent(&conn, agent_id)
+}
+
+/// All folders that contain agents, sorted by name
+#[tauri::command]
+pub async fn list_agent_folders(db: State<'_, AgentDb>) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT l.folder FROM agent_library l JOIN agents a ON a.id = l.agent_id
+             WHERE l.folder IS NOT NULL ORDER BY l.folder COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let folders = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(folders)
+}
+
+/// All tags used on agents, most used first
+#[tauri::command]
+pub async fn list_agent_tags(db: State<'_, AgentDb>) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.value FROM agent_library l JOIN agents a ON a.id = l.agent_id, json_each(l.tags) t
+             WHERE l.tags IS NOT NULL
+             GROUP BY t.value ORDER BY COUNT(*) DESC, t.value",
+        )
+        .map_err(|e| e.to_string())?;
+    let tags = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_folder() {
+        assert_eq!(
+            normalize_folder(" Reviews / Security/ "),
+            Some("Reviews/Security".to_string())
+        );
+        assert_eq!(normalize_folder(" / "), None);
+    }
+
+    #[test]
+    fn test_search_pattern() {
+        assert_eq!(search_pattern(" review "), Some("%review%".to_string()));
+        assert_eq!(
+            search_pattern("100%_done"),
+            Some("%100\\%\\_done%".to_string())
+        );
+        assert_eq!(search_pattern("  "), None);
+    }
+}
@@ -0,0 +1,484 @@
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agent_export::{
+    parse_json_list, serialize_json_list, BundledSlashCommand, RequiredMcpServer,
+};
+use crate::commands::agents::{
+    load_agent, parse_task_variables, serialize_task_variables, Agent, AgentDb,
+};
+use crate::commands::task_templates::TaskVariable;
+use crate::process::budget::RunBudget;
+
+/// An immutable snapshot of an agent's configuration
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentRevision {
+    pub id: i64,
+    pub agent_id: i64,
+    pub revision: i64, // 1-based, increasing per agent
+    pub name: String,
+    pub icon: String,
+    pub system_prompt: String,
+    pub default_task: Option<String>,
+    pub model: String,
+    pub enable_file_read: bool,
+    pub enable_file_write: bool,
+    pub enable_network: bool,
+    pub hooks: Option<String>,
+    pub task_variables: Vec<TaskVariable>,
+    pub budget: RunBudget,
+    pub required_mcp_servers: Vec<RequiredMcpServer>,
+    pub slash_commands: Vec<BundledSlashCommand>,
+    pub min_claude_version: Option<String>,
+    pub output_schema: Option<String>,
+    pub restored_from: Option<i64>, // Revision ID this one was restored from, if any
+    pub created_at: String,
+}
+
+/// A line of a prompt diff
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: String, // 'equal', 'added', 'removed'
+    pub text: String,
+}
+
+/// Differences between two revisions of an agent
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentRevisionDiff {
+    pub from: AgentRevision,
+    pub to: AgentRevision,
+    /// Line diff of the system prompts
+    pub prompt_diff: Vec<DiffLine>,
+    /// Names of the other fields that differ
+    pub changed_fields: Vec<String>,
+}
+
+const REVISION_COLUMNS: &str = "id, agent_id, revision, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, restored_from, created_at, task_variables, max_duration_secs, max_tokens, max_cost_usd, required_mcp_servers, slash_commands, min_claude_version, output_schema";
+
+impl AgentRevision {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(AgentRevision {
+            id: row.get(0)?,
+            agent_id: row.get(1)?,
+            revision: row.get(2)?,
+            name: row.get(3)?,
+            icon: row.get(4)?,
+            system_prompt: row.get(5)?,
+            default_task: row.get(6)?,
+            model: row.get(7)?,
+            enable_file_read: row.get(8)?,
+            enable_file_write: row.get(9)?,
+            enable_network: row.get(10)?,
+            hooks: row.get(11)?,
+            restored_from: row.get(12)?,
+            created_at: row.get(13)?,
+            task_variables: parse_task_variables(row.get::<_, Option<String>>(14)?),
+            budget: RunBudget::from_row(row, 15)?,
+            required_mcp_servers: parse_json_list(row.get::<_, Option<String>>(18)?),
+            slash_commands: parse_json_list(row.get::<_, Option<String>>(19)?),
+            min_claude_version: row.get(20)?,
+            output_schema: row.get(21)?,
+        })
+    }
+
+    /// Names of the versioned fields that differ from another revision, prompt excluded
+    fn changed_fields(&self, other: &AgentRevision) -> Vec<String> {
+        let mut fields = Vec::new();
+        if self.name != other.name {
+            fields.push("name");
+        }
+        if self.icon != other.icon {
+            fields.push("icon");
+        }
+        if self.default_task != other.default_task {
+            fields.push("default_task");
+        }
+        if self.model != other.model {
+            fields.push("model");
+        }
+        if self.enable_file_read != other.enable_file_read {
+            fields.push("enable_file_read");
+        }
+        if self.enable_file_write != other.enable_file_write {
+            fields.push("enable_file_write");
+        }
+        if self.enable_network != other.enable_network {
+            fields.push("enable_network");
+        }
+        if self.hooks != other.hooks {
+            fields.push("hooks");
+        }
+        if self.task_variables != other.task_variables {
+            fields.push("task_variables");
+        }
+        if self.budget != other.budget {
+            fields.push("budget");
+        }
+        if self.required_mcp_servers != other.required_mcp_servers {
+            fields.push("required_mcp_servers");
+        }
+        if self.slash_commands != other.slash_commands {
+            fields.push("slash_commands");
+        }
+        if self.min_claude_version != other.min_claude_version {
+            fields.push("min_claude_version");
+        }
+        if self.output_schema != other.output_schema {
+            fields.push("output_schema");
+        }
+        fields.into_iter().map(String::from).collect()
+    }
+
+    /// Whether the revision captures exactly the agent's current configuration
+    fn matches(&self, agent: &Agent) -> bool {
+        self.name == agent.name
+            && self.icon == agent.icon
+            && self.system_prompt == agent.system_prompt
+            && self.default_task == agent.default_task
+            && self.model == agent.model
+            && self.enable_file_read == agent.enable_file_read
+            && self.enable_file_write == agent.enable_file_write
+            && self.enable_network == agent.enable_network
+            && self.hooks == agent.hooks
+            && self.task_variables == agent.task_variables
+            && self.budget == agent.budget
+            && self.required_mcp_servers == agent.required_mcp_servers
+            && self.slash_commands == agent.slash_commands
+            && self.min_claude_version == agent.min_claude_version
+            && self.output_schema == agent.output_schema
+    }
+
+    /// Overwrite the agent's configuration with the one captured by the revision
+    pub(crate) fn apply_to(self, agent: &mut Agent) {
+        agent.name = self.name;
+        agent.icon = self.icon;
+        agent.system_prompt = self.system_prompt;
+        agent.default_task = self.default_task;
+        agent.model = self.model;
+        agent.enable_file_read = self.enable_file_read;
+        agent.enable_file_write = self.enable_file_write;
+        agent.enable_network = self.enable_network;
+        agent.hooks = self.hooks;
+        agent.task_variables = self.task_variables;
+        agent.budget = self.budget;
+        agent.required_mcp_servers = self.required_mcp_servers;
+        agent.slash_commands = self.slash_commands;
+        agent.min_claude_version = self.min_claude_version;
+        agent.output_schema = self.output_schema;
+    }
+}
+
+pub(crate) fn load_revision(conn: &Connection, id: i64) -> Result<AgentRevision, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM agent_revisions WHERE id = ?1", REVISION_COLUMNS),
+        params![id],
+        AgentRevision::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn latest_revision(conn: &Connection, agent_id: i64) -> Result<Option<AgentRevision>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM agent_revisions WHERE agent_id = ?1 ORDER BY revision DESC LIMIT 1",
+            REVISION_COLUMNS
+        ),
+        params![agent_id],
+        AgentRevision::from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn insert_revision(
+    conn: &Connection,
+    agent: &Agent,
+    restored_from: Option<i64>,
+) -> Result<i64, String> {
+    let agent_id = agent.id.ok_or("Agent has no ID")?;
+    conn.execute(
+        "INSERT INTO agent_revisions (agent_id, revision, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, restored_from, task_variables, max_duration_secs, max_tokens, max_cost_usd, required_mcp_servers, slash_commands, min_claude_version, output_schema)
+         VALUES (?1, (SELECT COALESCE(MAX(revision), 0) + 1 FROM agent_revisions WHERE agent_id = ?1), ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        params![
+            agent_id,
+            agent.name,
+            agent.icon,
+            agent.system_prompt,
+            agent.default_task,
+            agent.model,
+            agent.enable_file_read,
+            agent.enable_file_write,
+            agent.enable_network,
+            agent.hooks,
+            restored_from,
+            serialize_task_variables(&agent.task_variables),
+            agent.budget.max_duration_secs,
+            agent.budget.max_tokens,
+            agent.budget.max_cost_usd,
+            serialize_json_list(&agent.required_mcp_servers),
+            serialize_json_list(&agent.slash_commands),
+            agent.min_claude_version,
+            agent.output_schema
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Return the revision matching the agent's current configuration, recording a new
+/// one if the agent changed since its latest revision (or has none yet)
+pub(crate) fn ensure_current_revision(conn: &Connection, agent: &Agent) -> Result<i64, String> {
+    let agent_id = agent.id.ok_or("Agent has no ID")?;
+    match latest_revision(conn, agent_id)? {
+        Some(latest) if latest.matches(agent) => Ok(latest.id),
+        _ => insert_revision(conn, agent, None),
+    }
+}
+
+/// Line-based diff (longest common subsequence) of two texts
+pub(crate) fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let line = |kind: &str, text: &str| DiffLine {
+        kind: kind.to_string(),
+        text: text.to_string(),
+    };
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(line("equal", old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(line("removed", old[i]));
+            i += 1;
+        } else {
+            diff.push(line("added", new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|text| line("removed", text)));
+    diff.extend(new[j..].iter().map(|text| line("added", text)));
+
+    diff
+}
+
+/// List the revisions of an agent, newest first
+#[tauri::command]
+pub async fn list_agent_revisions(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<Vec<AgentRevision>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    // Agents created before revisions existed get their first revision on demand
+    let agent = load_agent(&conn, agent_id)?;
+    ensure_current_revision(&conn, &agent)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_revisions WHERE agent_id = ?1 ORDER BY revision DESC",
+            REVISION_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let revisions = stmt
+        .query_map(params![agent_id], AgentRevision::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(revisions)
+}
+
+/// Get a single revision by ID
+#[tauri::command]
+pub async fn get_agent_revision(db: State<'_, AgentDb>, id: i64) -> Result<AgentRevision, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_revision(&conn, id)
+}
+
+/// Diff the system prompts (and list the other changed fields) of two revisions
+#[tauri::command]
+pub async fn diff_agent_revisions(
+    db: State<'_, AgentDb>,
+    from_revision_id: i64,
+    to_revision_id: i64,
+) -> Result<AgentRevisionDiff, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let from = load_revision(&conn, from_revision_id)?;
+    let to = load_revision(&conn, to_revision_id)?;
+
+    if from.agent_id != to.agent_id {
+        return Err("Revisions belong to different agents".to_string());
+    }
+
+    Ok(AgentRevisionDiff {
+        prompt_diff: diff_lines(&from.system_prompt, &to.system_prompt),
+        changed_fields: from.changed_fields(&to),
+        from,
+        to,
+    })
+}
+
+/// Restore an agent to an older revision. History is kept: the restore is recorded
+/// as a new revision that references the one it was restored from.
+#[tauri::command]
+pub async fn restore_agent_revision(
+    db: State<'_, AgentDb>,
+    revision_id: i64,
+) -> Result<Agent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let revision = load_revision(&conn, revision_id)?;
+
+    // Make sure the state being replaced is preserved as a revision
+    let current = load_agent(&conn, revision.agent_id)?;
+    ensure_current_revision(&conn, &current)?;
+
+    conn.execute(
+        "UPDATE agents SET name = ?1, icon = ?2, system_prompt = ?3, default_task = ?4, model = ?5, enable_file_read = ?6, enable_file_write = ?7, enable_network = ?8, hooks = ?9, task_variables = ?10, max_duration_secs = ?11, max_tokens = ?12, max_cost_usd = ?13, required_mcp_servers = ?14, slash_commands = ?15, min_claude_version = ?16, output_schema = ?17 WHERE id = ?18",
+        params![
+            revision.name,
+            revision.icon,
+            revision.system_prompt,
+            revision.default_task,
+            revision.model,
+            revision.enable_file_read,
+            revision.enable_file_write,
+            revision.enable_network,
+            revision.hooks,
+            serialize_task_variables(&revision.task_variables),
+            revision.budget.max_duration_secs,
+            revision.budget.max_tokens,
+            revision.budget.max_cost_usd,
+            serialize_json_list(&revision.required_mcp_servers),
+            serialize_json_list(&revision.slash_commands),
+            revision.min_claude_version,
+            revision.output_schema,
+            revision.agent_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let agent = load_agent(&conn, revision.agent_id)?;
+    insert_revision(&conn, &agent, Some(revision.id))?;
+    info!(
+        "Restored agent {} to revision {}",
+        revision.agent_id, revision.revision
+    );
+
+    Ok(agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::agents::create_schema;
+
+    fn agent() -> Agent {
+        Agent {
+            id: Some(1),
+            name: "Reviewer".to_string(),
+            icon: "bot".to_string(),
+            system_prompt: "You are a reviewer.".to_string(),
+            default_task: None,
+            model: "sonnet".to_string(),
+            enable_file_read: true,
+            enable_file_write: false,
+            enable_network: false,
+            hooks: None,
+            task_variables: Vec::new(),
+            budget: Default::default(),
+            source: None,
+            required_mcp_servers: Vec::new(),
+            slash_commands: Vec::new(),
+            min_claude_version: None,
+            output_schema: None,
+            folder: None,
+            tags: Vec::new(),
+            favorite: false,
+            last_used_at: None,
+            run_count: 0,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_queued_run_keeps_revision_permissions() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+
+        // Queueing a run pins the agent's current revision
+        let mut queued = agent();
+        queued.budget.max_tokens = Some(10_000);
+        queued.output_schema = Some(r#"{"type":"object"}"#.to_string());
+        let revision_id = ensure_current_revision(&conn, &queued).unwrap();
+
+        // The agent is edited before the run starts
+        let mut edited = queued.clone();
+        edited.model = "opus".to_string();
+        edited.enable_file_write = true;
+        edited.enable_network = true;
+        edited.system_prompt = "You are a refactorer.".to_string();
+        edited.budget.max_tokens = None;
+        edited.output_schema = None;
+        edited.slash_commands = vec![BundledSlashCommand {
+            name: "fix".to_string(),
+            namespace: None,
+            description: None,
+            allowed_tools: Vec::new(),
+            content: "Fix it".to_string(),
+        }];
+        assert_ne!(ensure_current_revision(&conn, &edited).unwrap(), revision_id);
+
+        // Starting the run applies the pinned revision on top of the edited agent
+        let mut started = edited.clone();
+        load_revision(&conn, revision_id).unwrap().apply_to(&mut started);
+        assert_eq!(started.model, "sonnet");
+        assert!(started.enable_file_read);
+        assert!(!started.enable_file_write);
+        assert!(!started.enable_network);
+        assert_eq!(started.system_prompt, "You are a reviewer.");
+        assert_eq!(started.budget.max_tokens, Some(10_000));
+        assert_eq!(started.output_schema, queued.output_schema);
+        assert!(started.slash_commands.is_empty());
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("You are a reviewer.\nBe terse.\nUse tables.", "You are a reviewer.\nBe thorough.\nUse tables.\nCite files.");
+        let kinds: Vec<(&str, &str)> = diff
+            .iter()
+            .map(|l| (l.kind.as_str(), l.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("equal", "You are a reviewer."),
+                ("removed", "Be terse."),
+                ("added", "Be thorough."),
+                ("equal", "Use tables."),
+                ("added", "Cite files."),
+            ]
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use log::{error, info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agent_revisions::{diff_lines, ensure_current_revision, DiffLine};
+use crate::commands::agents::{load_agent, parse_agent_export, Agent, AgentData, AgentDb};
+use crate::commands::registries::{
+    download_agent_file, load_registry, registry_listing, sha256_hex, RegistryAgentFile,
+};
+
+/// How often imported agents are checked for updates
+const UPDATE_CHECK_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+/// Delay before the first check so it doesn't compete with app startup
+const UPDATE_CHECK_STARTUP_DELAY_SECS: u64 = 60;
+
+/// Result of checking an imported agent against its source
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentUpdateStatus {
+    pub agent_id: i64,
+    pub agent_name: String,
+    pub current_sha: Option<String>,
+    pub latest_sha: Option<String>,
+    pub update_available: bool,
+    /// The agent was edited since it was imported or last updated
+    pub locally_modified: bool,
+    pub error: Option<String>,
+}
+
+/// A field that can be updated from the source, in three versions
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentFieldUpdate {
+    pub field: String,
+    /// Value as last imported from the source
+    pub base: Option<String>,
+    pub local: Option<String>,
+    pub remote: Option<String>,
+    pub locally_modified: bool,
+    pub remote_changed: bool,
+    /// Both sides changed the field differently
+    pub conflict: bool,
+}
+
+/// Differences between an imported agent and the latest version at its source
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentUpdateDiff {
+    pub agent_id: i64,
+    pub current_sha: Option<String>,
+    pub latest_sha: String,
+    pub fields: Vec<AgentFieldUpdate>,
+    /// Line diff of the local system prompt against the remote one
+    pub prompt_diff: Vec<DiffLine>,
+}
+
+/// How to resolve fields changed both locally and at the source
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateConflictResolution {
+    KeepLocal,
+    TakeRemote,
+}
+
+/// The updatable fields of exported agent data, in display order
+fn update_fields(data: &AgentData) -> [(&'static str, Option<String>); 3] {
+    [
+        ("system_prompt", Some(data.system_prompt.clone())),
+        ("model", Some(data.model.clone())),
+        ("hooks", data.hooks.clone()),
+    ]
+}
+
+fn agent_fields(agent: &Agent) -> [(&'static str, Option<String>); 3] {
+    [
+        ("system_prompt", Some(agent.system_prompt.clone())),
+        ("model", Some(agent.model.clone())),
+        ("hooks", agent.hooks.clone()),
+    ]
+}
+
+/// Three-way comparison of the updatable fields. Without a base, as for agents imported
+/// before snapshots were kept, every field that differs from the source is a conflict.
+fn compare_fields(base: Option<&AgentData>, local: &Agent, remote: &AgentData) -> Vec<AgentFieldUpdate> {
+    let bases = base.map(update_fields);
+    agent_fields(local)
+        .into_iter()
+        .zip(update_fields(remote))
+        .enumerate()
+        .map(|(index, ((field, local), (_, remote)))| {
+            let base = bases.as_ref().map(|bases| bases[index].1.clone());
+            let (locally_modified, remote_changed) = match &base {
+                Some(base) => (local != *base, remote != *base),
+                None => (local != remote, local != remote),
+            };
+            let base = base.flatten();
+            AgentFieldUpdate {
+                field: field.to_string(),
+                conflict: locally_modified && remote_changed && local != remote,
+                base,
+                local,
+                remote,
+                locally_modified,
+                remote_changed,
+            }
+        })
+        .collect()
+}
+
+/// Source snapshot the agent was imported or last updated from
+fn load_snapshot(conn: &Connection, agent_id: i64) -> Result<Option<AgentData>, String> {
+    let snapshot: Option<String> = conn
+        .query_row(
+            "SELECT source_snapshot FROM agents WHERE id = ?1",
+            params![agent_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    Ok(snapshot.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+/// Look up an imported agent's entry in its registry and return it with its current hash
+async fn latest_source_file(
+    db: &State<'_, AgentDb>,
+    agent: &Agent,
+) -> Result<(RegistryAgentFile, String), String> {
+    let source = agent.source.as_ref().ok_or("Agent was not imported from a registry")?;
+    let registry_id = source.registry_id.ok_or("Agent has no source registry")?;
+    let path = source.path.clone().ok_or("Agent has no source path")?;
+
+    let registry = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_registry(&conn, registry_id).map_err(|_| "The source registry was deleted".to_string())?
+    };
+    let file = registry_listing(db, registry)
+        .await?
+        .agents
+        .into_iter()
+        .find(|a| a.path == path)
+        .ok_or_else(|| format!("'{}' is no longer in its registry", path))?;
+
+    let sha = match &file.sha {
+        Some(sha) => sha.clone(),
+        None => sha256_hex(&download_agent_file(db, &file).await?),
+    };
+    Ok((file, sha))
+}
+
+async fn check_agent(db: &State<'_, AgentDb>, agent: &Agent) -> AgentUpdateStatus {
+    let agent_id = agent.id.unwrap_or_default();
+    let current_sha = agent.source.as_ref().and_then(|s| s.sha.clone());
+
+    let locally_modified = db
+        .0
+        .lock()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| load_snapshot(&conn, agent_id))
+        .ok()
+        .flatten()
+        .map(|base| agent_fields(agent) != update_fields(&base))
+        .unwrap_or(false);
+
+    let mut status = AgentUpdateStatus {
+        agent_id,
+        agent_name: agent.name.clone(),
+        current_sha: current_sha.clone(),
+        latest_sha: None,
+        update_available: false,
+        locally_modified,
+        error: None,
+    };
+
+    match latest_source_file(db, agent).await {
+        Ok((_, latest_sha)) => {
+            status.update_available = current_sha.as_deref() != Some(latest_sha.as_str());
+            if let Ok(conn) = db.0.lock() {
+                let _ = conn.execute(
+                    "UPDATE agents SET latest_source_sha = ?1, source_checked_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                    params![latest_sha, agent_id],
+                );
+            }
+            status.latest_sha = Some(latest_sha);
+        }
+        Err(e) => status.error = Some(e),
+    }
+    status
+}
+
+/// Check every imported agent, or a single one, against its source
+async fn check_agents(
+    db: &State<'_, AgentDb>,
+    agent_id: Option<i64>,
+) -> Result<Vec<AgentUpdateStatus>, String> {
+    let agents: Vec<Agent> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        match agent_id {
+            Some(id) => vec![load_agent(&conn, id)?],
+            None => {
+                let mut stmt = conn
+                    .prepare("SELECT id FROM agents WHERE source_registry_id IS NOT NULL ORDER BY id")
+                    .map_err(|e| e.to_string())?;
+                let ids = stmt
+                    .query_map([], |row| row.get::<_, i64>(0))
+                    .map_err(|e| e.to_string())?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| e.to_string())?;
+                ids.into_iter()
+                    .map(|id| load_agent(&conn, id))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        }
+    };
+
+    let mut statuses = Vec::new();
+    for agent in agents.iter().filter(|a| a.source.is_some()) {
+        statuses.push(check_agent(db, agent).await);
+    }
+    Ok(statuses)
+}
+
+/// Periodically check imported agents for updates and notify the frontend
+pub fn start_update_checker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_secs(UPDATE_CHECK_STARTUP_DELAY_SECS)).await;
+        loop {
+            let db = app.state::<AgentDb>();
+            match check_agents(&db, None).await {
+                Ok(statuses) => {
+                    let available: Vec<AgentUpdateStatus> =
+                        statuses.into_iter().filter(|s| s.update_available).collect();
+                    if !available.is_empty() {
+                        info!("Updates available for {} imported agent(s)", available.len());
+                        let _ = app.emit("agent-updates-available", &available);
+                    }
+                }
+                Err(e) => error!("Failed to check imported agents for updates: {}", e),
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(UPDATE_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// Check imported agents (all, or one) for newer versions at their source
+#[tauri::command]
+pub async fn check_agent_updates(
+    db: State<'_, AgentDb>,
+    agent_id: Option<i64>,
+) -> Result<Vec<AgentUpdateStatus>, String> {
+    check_agents(&db, agent_id).await
+}
+
+/// Fetch the latest version of an imported agent and compare it with the local one
+async fn fetch_update(
+    db: &State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<(Agent, AgentData, AgentUpdateDiff), String> {
+    let (agent, base) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (load_agent(&conn, agent_id)?, load_snapshot(&conn, agent_id)?)
+    };
+
+    let (file, _) = latest_source_file(db, &agent).await?;
+    let content = download_agent_file(db, &file).await?;
+    let remote = parse_agent_export(&content)?.agent;
+    let latest_sha = file.sha.clone().unwrap_or_else(|| sha256_hex(&content));
+
+    let diff = AgentUpdateDiff {
+        agent_id,
+        current_sha: agent.source.as_ref().and_then(|s| s.sha.clone()),
+        latest_sha,
+        fields: compare_fields(base.as_ref(), &agent, &remote),
+        prompt_diff: diff_lines(&agent.system_prompt, &remote.system_prompt),
+    };
+    Ok((agent, remote, diff))
+}
+
+/// Show what updating an imported agent would change
+#[tauri::command]
+pub async fn get_agent_update_diff(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<AgentUpdateDiff, String> {
+    fetch_update(&db, agent_id).await.map(|(_, _, diff)| diff)
+}
+
+/// Apply the latest version of an imported agent. Fields edited locally but unchanged
+/// at the source are preserved; fields changed on both sides are conflicts that fail
+/// the update unless a `resolution` is given.
+#[tauri::command]
+pub async fn apply_agent_update(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    resolution: Option<UpdateConflictResolution>,
+) -> Result<Agent, String> {
+    let (agent, remote, diff) = fetch_update(&db, agent_id).await?;
+
+    let conflicts: Vec<&str> = diff
+        .fields
+        .iter()
+        .filter(|f| f.conflict)
+        .map(|f| f.field.as_str())
+        .collect();
+    if !conflicts.is_empty() && resolution.is_none() {
+        return Err(format!(
+            "Local edits conflict with the update in: {}",
+            conflicts.join(", ")
+        ));
+    }
+
+    let take = |field: &AgentFieldUpdate| {
+        if field.conflict {
+            resolution == Some(UpdateConflictResolution::TakeRemote)
+        } else {
+            field.remote_changed && !field.locally_modified
+        }
+    };
+    let value = |name: &str| -> Option<Option<String>> {
+        diff.fields
+            .iter()
+            .find(|f| f.field == name && take(f))
+            .map(|f| f.remote.clone())
+    };
+
+    let system_prompt = value("system_prompt").flatten().unwrap_or(agent.system_prompt.clone());
+    let model = value("model").flatten().unwrap_or(agent.model.clone());
+    let hooks = value("hooks").unwrap_or(agent.hooks.clone());
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    ensure_current_revision(&conn, &agent)?;
+    conn.execute(
+        "UPDATE agents SET system_prompt = ?1, model = ?2, hooks = ?3, source_sha = ?4, latest_source_sha = ?4, source_snapshot = ?5 WHERE id = ?6",
+        params![
+            system_prompt,
+            model,
+            hooks,
+            diff.latest_sha,
+            serde_json::to_string(&remote).map_err(|e| e.to_string())?,
+            agent_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let updated = load_agent(&conn, agent_id)?;
+    ensure_current_revision(&conn, &updated)?;
+    if !conflicts.is_empty() {
+        warn!(
+            "Resolved update conflicts of agent {} in {} with {:?}",
+            agent_id,
+            conflicts.join(", "),
+            resolution
+        );
+    }
+    info!("Updated agent {} to {}", agent_id, diff.latest_sha);
+
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(system_prompt: &str, model: &str, hooks: Option<&str>) -> AgentData {
+        AgentData {
+            name: "Reviewer".to_string(),
+            icon: "bot".to_string(),
+            system_prompt: system_prompt.to_string(),
+            default_task: None,
+            model: model.to_string(),
+            hooks: hooks.map(String::from),
+            task_variables: Vec::new(),
+            permissions: None,
+            required_mcp_servers: Vec::new(),
+            slash_commands: Vec::new(),
+            min_claude_version: None,
+            env: Vec::new(),
+            output_schema: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn agent(system_prompt: &str, model: &str, hooks: Option<&str>) -> Agent {
+        Agent {
+            id: Some(1),
+            name: "Reviewer".to_string(),
+            icon: "bot".to_string(),
+            system_prompt: system_prompt.to_string(),
+            default_task: None,
+            model: model.to_string(),
+            enable_file_read: true,
+            enable_file_write: true,
+            enable_network: false,
+            hooks: hooks.map(String::from),
+            task_variables: Vec::new(),
+            budget: Default::default(),
+            source: None,
+            required_mcp_servers: Vec::new(),
+            slash_commands: Vec::new(),
+            min_claude_version: None,
+            output_schema: None,
+            folder: None,
+            tags: Vec::new(),
+            favorite: false,
+            last_used_at: None,
+            run_count: 0,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_compare_fields() {
+        let base = data("Review code.", "sonnet", None);
+        let local = agent("Review code carefully.", "opus", None);
+        let remote = data("Review code and tests.", "sonnet", Some("{}"));
+
+        let fields = compare_fields(Some(&base), &local, &remote);
+        let flags: Vec<(&str, bool, bool, bool)> = fields
+            .iter()
+            .map(|f| (f.field.as_str(), f.locally_modified, f.remote_changed, f.conflict))
+            .collect();
+
+        assert_eq!(
+            flags,
+            vec![
+                ("system_prompt", true, true, true),
+                ("model", true, false, false),
+                ("hooks", false, true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_change_on_both_sides_is_not_a_conflict() {
+        let base = data("Review code.", "sonnet", None);
+        let local = agent("Review code.", "opus", None);
+        let remote = data("Review code.", "opus", None);
+
+        assert!(compare_fields(Some(&base), &local, &remote).iter().all(|f| !f.conflict));
+    }
+
+    #[test]
+    fn test_without_snapshot_differences_are_conflicts() {
+        let local = agent("Review code.", "sonnet", None);
+        let remote = data("Review code and tests.", "sonnet", None);
+
+        let fields = compare_fields(None, &local, &remote);
+        let conflicts: Vec<(&str, bool)> = fields
+            .iter()
+            .map(|f| (f.field.as_str(), f.conflict))
+            .collect();
+        assert_eq!(
+            conflicts,
+            vec![("system_prompt", true), ("model", false), ("hooks", false)]
+        );
+    }
+}
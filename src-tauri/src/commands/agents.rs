@@ -2,7 +2,6 @@ use anyhow::Result;
 use chrono;
 use dirs;
 use log::{debug, error, info, warn};
-use reqwest;
 use rusqlite::{params, Connection, Result as SqliteResult};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -15,6 +14,25 @@ use tauri_plugin_shell::process::CommandEvent;
 use tokio::io::{AsyncBufReadExt, BufReader as TokioBufReader};
 use tokio::process::Command;
 
+use crate::commands::agent_export::{
+    self, parse_json_list, serialize_json_list, AgentPermissions, BundledSlashCommand,
+    RequiredMcpServer, AGENT_EXPORT_VERSION,
+};
+use crate::commands::agent_env::{self, AgentEnv, ExportedEnvVar};
+use crate::commands::agent_library::{self, AgentSort};
+use crate::commands::agent_revisions::ensure_current_revision;
+use crate::commands::registries::RegistrySource;
+use crate::commands::run_annotations::RunAnnotation;
+use crate::commands::run_changes::{record_file_changes, RunFileChange};
+use crate::commands::run_results::{record_run_result, RunResult};
+use crate::commands::task_templates::{self, TaskVariable};
+use crate::commands::notifications;
+use crate::commands::webhooks;
+use crate::commands::worktrees::RunWorktree;
+use crate::process::budget::{self, BudgetTracker, RunBudget};
+use crate::process::usage_limit::{self, UsageLimitTracker};
+use crate::process::watchdog::{self, load_process_timeouts, ActivityMonitor};
+
 /// Finds the full path to the claude binary
 /// This is necessary because macOS apps have a limited PATH environment
 fn find_claude_binary(app_handle: &AppHandle) -> Result<String, String> {
@@ -34,10 +52,124 @@ pub struct Agent {
     pub enable_file_write: bool,
     pub enable_network: bool,
     pub hooks: Option<String>, // JSON string of hooks configuration
+    #[serde(default)]
+    pub task_variables: Vec<TaskVariable>, // Variables available to the task template
+    #[serde(default)]
+    pub budget: RunBudget, // Default caps for runs of this agent
+    #[serde(default)]
+    pub source: Option<AgentSource>, // Set for agents imported from a registry
+    #[serde(default)]
+    pub required_mcp_servers: Vec<RequiredMcpServer>, // MCP servers the agent expects to be configured
+    #[serde(default)]
+    pub slash_commands: Vec<BundledSlashCommand>, // Installed into the project before each run
+    #[serde(default)]
+    pub min_claude_version: Option<String>, // Oldest Claude Code version the agent works with
+    #[serde(default)]
+    pub output_schema: Option<String>, // JSON schema the final answer is validated against
+    #[serde(default)]
+    pub folder: Option<String>, // Library folder, such as "Reviews/Security"
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub last_used_at: Option<String>, // When a run of the agent was last queued
+    #[serde(default)]
+    pub run_count: i64,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// Where an imported agent came from, pinned to the content it was imported from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AgentSource {
+    pub registry_id: Option<i64>,
+    pub path: Option<String>,
+    pub url: Option<String>,
+    pub sha: Option<String>,
+    pub imported_at: String,
+    pub latest_sha: Option<String>, // SHA seen at the source by the last update check
+    pub checked_at: Option<String>,
+}
+
+/// Columns selected for every `Agent` query, in the order expected by `Agent::from_row`
+pub(crate) const AGENT_COLUMNS: &str = "id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at, task_variables, max_duration_secs, max_tokens, max_cost_usd, source_registry_id, source_path, source_url, source_sha, imported_at, latest_source_sha, source_checked_at, required_mcp_servers, slash_commands, min_claude_version, output_schema, folder, tags, favorite, last_used_at, run_count";
+
+/// Tables `AGENT_COLUMNS` are selected from. Library data lives outside the agents
+/// table so that organising agents doesn't touch their `updated_at`.
+pub(crate) const AGENT_TABLES: &str =
+    "agents LEFT JOIN agent_library ON agent_library.agent_id = agents.id";
+
+impl Agent {
+    /// Build an `Agent` from a row selected with `AGENT_COLUMNS`
+    pub(crate) fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Agent {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            icon: row.get(2)?,
+            system_prompt: row.get(3)?,
+            default_task: row.get(4)?,
+            model: row
+                .get::<_, String>(5)
+                .unwrap_or_else(|_| "sonnet".to_string()),
+            enable_file_read: row.get::<_, bool>(6).unwrap_or(true),
+            enable_file_write: row.get::<_, bool>(7).unwrap_or(true),
+            enable_network: row.get::<_, bool>(8).unwrap_or(false),
+            hooks: row.get(9)?,
+            task_variables: parse_task_variables(row.get::<_, Option<String>>(12)?),
+            budget: RunBudget::from_row(row, 13)?,
+            source: match row.get::<_, Option<String>>(20)? {
+                Some(imported_at) => Some(AgentSource {
+                    registry_id: row.get(16)?,
+                    path: row.get(17)?,
+                    url: row.get(18)?,
+                    sha: row.get(19)?,
+                    imported_at,
+                    latest_sha: row.get(21)?,
+                    checked_at: row.get(22)?,
+                }),
+                None => None,
+            },
+            required_mcp_servers: parse_json_list(row.get::<_, Option<String>>(23)?),
+            slash_commands: parse_json_list(row.get::<_, Option<String>>(24)?),
+            min_claude_version: row.get(25)?,
+            output_schema: row.get(26)?,
+            folder: row.get(27)?,
+            tags: parse_json_list(row.get::<_, Option<String>>(28)?),
+            favorite: row.get::<_, Option<bool>>(29)?.unwrap_or(false),
+            last_used_at: row.get(30)?,
+            run_count: row.get::<_, Option<i64>>(31)?.unwrap_or(0),
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
+        })
+    }
+}
+
+/// Parse task variable definitions stored as a JSON array
+pub(crate) fn parse_task_variables(json: Option<String>) -> Vec<TaskVariable> {
+    json.and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Serialize task variable definitions for storage; no variables are stored as NULL
+pub(crate) fn serialize_task_variables(variables: &[TaskVariable]) -> Option<String> {
+    if variables.is_empty() {
+        None
+    } else {
+        serde_json::to_string(variables).ok()
+    }
+}
+
+/// Load a single agent by ID
+pub(crate) fn load_agent(conn: &Connection, id: i64) -> Result<Agent, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM {} WHERE id = ?1", AGENT_COLUMNS, AGENT_TABLES),
+        params![id],
+        Agent::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
 /// Represents an agent execution run
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AgentRun {
@@ -49,11 +181,88 @@ pub struct AgentRun {
     pub model: String,
     pub project_path: String,
     pub session_id: String, // UUID session ID from Claude Code
-    pub status: String,     // 'pending', 'running', 'completed', 'failed', 'cancelled'
+    pub status: String,     // 'queued', 'pending', 'running', 'completed', 'failed', 'cancelled', 'budget_exceeded', 'timed_out', 'rate_limited'
     pub pid: Option<u32>,
     pub process_started_at: Option<String>,
     pub created_at: String,
     pub completed_at: Option<String>,
+    pub output_log_path: Option<String>, // zstd-compressed copy of the raw stream-json output
+    pub priority: i64, // Higher values are dispatched first when the queue runs in priority order
+    pub schedule_id: Option<i64>, // Schedule that triggered this run, if any
+    pub pipeline_run_id: Option<i64>, // Pipeline run this run is a step of, if any
+    pub revision_id: Option<i64>, // Agent revision the run was executed with
+    pub task_template: Option<String>, // Template the task was rendered from, if any
+    pub budget: RunBudget, // Caps in effect for this run
+    pub status_reason: Option<String>, // Why the run was stopped, e.g. which budget was exceeded
+    pub worktree: Option<RunWorktree>, // Set when the run executes in an isolated git worktree
+    pub rerun_of: Option<i64>, // Run this one repeats, if it was started as a rerun
+    pub batch_id: Option<i64>, // Multi-project batch the run belongs to, if any
+    pub result: Option<RunResult>, // Final outcome, recorded when the run finishes
+    pub annotation: Option<RunAnnotation>, // Rating, notes and tags added by the user
+    pub checkpoint_id: Option<String>, // Snapshot of the project taken before the run started
+    pub file_changes: Option<Vec<RunFileChange>>, // Files changed relative to the snapshot, once finished
+    pub changes_restored_at: Option<String>, // When the run's changes were reverted, if they were
+    pub resume_at: Option<String>, // Set once a usage limit stopped the run; it then resumes its session
+}
+
+/// Columns selected for every `AgentRun` query, in the order expected by `AgentRun::from_row`
+pub(crate) const AGENT_RUN_COLUMNS: &str = "id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at, output_log_path, priority, schedule_id, pipeline_run_id, revision_id, task_template, max_duration_secs, max_tokens, max_cost_usd, status_reason, worktree_status, worktree_path, worktree_branch, worktree_base_commit, rerun_of, batch_id, result_is_error, result_text, result_num_turns, result_cost_usd, result_duration_ms, result_files, result_output_valid, result_output_errors, annotated_at, annotation_rating, annotation_notes, annotation_tags, checkpoint_id, file_changes, changes_restored_at, resume_at";
+
+/// Statuses of runs that have finished and will not start again
+pub(crate) const FINISHED_STATUSES: &[&str] = &["completed", "failed", "cancelled", "budget_exceeded", "timed_out"];
+
+impl AgentRun {
+    /// Build an `AgentRun` from a row selected with `AGENT_RUN_COLUMNS`
+    pub(crate) fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(AgentRun {
+            id: Some(row.get(0)?),
+            agent_id: row.get(1)?,
+            agent_name: row.get(2)?,
+            agent_icon: row.get(3)?,
+            task: row.get(4)?,
+            model: row.get(5)?,
+            project_path: row.get(6)?,
+            session_id: row.get(7)?,
+            status: row
+                .get::<_, String>(8)
+                .unwrap_or_else(|_| "pending".to_string()),
+            pid: row
+                .get::<_, Option<i64>>(9)
+                .ok()
+                .flatten()
+                .map(|p| p as u32),
+            process_started_at: row.get(10)?,
+            created_at: row.get(11)?,
+            completed_at: row.get(12)?,
+            output_log_path: row.get(13)?,
+            priority: row.get::<_, Option<i64>>(14)?.unwrap_or(0),
+            schedule_id: row.get(15)?,
+            pipeline_run_id: row.get(16)?,
+            revision_id: row.get(17)?,
+            task_template: row.get(18)?,
+            budget: RunBudget::from_row(row, 19)?,
+            status_reason: row.get(22)?,
+            worktree: RunWorktree::from_row(row, 23)?,
+            rerun_of: row.get(27)?,
+            batch_id: row.get(28)?,
+            result: RunResult::from_row(row, 29)?,
+            annotation: RunAnnotation::from_row(row, 37)?,
+            checkpoint_id: row.get(41)?,
+            file_changes: row
+                .get::<_, Option<String>>(42)?
+                .and_then(|json| serde_json::from_str(&json).ok()),
+            changes_restored_at: row.get(43)?,
+            resume_at: row.get(44)?,
+        })
+    }
+
+    /// Directory the agent process runs in: its worktree if isolated, the project otherwise
+    pub fn execution_path(&self) -> &str {
+        self.worktree
+            .as_ref()
+            .and_then(|w| w.path.as_deref())
+            .unwrap_or(&self.project_path)
+    }
 }
 
 /// Represents runtime metrics calculated from JSONL
@@ -83,7 +292,7 @@ pub struct AgentExport {
 }
 
 /// Agent data within export
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AgentData {
     pub name: String,
     pub icon: String,
@@ -91,6 +300,23 @@ pub struct AgentData {
     pub default_task: Option<String>,
     pub model: String,
     pub hooks: Option<String>,
+    #[serde(default)]
+    pub task_variables: Vec<TaskVariable>,
+    // Added in version 2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<AgentPermissions>,
+    #[serde(default)]
+    pub required_mcp_servers: Vec<RequiredMcpServer>,
+    #[serde(default)]
+    pub slash_commands: Vec<BundledSlashCommand>,
+    #[serde(default)]
+    pub min_claude_version: Option<String>,
+    #[serde(default)]
+    pub env: Vec<ExportedEnvVar>,
+    #[serde(default)]
+    pub output_schema: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Database connection state
@@ -194,7 +420,7 @@ pub async fn read_session_jsonl(session_id: &str, project_path: &str) -> Result<
 
 /// Get agent run with real-time metrics
 pub async fn get_agent_run_with_metrics(run: AgentRun) -> AgentRunWithMetrics {
-    match read_session_jsonl(&run.session_id, &run.project_path).await {
+    match read_session_jsonl(&run.session_id, run.execution_path()).await {
         Ok(jsonl_content) => {
             let metrics = AgentRunMetrics::from_jsonl(&jsonl_content);
             AgentRunWithMetrics {
@@ -224,7 +450,12 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
 
     let db_path = app_dir.join("agents.db");
     let conn = Connection::open(db_path)?;
+    create_schema(&conn)?;
+    Ok(conn)
+}
 
+/// Create the tables that don't exist yet and migrate the existing ones
+pub(crate) fn create_schema(conn: &Connection) -> SqliteResult<()> {
     // Create agents table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS agents (
@@ -239,7 +470,23 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
             enable_network BOOLEAN NOT NULL DEFAULT 0,
             hooks TEXT,
             created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            task_variables TEXT,
+            max_duration_secs INTEGER,
+            max_tokens INTEGER,
+            max_cost_usd REAL,
+            source_registry_id INTEGER,
+            source_path TEXT,
+            source_url TEXT,
+            source_sha TEXT,
+            imported_at TEXT,
+            source_snapshot TEXT,
+            latest_source_sha TEXT,
+            source_checked_at TEXT,
+            required_mcp_servers TEXT,
+            slash_commands TEXT,
+            min_claude_version TEXT,
+            output_schema TEXT
         )",
         [],
     )?;
@@ -251,6 +498,22 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         [],
     );
     let _ = conn.execute("ALTER TABLE agents ADD COLUMN hooks TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN task_variables TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN max_duration_secs INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN max_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN max_cost_usd REAL", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN source_registry_id INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN source_path TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN source_url TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN source_sha TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN imported_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN source_snapshot TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN latest_source_sha TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN source_checked_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN required_mcp_servers TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN slash_commands TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN min_claude_version TEXT", []);
+    let _ = conn.execute("ALTER TABLE agents ADD COLUMN output_schema TEXT", []);
     let _ = conn.execute(
         "ALTER TABLE agents ADD COLUMN enable_file_read BOOLEAN DEFAULT 1",
         [],
@@ -264,6 +527,26 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         [],
     );
 
+    // Create agent_library table for folders, tags, favourites and usage of agents
+    let library_exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'agent_library'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(true);
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_library (
+            agent_id INTEGER PRIMARY KEY,
+            folder TEXT,
+            tags TEXT,
+            favorite BOOLEAN NOT NULL DEFAULT 0,
+            last_used_at TEXT,
+            run_count INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
     // Create agent_runs table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS agent_runs (
@@ -280,6 +563,39 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
             process_started_at TEXT,
             created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
             completed_at TEXT,
+            output_log_path TEXT,
+            priority INTEGER NOT NULL DEFAULT 0,
+            schedule_id INTEGER,
+            pipeline_run_id INTEGER,
+            revision_id INTEGER,
+            task_template TEXT,
+            max_duration_secs INTEGER,
+            max_tokens INTEGER,
+            max_cost_usd REAL,
+            status_reason TEXT,
+            worktree_status TEXT,
+            worktree_path TEXT,
+            worktree_branch TEXT,
+            worktree_base_commit TEXT,
+            rerun_of INTEGER,
+            batch_id INTEGER,
+            result_is_error BOOLEAN,
+            result_text TEXT,
+            result_num_turns INTEGER,
+            result_cost_usd REAL,
+            result_duration_ms INTEGER,
+            result_files TEXT,
+            result_output_valid BOOLEAN,
+            result_output_errors TEXT,
+            annotated_at TEXT,
+            annotation_rating INTEGER,
+            annotation_notes TEXT,
+            annotation_tags TEXT,
+            checkpoint_id TEXT,
+            file_changes TEXT,
+            changes_restored_at TEXT,
+            resume_at TEXT,
+            prior_duration_secs INTEGER NOT NULL DEFAULT 0,
             FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
         )",
         [],
@@ -296,6 +612,45 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         "ALTER TABLE agent_runs ADD COLUMN process_started_at TEXT",
         [],
     );
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN output_log_path TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE agent_runs ADD COLUMN priority INTEGER DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN schedule_id INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN pipeline_run_id INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN revision_id INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN task_template TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN max_duration_secs INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN max_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN max_cost_usd REAL", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN status_reason TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_status TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_path TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_branch TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN worktree_base_commit TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN rerun_of INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN batch_id INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_is_error BOOLEAN", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_text TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_num_turns INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_cost_usd REAL", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_duration_ms INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_files TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_output_valid BOOLEAN", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN result_output_errors TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN annotated_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN annotation_rating INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN annotation_notes TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN annotation_tags TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN checkpoint_id TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN file_changes TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN changes_restored_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_runs ADD COLUMN resume_at TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE agent_runs ADD COLUMN prior_duration_secs INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
 
     // Drop old columns that are no longer needed (data is now read from JSONL files)
     // Note: SQLite doesn't support DROP COLUMN, so we'll ignore errors for existing columns
@@ -310,6 +665,376 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         [],
     );
 
+    // Create agent_revisions table with immutable snapshots of agent configurations
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            revision INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            icon TEXT NOT NULL,
+            system_prompt TEXT NOT NULL,
+            default_task TEXT,
+            model TEXT NOT NULL,
+            enable_file_read BOOLEAN NOT NULL DEFAULT 1,
+            enable_file_write BOOLEAN NOT NULL DEFAULT 1,
+            enable_network BOOLEAN NOT NULL DEFAULT 0,
+            hooks TEXT,
+            task_variables TEXT,
+            restored_from INTEGER,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            max_duration_secs INTEGER,
+            max_tokens INTEGER,
+            max_cost_usd REAL,
+            required_mcp_servers TEXT,
+            slash_commands TEXT,
+            min_claude_version TEXT,
+            output_schema TEXT,
+            UNIQUE (agent_id, revision),
+            FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN task_variables TEXT", []);
+    let revision_settings_added = conn
+        .execute("ALTER TABLE agent_revisions ADD COLUMN max_duration_secs INTEGER", [])
+        .is_ok();
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN max_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN max_cost_usd REAL", []);
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN required_mcp_servers TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN slash_commands TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN min_claude_version TEXT", []);
+    let _ = conn.execute("ALTER TABLE agent_revisions ADD COLUMN output_schema TEXT", []);
+    if revision_settings_added {
+        // Earlier revisions ran with the agent's current settings, so keep doing that
+        conn.execute(
+            "UPDATE agent_revisions SET (max_duration_secs, max_tokens, max_cost_usd, required_mcp_servers, slash_commands, min_claude_version, output_schema) =
+             (SELECT max_duration_secs, max_tokens, max_cost_usd, required_mcp_servers, slash_commands, min_claude_version, output_schema FROM agents WHERE agents.id = agent_revisions.agent_id)",
+            [],
+        )?;
+    }
+
+    // Create agent_schedules table for cron-triggered runs
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            cron_expression TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            catch_up_policy TEXT NOT NULL DEFAULT 'run_once',
+            last_run_at TEXT,
+            next_run_at TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    // Foreign keys aren't enforced, so drop schedules left behind by deleted agents
+    conn.execute(
+        "DELETE FROM agent_schedules WHERE agent_id NOT IN (SELECT id FROM agents)",
+        [],
+    )?;
+
+    // Create pipeline tables for multi-step agent chains
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipelines (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipeline_steps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            step_key TEXT NOT NULL,
+            agent_id INTEGER NOT NULL,
+            task_template TEXT NOT NULL,
+            model TEXT,
+            depends_on TEXT,
+            on_failure TEXT NOT NULL DEFAULT 'stop',
+            max_retries INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY (pipeline_id) REFERENCES pipelines(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipeline_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_id INTEGER NOT NULL,
+            pipeline_name TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            definition TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            completed_at TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pipeline_run_steps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pipeline_run_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            step_key TEXT NOT NULL,
+            agent_id INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'waiting',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            agent_run_id INTEGER,
+            output TEXT,
+            error TEXT,
+            completed_at TEXT,
+            FOREIGN KEY (pipeline_run_id) REFERENCES pipeline_runs(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Create agent registry tables; new installations start with the official registry
+    let registries_exist: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'agent_registries'",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_registries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            source TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    if !registries_exist {
+        let source = serde_json::to_string(&RegistrySource::default_source())
+            .expect("Failed to serialize default registry");
+        conn.execute(
+            "INSERT INTO agent_registries (name, source) VALUES ('Claudia Community', ?1)",
+            params![source],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_registry_cache (
+            registry_id INTEGER PRIMARY KEY,
+            etag TEXT,
+            agents TEXT NOT NULL,
+            fetched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (registry_id) REFERENCES agent_registries(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Per-agent environment variables; secret values are encrypted
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_env_vars (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT,
+            is_secret BOOLEAN NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(agent_id, key),
+            FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Runs of one agent fanned out across several projects
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_run_batches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            agent_name TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // The same agent and task run under several models side by side
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_comparisons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            agent_name TEXT NOT NULL,
+            task TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_comparison_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            comparison_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            model TEXT NOT NULL,
+            run_id INTEGER NOT NULL,
+            copy_path TEXT,
+            copy_base_commit TEXT,
+            FOREIGN KEY (comparison_id) REFERENCES agent_comparisons(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Eval suites: test cases run against an agent in copies of fixture projects
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS eval_suites (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS eval_cases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            suite_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            fixture_path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT,
+            assertions TEXT NOT NULL DEFAULT '[]',
+            FOREIGN KEY (suite_id) REFERENCES eval_suites(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Reports of eval suite executions, tied to the agent revision that was tested
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS eval_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            suite_id INTEGER NOT NULL,
+            suite_name TEXT NOT NULL,
+            agent_id INTEGER NOT NULL,
+            revision_id INTEGER NOT NULL,
+            model TEXT,
+            status TEXT NOT NULL DEFAULT 'running',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            completed_at TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS eval_run_cases (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            eval_run_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            case_name TEXT NOT NULL,
+            assertions TEXT NOT NULL,
+            workdir TEXT NOT NULL,
+            agent_run_id INTEGER,
+            status TEXT NOT NULL DEFAULT 'running',
+            outcomes TEXT,
+            error TEXT,
+            cost_usd REAL,
+            completed_at TEXT,
+            FOREIGN KEY (eval_run_id) REFERENCES eval_runs(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    // Cases whose evaluation was interrupted by a restart are evaluated again
+    conn.execute(
+        "UPDATE eval_run_cases SET status = 'running' WHERE status = 'evaluating'",
+        [],
+    )?;
+
+    // Links between agents and Claude Code native subagent files
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS native_subagent_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_path TEXT NOT NULL UNIQUE,
+            agent_id INTEGER NOT NULL,
+            scope TEXT NOT NULL,
+            description TEXT,
+            tools TEXT,
+            file_mtime INTEGER,
+            agent_hash TEXT,
+            synced_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    let _ = conn.execute("ALTER TABLE native_subagent_links ADD COLUMN agent_hash TEXT", []);
+
+    // User-configured HTTP endpoints notified of run and session lifecycle events
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            events TEXT NOT NULL DEFAULT '[]',
+            payload_template TEXT,
+            secret TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            response_status INTEGER,
+            response_body TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            last_attempt_at TEXT,
+            FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Interactive sessions waiting for their usage limit to reset
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_resumes (
+            session_id TEXT PRIMARY KEY,
+            project_path TEXT NOT NULL,
+            model TEXT NOT NULL,
+            reset_at TEXT NOT NULL,
+            message TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Count the runs made before agent usage was recorded
+    if !library_exists {
+        conn.execute(
+            "INSERT OR IGNORE INTO agent_library (agent_id, last_used_at, run_count)
+             SELECT agent_id, MAX(created_at), COUNT(*) FROM agent_runs GROUP BY agent_id",
+            [],
+        )?;
+    }
+
     // Create trigger to update the updated_at timestamp
     conn.execute(
         "CREATE TRIGGER IF NOT EXISTS update_agent_timestamp 
@@ -344,37 +1069,62 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         [],
     )?;
 
-    Ok(conn)
+    Ok(())
 }
 
-/// List all agents
+/// List agents, optionally filtered by folder, tag, favourites and text in their name or system prompt
 #[tauri::command]
-pub async fn list_agents(db: State<'_, AgentDb>) -> Result<Vec<Agent>, String> {
+pub async fn list_agents(
+    db: State<'_, AgentDb>,
+    folder: Option<String>,
+    tag: Option<String>,
+    favorites_only: Option<bool>,
+    search: Option<String>,
+    sort: Option<AgentSort>,
+) -> Result<Vec<Agent>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-    let mut stmt = conn
-        .prepare("SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents ORDER BY created_at DESC")
-        .map_err(|e| e.to_string())?;
+    let mut clauses = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    // An empty folder selects the agents outside any folder
+    match folder.map(|f| agent_library::normalize_folder(&f)) {
+        Some(Some(folder)) => {
+            clauses.push("agent_library.folder = ?");
+            values.push(folder.into());
+        }
+        Some(None) => clauses.push("agent_library.folder IS NULL"),
+        None => {}
+    }
+    if let Some(tag) = tag.map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+        clauses.push("EXISTS (SELECT 1 FROM json_each(agent_library.tags) WHERE value = ?)");
+        values.push(tag.into());
+    }
+    if favorites_only.unwrap_or(false) {
+        clauses.push("agent_library.favorite = 1");
+    }
+    if let Some(pattern) = search.as_deref().and_then(agent_library::search_pattern) {
+        clauses.push("(name LIKE ? ESCAPE '\\' OR system_prompt LIKE ? ESCAPE '\\')");
+        values.push(pattern.clone().into());
+        values.push(pattern.into());
+    }
+
+    let filter = if clauses.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", clauses.join(" AND "))
+    };
+    let query = format!(
+        "SELECT {} FROM {}{} ORDER BY {}",
+        AGENT_COLUMNS,
+        AGENT_TABLES,
+        filter,
+        sort.unwrap_or_default().order_clause()
+    );
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
 
     let agents = stmt
-        .query_map([], |row| {
-            Ok(Agent {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                icon: row.get(2)?,
-                system_prompt: row.get(3)?,
-                default_task: row.get(4)?,
-                model: row
-                    .get::<_, String>(5)
-                    .unwrap_or_else(|_| "sonnet".to_string()),
-                enable_file_read: row.get::<_, bool>(6).unwrap_or(true),
-                enable_file_write: row.get::<_, bool>(7).unwrap_or(true),
-                enable_network: row.get::<_, bool>(8).unwrap_or(false),
-                hooks: row.get(9)?,
-                created_at: row.get(10)?,
-                updated_at: row.get(11)?,
-            })
-        })
+        .query_map(rusqlite::params_from_iter(values), Agent::from_row)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -395,44 +1145,27 @@ pub async fn create_agent(
     enable_file_write: Option<bool>,
     enable_network: Option<bool>,
     hooks: Option<String>,
+    task_variables: Option<Vec<TaskVariable>>,
 ) -> Result<Agent, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let model = model.unwrap_or_else(|| "sonnet".to_string());
     let enable_file_read = enable_file_read.unwrap_or(true);
     let enable_file_write = enable_file_write.unwrap_or(true);
     let enable_network = enable_network.unwrap_or(false);
+    let task_variables = task_variables.unwrap_or_default();
+    task_templates::validate_variables(&task_variables)?;
 
     conn.execute(
-        "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        params![name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks],
+        "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, task_variables) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, serialize_task_variables(&task_variables)],
     )
     .map_err(|e| e.to_string())?;
 
     let id = conn.last_insert_rowid();
 
-    // Fetch the created agent
-    let agent = conn
-        .query_row(
-            "SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok(Agent {
-                    id: Some(row.get(0)?),
-                    name: row.get(1)?,
-                    icon: row.get(2)?,
-                    system_prompt: row.get(3)?,
-                    default_task: row.get(4)?,
-                    model: row.get(5)?,
-                    enable_file_read: row.get(6)?,
-                    enable_file_write: row.get(7)?,
-                    enable_network: row.get(8)?,
-                    hooks: row.get(9)?,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                })
-            },
-        )
-        .map_err(|e| e.to_string())?;
+    // Fetch the created agent and record its first revision
+    let agent = load_agent(&conn, id)?;
+    ensure_current_revision(&conn, &agent)?;
 
     Ok(agent)
 }
@@ -451,9 +1184,17 @@ pub async fn update_agent(
     enable_file_write: Option<bool>,
     enable_network: Option<bool>,
     hooks: Option<String>,
+    task_variables: Option<Vec<TaskVariable>>,
 ) -> Result<Agent, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let model = model.unwrap_or_else(|| "sonnet".to_string());
+    if let Some(variables) = &task_variables {
+        task_templates::validate_variables(variables)?;
+    }
+
+    // Preserve the configuration being replaced (agents created before revisions existed have none yet)
+    let previous = load_agent(&conn, id)?;
+    ensure_current_revision(&conn, &previous)?;
 
     // Build dynamic query based on provided parameters
     let mut query =
@@ -484,6 +1225,11 @@ pub async fn update_agent(
         query.push_str(&format!(", enable_network = ?{}", param_count));
         params_vec.push(Box::new(en));
     }
+    if let Some(variables) = task_variables {
+        param_count += 1;
+        query.push_str(&format!(", task_variables = ?{}", param_count));
+        params_vec.push(Box::new(serialize_task_variables(&variables)));
+    }
 
     param_count += 1;
     query.push_str(&format!(" WHERE id = ?{}", param_count));
@@ -495,125 +1241,131 @@ pub async fn update_agent(
     )
     .map_err(|e| e.to_string())?;
 
-    // Fetch the updated agent
-    let agent = conn
-        .query_row(
-            "SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok(Agent {
-                    id: Some(row.get(0)?),
-                    name: row.get(1)?,
-                    icon: row.get(2)?,
-                    system_prompt: row.get(3)?,
-                    default_task: row.get(4)?,
-                    model: row.get(5)?,
-                    enable_file_read: row.get(6)?,
-                    enable_file_write: row.get(7)?,
-                    enable_network: row.get(8)?,
-                    hooks: row.get(9)?,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                })
-            },
-        )
-        .map_err(|e| e.to_string())?;
+    // Fetch the updated agent and record a new revision if anything changed
+    let agent = load_agent(&conn, id)?;
+    ensure_current_revision(&conn, &agent)?;
 
     Ok(agent)
 }
 
-/// Delete an agent
+/// Set the default run budget of an agent; unset limits are unlimited
 #[tauri::command]
-pub async fn delete_agent(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+pub async fn update_agent_budget(
+    db: State<'_, AgentDb>,
+    id: i64,
+    budget: RunBudget,
+) -> Result<Agent, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-    conn.execute("DELETE FROM agents WHERE id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agents SET max_duration_secs = ?1, max_tokens = ?2, max_cost_usd = ?3 WHERE id = ?4",
+        params![budget.max_duration_secs, budget.max_tokens, budget.max_cost_usd, id],
+    )
+    .map_err(|e| e.to_string())?;
 
-    Ok(())
+    load_agent(&conn, id)
 }
 
-/// Get a single agent by ID
+/// Delete an agent along with its revisions, settings and schedules. Agents used by a
+/// pipeline can't be deleted, since the pipeline could no longer run.
 #[tauri::command]
-pub async fn get_agent(db: State<'_, AgentDb>, id: i64) -> Result<Agent, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub async fn delete_agent(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
 
-    let agent = conn
-        .query_row(
-            "SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok(Agent {
-                    id: Some(row.get(0)?),
-                    name: row.get(1)?,
-                    icon: row.get(2)?,
-                    system_prompt: row.get(3)?,
-                    default_task: row.get(4)?,
-                    model: row.get::<_, String>(5).unwrap_or_else(|_| "sonnet".to_string()),
-                    enable_file_read: row.get::<_, bool>(6).unwrap_or(true),
-                    enable_file_write: row.get::<_, bool>(7).unwrap_or(true),
-                    enable_network: row.get::<_, bool>(8).unwrap_or(false),
-                    hooks: row.get(9)?,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                })
-            },
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT p.name FROM pipelines p JOIN pipeline_steps s ON s.pipeline_id = p.id WHERE s.agent_id = ?1 ORDER BY p.name",
         )
         .map_err(|e| e.to_string())?;
+    let pipelines = stmt
+        .query_map(params![id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+    if !pipelines.is_empty() {
+        return Err(format!(
+            "The agent is used by pipelines {}; remove it from them first",
+            pipelines.join(", ")
+        ));
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for sql in [
+        "DELETE FROM agents WHERE id = ?1",
+        "DELETE FROM agent_revisions WHERE agent_id = ?1",
+        "DELETE FROM native_subagent_links WHERE agent_id = ?1",
+        "DELETE FROM agent_env_vars WHERE agent_id = ?1",
+        "DELETE FROM eval_cases WHERE suite_id IN (SELECT id FROM eval_suites WHERE agent_id = ?1)",
+        "DELETE FROM eval_suites WHERE agent_id = ?1",
+        "DELETE FROM agent_library WHERE agent_id = ?1",
+        "DELETE FROM agent_schedules WHERE agent_id = ?1",
+    ] {
+        tx.execute(sql, params![id]).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Get a single agent by ID
+#[tauri::command]
+pub async fn get_agent(db: State<'_, AgentDb>, id: i64) -> Result<Agent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let agent = load_agent(&conn, id)?;
 
     Ok(agent)
 }
 
-/// List agent runs (optionally filtered by agent_id)
+/// List agent runs, optionally filtered by agent, rating range and tag
 #[tauri::command]
 pub async fn list_agent_runs(
     db: State<'_, AgentDb>,
     agent_id: Option<i64>,
+    min_rating: Option<i64>,
+    max_rating: Option<i64>,
+    tag: Option<String>,
 ) -> Result<Vec<AgentRun>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-    let query = if agent_id.is_some() {
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at 
-         FROM agent_runs WHERE agent_id = ?1 ORDER BY created_at DESC"
+    let mut clauses = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    if let Some(aid) = agent_id {
+        clauses.push("agent_id = ?");
+        values.push(aid.into());
+    }
+    if let Some(min_rating) = min_rating {
+        clauses.push("annotation_rating >= ?");
+        values.push(min_rating.into());
+    }
+    if let Some(max_rating) = max_rating {
+        clauses.push("annotation_rating <= ?");
+        values.push(max_rating.into());
+    }
+    if let Some(tag) = tag.map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+        clauses.push("EXISTS (SELECT 1 FROM json_each(agent_runs.annotation_tags) WHERE value = ?)");
+        values.push(tag.into());
+    }
+
+    let query = if clauses.is_empty() {
+        format!(
+            "SELECT {} FROM agent_runs ORDER BY created_at DESC",
+            AGENT_RUN_COLUMNS
+        )
     } else {
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at 
-         FROM agent_runs ORDER BY created_at DESC"
+        format!(
+            "SELECT {} FROM agent_runs WHERE {} ORDER BY created_at DESC",
+            AGENT_RUN_COLUMNS,
+            clauses.join(" AND ")
+        )
     };
 
-    let mut stmt = conn.prepare(query).map_err(|e| e.to_string())?;
-
-    let run_mapper = |row: &rusqlite::Row| -> rusqlite::Result<AgentRun> {
-        Ok(AgentRun {
-            id: Some(row.get(0)?),
-            agent_id: row.get(1)?,
-            agent_name: row.get(2)?,
-            agent_icon: row.get(3)?,
-            task: row.get(4)?,
-            model: row.get(5)?,
-            project_path: row.get(6)?,
-            session_id: row.get(7)?,
-            status: row
-                .get::<_, String>(8)
-                .unwrap_or_else(|_| "pending".to_string()),
-            pid: row
-                .get::<_, Option<i64>>(9)
-                .ok()
-                .flatten()
-                .map(|p| p as u32),
-            process_started_at: row.get(10)?,
-            created_at: row.get(11)?,
-            completed_at: row.get(12)?,
-        })
-    };
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
 
-    let runs = if let Some(aid) = agent_id {
-        stmt.query_map(params![aid], run_mapper)
-    } else {
-        stmt.query_map(params![], run_mapper)
-    }
-    .map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
+    let runs = stmt
+        .query_map(rusqlite::params_from_iter(values), AgentRun::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
 
     Ok(runs)
 }
@@ -625,26 +1377,9 @@ pub async fn get_agent_run(db: State<'_, AgentDb>, id: i64) -> Result<AgentRun,
 
     let run = conn
         .query_row(
-            "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at 
-             FROM agent_runs WHERE id = ?1",
+            &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
             params![id],
-            |row| {
-                Ok(AgentRun {
-                    id: Some(row.get(0)?),
-                    agent_id: row.get(1)?,
-                    agent_name: row.get(2)?,
-                    agent_icon: row.get(3)?,
-                    task: row.get(4)?,
-                    model: row.get(5)?,
-                    project_path: row.get(6)?,
-                    session_id: row.get(7)?,
-                    status: row.get::<_, String>(8).unwrap_or_else(|_| "pending".to_string()),
-                    pid: row.get::<_, Option<i64>>(9).ok().flatten().map(|p| p as u32),
-                    process_started_at: row.get(10)?,
-                    created_at: row.get(11)?,
-                    completed_at: row.get(12)?,
-                })
-            },
+            AgentRun::from_row,
         )
         .map_err(|e| e.to_string())?;
 
@@ -667,7 +1402,7 @@ pub async fn list_agent_runs_with_metrics(
     db: State<'_, AgentDb>,
     agent_id: Option<i64>,
 ) -> Result<Vec<AgentRunWithMetrics>, String> {
-    let runs = list_agent_runs(db, agent_id).await?;
+    let runs = list_agent_runs(db, agent_id, None, None, None).await?;
     let mut runs_with_metrics = Vec::new();
 
     for run in runs {
@@ -678,7 +1413,9 @@ pub async fn list_agent_runs_with_metrics(
     Ok(runs_with_metrics)
 }
 
-/// Execute a CC agent with streaming output
+/// Queue a CC agent run; it is started by the run queue as soon as concurrency limits allow.
+/// `task` may be a template whose `{{variables}}` are filled from `variables` or the agent's defaults.
+/// With `use_worktree` the agent runs in a fresh git worktree instead of the project itself.
 #[tauri::command]
 pub async fn execute_agent(
     app: AppHandle,
@@ -686,14 +1423,158 @@ pub async fn execute_agent(
     project_path: String,
     task: String,
     model: Option<String>,
+    priority: Option<i64>,
+    variables: Option<std::collections::HashMap<String, String>>,
+    budget: Option<RunBudget>,
+    use_worktree: Option<bool>,
     db: State<'_, AgentDb>,
-    registry: State<'_, crate::process::ProcessRegistryState>,
 ) -> Result<i64, String> {
-    info!("Executing agent {} with task: {}", agent_id, task);
+    info!("Queueing agent {} with task: {}", agent_id, task);
 
     // Get the agent from database
     let agent = get_agent(db.clone(), agent_id).await?;
     let execution_model = model.unwrap_or(agent.model.clone());
+    let rendered_task =
+        task_templates::render_task(&task, &agent.task_variables, &variables.unwrap_or_default())?;
+
+    // Create a new run record in the queue
+    let run_id = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        insert_queued_run(
+            &conn,
+            &agent,
+            &QueuedRunParams {
+                project_path,
+                task: rendered_task,
+                task_template: Some(task),
+                model: execution_model,
+                priority: priority.unwrap_or(0),
+                budget: budget.unwrap_or_default(),
+                use_worktree: use_worktree.unwrap_or(false),
+                ..Default::default()
+            },
+        )?
+    };
+
+    let _ = app.emit(&format!("agent-queued:{}", run_id), run_id);
+    crate::commands::queue::schedule_dispatch(&app);
+
+    Ok(run_id)
+}
+
+/// Parameters of a run that is about to be added to the queue
+#[derive(Debug, Default, Clone)]
+pub(crate) struct QueuedRunParams {
+    pub project_path: String,
+    pub task: String,
+    pub task_template: Option<String>,
+    pub model: String,
+    pub priority: i64,
+    pub schedule_id: Option<i64>,
+    pub pipeline_run_id: Option<i64>,
+    /// Per-run caps; unset limits fall back to the agent's budget
+    pub budget: RunBudget,
+    /// Run in an isolated git worktree
+    pub use_worktree: bool,
+    /// Revision to run with instead of the agent's current configuration
+    pub revision_id: Option<i64>,
+    /// Run this one repeats
+    pub rerun_of: Option<i64>,
+    /// Multi-project batch the run is part of
+    pub batch_id: Option<i64>,
+}
+
+/// Insert a new run with status 'queued' and return its ID
+pub(crate) fn insert_queued_run(
+    conn: &Connection,
+    agent: &Agent,
+    run: &QueuedRunParams,
+) -> Result<i64, String> {
+    // Pin the run to the agent configuration at the time it was queued
+    let (revision_id, agent_budget) = match run.revision_id {
+        Some(revision_id) => (
+            revision_id,
+            crate::commands::agent_revisions::load_revision(conn, revision_id)?.budget,
+        ),
+        None => (ensure_current_revision(conn, agent)?, agent.budget),
+    };
+    let budget = run.budget.or(agent_budget);
+    // Plain tasks aren't templates; only keep the template when it has `{{var}}` placeholders
+    let task_template = run
+        .task_template
+        .as_ref()
+        .filter(|template| !task_templates::placeholders(template).is_empty());
+
+    conn.execute(
+        "INSERT INTO agent_runs (agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, priority, schedule_id, pipeline_run_id, revision_id, task_template, max_duration_secs, max_tokens, max_cost_usd, worktree_status, rerun_of, batch_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, '', 'queued', ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            agent.id,
+            agent.name,
+            agent.icon,
+            run.task,
+            run.model,
+            run.project_path,
+            run.priority,
+            run.schedule_id,
+            run.pipeline_run_id,
+            revision_id,
+            task_template,
+            budget.max_duration_secs,
+            budget.max_tokens,
+            budget.max_cost_usd,
+            run.use_worktree.then_some("requested"),
+            run.rerun_of,
+            run.batch_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let run_id = conn.last_insert_rowid();
+    if let Some(agent_id) = agent.id {
+        agent_library::record_use(conn, agent_id)?;
+    }
+    Ok(run_id)
+}
+
+/// Start a run that the queue has dispatched
+pub(crate) async fn start_agent_run(app: &AppHandle, run_id: i64) -> Result<(), String> {
+    let db = app.state::<AgentDb>();
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+
+    let run = get_agent_run(db.clone(), run_id).await?;
+    if run.status != "pending" {
+        return Err(format!("Run {} is {} and will not be started", run_id, run.status));
+    }
+    let mut agent = get_agent(db.clone(), run.agent_id).await?;
+
+    // Run with the revision the run was queued with, even if the agent was edited since
+    if let Some(revision_id) = run.revision_id {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        crate::commands::agent_revisions::load_revision(&conn, revision_id)?.apply_to(&mut agent);
+    }
+    // Isolated runs execute in a worktree created just before the process starts
+    let project_path = if run.worktree.as_ref().is_some_and(|w| w.status == "requested") {
+        crate::commands::worktrees::create_run_worktree(app, &run)?
+    } else {
+        run.execution_path().to_string()
+    };
+    // Snapshot the project so the run's changes can be summarised and reverted;
+    // worktree runs already leave the project untouched, resumed runs keep their first snapshot
+    if run.worktree.is_none() && run.checkpoint_id.is_none() {
+        match crate::commands::run_changes::snapshot_run_project(&project_path, run_id).await {
+            Ok(checkpoint_id) => {
+                let conn = db.0.lock().map_err(|e| e.to_string())?;
+                conn.execute(
+                    "UPDATE agent_runs SET checkpoint_id = ?1 WHERE id = ?2",
+                    params![checkpoint_id, run_id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            Err(e) => warn!("Failed to snapshot project before run {}: {}", run_id, e),
+        }
+    }
+    let task = run.task.clone();
+    let execution_model = run.model.clone();
+    info!("Executing agent {} (run {}) with task: {}", run.agent_id, run_id, task);
     
     // Create .claude/settings.json with agent hooks if it doesn't exist
     if let Some(hooks_json) = &agent.hooks {
@@ -731,31 +1612,51 @@ pub async fn execute_agent(
         }
     }
 
-    // Create a new run record
-    let run_id = {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        conn.execute(
-            "INSERT INTO agent_runs (agent_id, agent_name, agent_icon, task, model, project_path, session_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![agent_id, agent.name, agent.icon, task, execution_model, project_path, ""],
-        )
-        .map_err(|e| e.to_string())?;
-        conn.last_insert_rowid()
-    };
-
     // Find Claude binary
     info!("Running agent '{}'", agent.name);
-    let claude_path = match find_claude_binary(&app) {
+    let claude_path = match find_claude_binary(app) {
         Ok(path) => path,
         Err(e) => {
             error!("Failed to find claude binary: {}", e);
             return Err(e);
         }
     };
+    if let Some(min_version) = &agent.min_claude_version {
+        agent_export::check_min_claude_version(&claude_path, min_version)?;
+    }
+    agent_export::install_bundled_commands(&project_path, &agent.slash_commands)?;
+    let env = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        agent_env::load_agent_env(app, &conn, run.agent_id)?
+    };
+
+    // Runs stopped by a usage limit continue their session instead of starting over
+    let resume_session = run
+        .resume_at
+        .is_some()
+        .then(|| run.session_id.clone())
+        .filter(|session_id| !session_id.is_empty());
+    let previous_output = match (&resume_session, &run.output_log_path) {
+        (Some(_), Some(log_path)) => {
+            crate::process::run_log::read_run_log(std::path::Path::new(log_path)).unwrap_or_default()
+        }
+        _ => String::new(),
+    };
 
     // Build arguments
-    let args = vec![
+    let mut args = Vec::new();
+    if let Some(session_id) = &resume_session {
+        info!("Resuming session {} of run {} after its usage limit reset", session_id, run_id);
+        args.push("--resume".to_string());
+        args.push(session_id.clone());
+    }
+    let prompt = match resume_session {
+        Some(_) => usage_limit::RESUME_PROMPT.to_string(),
+        None => task.clone(),
+    };
+    args.extend([
         "-p".to_string(),
-        task.clone(),
+        prompt,
         "--system-prompt".to_string(),
         agent.system_prompt.clone(),
         "--model".to_string(),
@@ -764,13 +1665,40 @@ pub async fn execute_agent(
         "stream-json".to_string(),
         "--verbose".to_string(),
         "--dangerously-skip-permissions".to_string(),
-    ];
+    ]);
 
     // Execute based on whether we should use sidecar or system binary
     if should_use_sidecar(&claude_path) {
-        spawn_agent_sidecar(app, run_id, agent_id, agent.name.clone(), args, project_path, task, execution_model, db, registry).await
+        spawn_agent_sidecar(app.clone(), run_id, run.agent_id, agent.name.clone(), args, project_path, task, execution_model, run.budget, env, previous_output, db, registry).await?;
     } else {
-        spawn_agent_system(app, run_id, agent_id, agent.name.clone(), claude_path, args, project_path, task, execution_model, db, registry).await
+        spawn_agent_system(app.clone(), run_id, run.agent_id, agent.name.clone(), claude_path, args, project_path, task, execution_model, run.budget, env, previous_output, db, registry).await?;
+    }
+
+    Ok(())
+}
+
+/// Persist the raw stream-json output of a finished run and record the log location
+fn persist_run_output(db_path: &std::path::Path, logs_dir: &std::path::Path, run_id: i64, output: &str) {
+    if output.is_empty() {
+        debug!("No output to persist for agent run {}", run_id);
+        return;
+    }
+
+    match crate::process::run_log::write_run_log(logs_dir, run_id, output) {
+        Ok(log_path) => {
+            info!("💾 Persisted output of agent run {} to {:?}", run_id, log_path);
+            if let Ok(conn) = Connection::open(db_path) {
+                if let Err(e) = conn.execute(
+                    "UPDATE agent_runs SET output_log_path = ?1 WHERE id = ?2",
+                    params![log_path.to_string_lossy().to_string(), run_id],
+                ) {
+                    error!("❌ Failed to record output log path for run {}: {}", run_id, e);
+                }
+            }
+        }
+        Err(e) => {
+            error!("❌ Failed to persist output of agent run {}: {}", run_id, e);
+        }
     }
 }
 
@@ -784,6 +1712,7 @@ fn create_agent_sidecar_command(
     app: &AppHandle,
     args: Vec<String>,
     project_path: &str,
+    env: &AgentEnv,
 ) -> Result<tauri_plugin_shell::process::Command, String> {
     let mut sidecar_cmd = app
         .shell()
@@ -807,6 +1736,12 @@ fn create_agent_sidecar_command(
             sidecar_cmd = sidecar_cmd.env(&key, &value);
         }
     }
+
+    // Agent-specific variables override anything inherited
+    for (key, value) in env.vars() {
+        debug!("Setting agent env var for sidecar: {}", key);
+        sidecar_cmd = sidecar_cmd.env(key, value);
+    }
     
     Ok(sidecar_cmd)
 }
@@ -816,6 +1751,7 @@ fn create_agent_system_command(
     claude_path: &str,
     args: Vec<String>,
     project_path: &str,
+    env: &AgentEnv,
 ) -> Command {
     let mut cmd = create_command_with_env(claude_path);
     
@@ -823,6 +1759,12 @@ fn create_agent_system_command(
     for arg in args {
         cmd.arg(arg);
     }
+
+    // Agent-specific variables override anything inherited
+    for (key, value) in env.vars() {
+        debug!("Setting agent env var: {}", key);
+        cmd.env(key, value);
+    }
     
     cmd.current_dir(project_path)
         .stdin(Stdio::null())
@@ -842,11 +1784,14 @@ async fn spawn_agent_sidecar(
     project_path: String,
     task: String,
     execution_model: String,
+    budget: RunBudget,
+    env: AgentEnv,
+    previous_output: String,
     db: State<'_, AgentDb>,
     registry: State<'_, crate::process::ProcessRegistryState>,
 ) -> Result<i64, String> {
     // Build the sidecar command
-    let sidecar_cmd = create_agent_sidecar_command(&app, args, &project_path)?;
+    let sidecar_cmd = create_agent_sidecar_command(&app, args, &project_path, &env)?;
 
     // Spawn the process
     info!("🚀 Spawning Claude sidecar process...");
@@ -861,14 +1806,15 @@ async fn spawn_agent_sidecar(
     info!("✅ Claude sidecar process spawned successfully with PID: {}", pid);
 
     // Update the database with PID and status
-    {
+    let (timeouts, prior_duration) = {
         let conn = db.0.lock().map_err(|e| e.to_string())?;
         conn.execute(
-            "UPDATE agent_runs SET status = 'running', pid = ?1, process_started_at = ?2 WHERE id = ?3",
+            "UPDATE agent_runs SET status = 'running', pid = ?1, process_started_at = ?2 WHERE id = ?3 AND status = 'pending'",
             params![pid as i64, now, run_id],
         ).map_err(|e| e.to_string())?;
         info!("📝 Updated database with running status and PID");
-    }
+        (load_process_timeouts(&conn), budget::prior_duration(&conn, run_id))
+    };
 
     // Get app directory for database path
     let app_dir = app
@@ -876,11 +1822,14 @@ async fn spawn_agent_sidecar(
         .app_data_dir()
         .expect("Failed to get app data dir");
     let db_path = app_dir.join("agents.db");
+    let logs_dir = crate::process::run_log::run_logs_dir(&app)?;
 
     // Shared state for collecting session ID and live output
     let session_id = std::sync::Arc::new(Mutex::new(String::new()));
-    let live_output = std::sync::Arc::new(Mutex::new(String::new()));
-    let _start_time = std::time::Instant::now();
+    // Resumed runs keep the output of their earlier attempts, which counts towards the budget
+    let mut budget_tracker = BudgetTracker::resumed(budget, &previous_output);
+    let live_output = std::sync::Arc::new(Mutex::new(previous_output));
+    let start_time = std::time::Instant::now();
 
     // Register the process in the registry
     registry
@@ -897,13 +1846,22 @@ async fn spawn_agent_sidecar(
         .map_err(|e| format!("Failed to register sidecar process: {}", e))?;
     info!("📋 Registered sidecar process in registry");
 
+    // Enforce the run's budget: time through a watchdog, tokens and cost from the output
+    let duration_watchdog = budget::spawn_duration_watchdog(app.clone(), registry.0.clone(), db_path.clone(), run_id, budget, prior_duration);
+
+    // Stop the process if it never starts, stalls or runs past the wall-clock limit
+    let activity = ActivityMonitor::new();
+    let (watchdog_app, watchdog_registry, watchdog_db_path) = (app.clone(), registry.0.clone(), db_path.clone());
+    watchdog::spawn_watchdog(timeouts, activity.clone(), move |reason| async move {
+        watchdog::stop_stalled_run(&watchdog_app, &watchdog_registry, &watchdog_db_path, run_id, reason).await;
+    });
+    let usage_limits = UsageLimitTracker::default();
+
     // Handle sidecar events
     let app_handle = app.clone();
     let session_id_clone = session_id.clone();
     let live_output_clone = live_output.clone();
     let registry_clone = registry.0.clone();
-    let first_output = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
-    let first_output_clone = first_output.clone();
     let db_path_for_sidecar = db_path.clone();
 
     tokio::spawn(async move {
@@ -913,16 +1871,16 @@ async fn spawn_agent_sidecar(
         while let Some(event) = receiver.recv().await {
             match event {
                 CommandEvent::Stdout(line_bytes) => {
-                    let line = String::from_utf8_lossy(&line_bytes);
+                    // Scrub secrets before the line reaches logs, the registry or the UI
+                    let line = env.redact(&String::from_utf8_lossy(&line_bytes));
                     line_count += 1;
 
                     // Log first output
-                    if !first_output_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                    if activity.touch() {
                         info!(
                             "🎉 First output received from Claude sidecar process! Line: {}",
                             line
                         );
-                        first_output_clone.store(true, std::sync::atomic::Ordering::Relaxed);
                     }
 
                     if line_count <= 5 {
@@ -939,6 +1897,14 @@ async fn spawn_agent_sidecar(
 
                     // Also store in process registry
                     let _ = registry_clone.append_live_output(run_id, &line);
+                    usage_limits.observe(&line);
+
+                    if let Some(reason) = budget_tracker.observe(&line) {
+                        let (app, registry, db_path) = (app_handle.clone(), registry_clone.clone(), db_path_for_sidecar.clone());
+                        tokio::spawn(async move {
+                            budget::enforce_budget(&app, &registry, &db_path, run_id, &reason).await;
+                        });
+                    }
 
                     // Extract session ID from JSONL output
                     if let Ok(json) = serde_json::from_str::<JsonValue>(&line) {
@@ -977,13 +1943,19 @@ async fn spawn_agent_sidecar(
                     let _ = app_handle.emit("agent-output", &line);
                 }
                 CommandEvent::Stderr(line_bytes) => {
-                    let line = String::from_utf8_lossy(&line_bytes);
+                    let line = env.redact(&String::from_utf8_lossy(&line_bytes));
                     error!("sidecar stderr: {}", line);
+                    activity.touch();
+                    usage_limits.observe_stderr(&line);
                     let _ = app_handle.emit(&format!("agent-error:{}", run_id), &line);
                     let _ = app_handle.emit("agent-error", &line);
                 }
                 CommandEvent::Terminated(payload) => {
                     info!("Claude sidecar process terminated with code: {:?}", payload.code);
+                    activity.finish();
+                    if let Some(duration_watchdog) = &duration_watchdog {
+                        duration_watchdog.abort();
+                    }
                     
                     // Get the session ID
                     let extracted_session_id = if let Ok(sid) = session_id.lock() {
@@ -992,17 +1964,39 @@ async fn spawn_agent_sidecar(
                         String::new()
                     };
 
-                    // Update database with completion
+                    // Persist the full output before the registry drops it
+                    let output = live_output_clone
+                        .lock()
+                        .map(|output| output.clone())
+                        .unwrap_or_default();
+                    persist_run_output(&db_path, &logs_dir, run_id, &output);
+                    record_run_result(&db_path, run_id, &output);
+                    record_file_changes(&db_path, run_id);
+
+                    // Runs stopped by a usage limit wait for the reset instead of finishing
+                    let rate_limit = usage_limits
+                        .detected()
+                        .filter(|limit| usage_limit::mark_rate_limited(&db_path, run_id, limit, start_time.elapsed()));
+
+                    // Update database with completion; runs cancelled in the meantime keep their status
+                    let success = rate_limit.is_none() && payload.code.unwrap_or(1) == 0;
                     if let Ok(conn) = Connection::open(&db_path) {
                         let _ = conn.execute(
-                            "UPDATE agent_runs SET session_id = ?1, status = 'completed', completed_at = CURRENT_TIMESTAMP WHERE id = ?2",
-                            params![extracted_session_id, run_id],
+                            "UPDATE agent_runs SET session_id = COALESCE(NULLIF(?1, ''), session_id), status = CASE WHEN status IN ('pending', 'running') THEN ?2 ELSE status END, completed_at = CASE WHEN status = 'rate_limited' THEN NULL ELSE COALESCE(completed_at, CURRENT_TIMESTAMP) END WHERE id = ?3",
+                            params![extracted_session_id, if success { "completed" } else { "failed" }, run_id],
                         );
                     }
 
-                    let success = payload.code.unwrap_or(1) == 0;
+                    if let Some(limit) = &rate_limit {
+                        let _ = app.emit(&format!("agent-rate-limited:{}", run_id), limit);
+                    }
                     let _ = app.emit("agent-complete", success);
                     let _ = app.emit(&format!("agent-complete:{}", run_id), success);
+                    webhooks::notify_run_finished(&app, run_id);
+                    notifications::notify_run_finished(&app, run_id);
+
+                    // A slot has been freed, start the next queued run
+                    crate::commands::queue::schedule_dispatch(&app);
                     break;
                 }
                 _ => {}
@@ -1026,11 +2020,14 @@ async fn spawn_agent_system(
     project_path: String,
     task: String,
     execution_model: String,
+    budget: RunBudget,
+    env: AgentEnv,
+    previous_output: String,
     db: State<'_, AgentDb>,
     registry: State<'_, crate::process::ProcessRegistryState>,
 ) -> Result<i64, String> {
     // Build the command
-    let mut cmd = create_agent_system_command(&claude_path, args, &project_path);
+    let mut cmd = create_agent_system_command(&claude_path, args, &project_path, &env);
 
     // Spawn the process
     info!("🚀 Spawning Claude system process...");
@@ -1047,14 +2044,15 @@ async fn spawn_agent_system(
     info!("✅ Claude process spawned successfully with PID: {}", pid);
 
     // Update the database with PID and status
-    {
+    let (timeouts, prior_duration) = {
         let conn = db.0.lock().map_err(|e| e.to_string())?;
         conn.execute(
-            "UPDATE agent_runs SET status = 'running', pid = ?1, process_started_at = ?2 WHERE id = ?3",
+            "UPDATE agent_runs SET status = 'running', pid = ?1, process_started_at = ?2 WHERE id = ?3 AND status = 'pending'",
             params![pid as i64, now, run_id],
         ).map_err(|e| e.to_string())?;
         info!("📝 Updated database with running status and PID");
-    }
+        (load_process_timeouts(&conn), budget::prior_duration(&conn, run_id))
+    };
 
     // Get stdout and stderr
     let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
@@ -1071,10 +2069,13 @@ async fn spawn_agent_system(
         .app_data_dir()
         .expect("Failed to get app data dir");
     let db_path = app_dir.join("agents.db");
+    let logs_dir = crate::process::run_log::run_logs_dir(&app)?;
 
     // Shared state for collecting session ID and live output
     let session_id = std::sync::Arc::new(Mutex::new(String::new()));
-    let live_output = std::sync::Arc::new(Mutex::new(String::new()));
+    // Resumed runs keep the output of their earlier attempts, which counts towards the budget
+    let mut budget_tracker = BudgetTracker::resumed(budget, &previous_output);
+    let live_output = std::sync::Arc::new(Mutex::new(previous_output));
     let start_time = std::time::Instant::now();
 
     // Spawn tasks to read stdout and stderr
@@ -1082,9 +2083,12 @@ async fn spawn_agent_system(
     let session_id_clone = session_id.clone();
     let live_output_clone = live_output.clone();
     let registry_clone = registry.0.clone();
-    let first_output = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
-    let first_output_clone = first_output.clone();
+    let activity = ActivityMonitor::new();
+    let stdout_activity = activity.clone();
     let db_path_for_stdout = db_path.clone(); // Clone the db_path for the stdout task
+    let usage_limits = UsageLimitTracker::default();
+    let stdout_usage_limits = usage_limits.clone();
+    let stdout_env = env.clone();
 
     let stdout_task = tokio::spawn(async move {
         info!("📖 Starting to read Claude stdout...");
@@ -1092,15 +2096,16 @@ async fn spawn_agent_system(
         let mut line_count = 0;
 
         while let Ok(Some(line)) = lines.next_line().await {
+            // Scrub secrets before the line reaches logs, the registry or the UI
+            let line = stdout_env.redact(&line);
             line_count += 1;
 
             // Log first output
-            if !first_output_clone.load(std::sync::atomic::Ordering::Relaxed) {
+            if stdout_activity.touch() {
                 info!(
                     "🎉 First output received from Claude process! Line: {}",
                     line
                 );
-                first_output_clone.store(true, std::sync::atomic::Ordering::Relaxed);
             }
 
             if line_count <= 5 {
@@ -1117,6 +2122,15 @@ async fn spawn_agent_system(
 
             // Also store in process registry for cross-session access
             let _ = registry_clone.append_live_output(run_id, &line);
+            stdout_usage_limits.observe(&line);
+
+            // Stop the run as soon as it goes over its token or cost budget
+            if let Some(reason) = budget_tracker.observe(&line) {
+                let (app, registry, db_path) = (app_handle.clone(), registry_clone.clone(), db_path_for_stdout.clone());
+                tokio::spawn(async move {
+                    budget::enforce_budget(&app, &registry, &db_path, run_id, &reason).await;
+                });
+            }
 
             // Extract session ID from JSONL output
             if let Ok(json) = serde_json::from_str::<JsonValue>(&line) {
@@ -1166,6 +2180,8 @@ async fn spawn_agent_system(
     let app_handle_stderr = app.clone();
     let first_error = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
     let first_error_clone = first_error.clone();
+    let stderr_activity = activity.clone();
+    let stderr_usage_limits = usage_limits.clone();
 
     let stderr_task = tokio::spawn(async move {
         info!("📖 Starting to read Claude stderr...");
@@ -1173,7 +2189,10 @@ async fn spawn_agent_system(
         let mut error_count = 0;
 
         while let Ok(Some(line)) = lines.next_line().await {
+            let line = env.redact(&line);
             error_count += 1;
+            stderr_activity.touch();
+            stderr_usage_limits.observe_stderr(&line);
 
             // Log first error
             if !first_error_clone.load(std::sync::atomic::Ordering::Relaxed) {
@@ -1214,77 +2233,28 @@ async fn spawn_agent_system(
         .map_err(|e| format!("Failed to register process: {}", e))?;
     info!("📋 Registered process in registry");
 
+    let duration_watchdog = budget::spawn_duration_watchdog(app.clone(), registry.0.clone(), db_path.clone(), run_id, budget, prior_duration);
+
+    // Stop the process if it never starts, stalls or runs past the wall-clock limit
+    let (watchdog_app, watchdog_registry, watchdog_db_path) = (app.clone(), registry.0.clone(), db_path.clone());
+    watchdog::spawn_watchdog(timeouts, activity.clone(), move |reason| async move {
+        watchdog::stop_stalled_run(&watchdog_app, &watchdog_registry, &watchdog_db_path, run_id, reason).await;
+    });
+
     let db_path_for_monitor = db_path.clone(); // Clone for the monitor task
 
     // Monitor process status and wait for completion
     tokio::spawn(async move {
         info!("🕐 Starting process monitoring...");
 
-        // Wait for first output with timeout
-        for i in 0..300 {
-            // 30 seconds (300 * 100ms)
-            if first_output.load(std::sync::atomic::Ordering::Relaxed) {
-                info!(
-                    "✅ Output detected after {}ms, continuing normal execution",
-                    i * 100
-                );
-                break;
-            }
-
-            if i == 299 {
-                warn!("⏰ TIMEOUT: No output from Claude process after 30 seconds");
-                warn!("💡 This usually means:");
-                warn!("   1. Claude process is waiting for user input");
-                warn!("   3. Claude failed to initialize but didn't report an error");
-                warn!("   4. Network connectivity issues");
-                warn!("   5. Authentication issues (API key not found/invalid)");
-
-                // Process timed out - kill it via PID
-                warn!(
-                    "🔍 Process likely stuck waiting for input, attempting to kill PID: {}",
-                    pid
-                );
-                let kill_result = std::process::Command::new("kill")
-                    .arg("-TERM")
-                    .arg(pid.to_string())
-                    .output();
-
-                match kill_result {
-                    Ok(output) if output.status.success() => {
-                        warn!("🔍 Successfully sent TERM signal to process");
-                    }
-                    Ok(_) => {
-                        warn!("🔍 Failed to kill process with TERM, trying KILL");
-                        let _ = std::process::Command::new("kill")
-                            .arg("-KILL")
-                            .arg(pid.to_string())
-                            .output();
-                    }
-                    Err(e) => {
-                        warn!("🔍 Error killing process: {}", e);
-                    }
-                }
-
-                // Update database
-                if let Ok(conn) = Connection::open(&db_path_for_monitor) {
-                    let _ = conn.execute(
-                        "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
-                        params![run_id],
-                    );
-                }
-
-                let _ = app.emit("agent-complete", false);
-                let _ = app.emit(&format!("agent-complete:{}", run_id), false);
-                return;
-            }
-
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        }
-
         // Wait for reading tasks to complete
         info!("⏳ Waiting for stdout/stderr reading to complete...");
         let _ = stdout_task.await;
         let _ = stderr_task.await;
+        activity.finish();
+        if let Some(duration_watchdog) = &duration_watchdog {
+            duration_watchdog.abort();
+        }
 
         let duration_ms = start_time.elapsed().as_millis() as i64;
         info!("⏱️ Process execution took {} ms", duration_ms);
@@ -1296,14 +2266,28 @@ async fn spawn_agent_system(
             String::new()
         };
 
+        // Persist the full output before the registry drops it
+        let output = live_output
+            .lock()
+            .map(|output| output.clone())
+            .unwrap_or_default();
+        persist_run_output(&db_path_for_monitor, &logs_dir, run_id, &output);
+        record_run_result(&db_path_for_monitor, run_id, &output);
+        record_file_changes(&db_path_for_monitor, run_id);
+
         // Wait for process completion and update status
         info!("✅ Claude process execution monitoring complete");
 
-        // Update the run record with session ID and mark as completed - open a new connection
+        // Runs stopped by a usage limit wait for the reset instead of completing
+        let rate_limit = usage_limits
+            .detected()
+            .filter(|limit| usage_limit::mark_rate_limited(&db_path_for_monitor, run_id, limit, start_time.elapsed()));
+
+        // Update the run record with session ID and mark as completed unless it was cancelled meanwhile - open a new connection
         if let Ok(conn) = Connection::open(&db_path_for_monitor) {
             info!("🔄 Updating database with extracted session ID: {}", extracted_session_id);
             match conn.execute(
-                "UPDATE agent_runs SET session_id = ?1, status = 'completed', completed_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                "UPDATE agent_runs SET session_id = COALESCE(NULLIF(?1, ''), session_id), status = CASE WHEN status IN ('pending', 'running') THEN 'completed' ELSE status END, completed_at = CASE WHEN status = 'rate_limited' THEN NULL ELSE COALESCE(completed_at, CURRENT_TIMESTAMP) END WHERE id = ?2",
                 params![extracted_session_id, run_id],
             ) {
                 Ok(rows_affected) => {
@@ -1323,8 +2307,28 @@ async fn spawn_agent_system(
 
         // Cleanup will be handled by the cleanup_finished_processes function
 
-        let _ = app.emit("agent-complete", true);
-        let _ = app.emit(&format!("agent-complete:{}", run_id), true);
+        // Runs stopped by a budget, timeout or usage limit (or cancelled) keep their status and are reported as unsuccessful
+        let success = Connection::open(&db_path_for_monitor)
+            .and_then(|conn| {
+                conn.query_row(
+                    "SELECT status FROM agent_runs WHERE id = ?1",
+                    params![run_id],
+                    |row| row.get::<_, String>(0),
+                )
+            })
+            .map(|status| status == "completed")
+            .unwrap_or(true);
+
+        if let Some(limit) = &rate_limit {
+            let _ = app.emit(&format!("agent-rate-limited:{}", run_id), limit);
+        }
+        let _ = app.emit("agent-complete", success);
+        let _ = app.emit(&format!("agent-complete:{}", run_id), success);
+        webhooks::notify_run_finished(&app, run_id);
+        notifications::notify_run_finished(&app, run_id);
+
+        // A slot has been freed, start the next queued run
+        crate::commands::queue::schedule_dispatch(&app);
     });
 
     Ok(run_id)
@@ -1339,35 +2343,15 @@ pub async fn list_running_sessions(
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     // First get all running sessions from the database
-    let mut stmt = conn.prepare(
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at 
-         FROM agent_runs WHERE status = 'running' ORDER BY process_started_at DESC"
-    ).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_runs WHERE status = 'running' ORDER BY process_started_at DESC",
+            AGENT_RUN_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
 
     let mut runs = stmt
-        .query_map([], |row| {
-            Ok(AgentRun {
-                id: Some(row.get(0)?),
-                agent_id: row.get(1)?,
-                agent_name: row.get(2)?,
-                agent_icon: row.get(3)?,
-                task: row.get(4)?,
-                model: row.get(5)?,
-                project_path: row.get(6)?,
-                session_id: row.get(7)?,
-                status: row
-                    .get::<_, String>(8)
-                    .unwrap_or_else(|_| "pending".to_string()),
-                pid: row
-                    .get::<_, Option<i64>>(9)
-                    .ok()
-                    .flatten()
-                    .map(|p| p as u32),
-                process_started_at: row.get(10)?,
-                created_at: row.get(11)?,
-                completed_at: row.get(12)?,
-            })
-        })
+        .query_map([], AgentRun::from_row)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -1441,16 +2425,20 @@ pub async fn kill_agent_session(
         }
     }
 
-    // Update the database to mark as cancelled
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let updated = conn.execute(
-        "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
-        params![run_id],
-    ).map_err(|e| e.to_string())?;
+    // Update the database to mark as cancelled (queued runs are simply taken off the queue)
+    let updated = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status IN ('running', 'pending', 'queued', 'rate_limited')",
+            params![run_id],
+        ).map_err(|e| e.to_string())?
+    };
 
     // Emit cancellation event with run_id for proper isolation
     let _ = app.emit(&format!("agent-cancelled:{}", run_id), true);
 
+    crate::commands::queue::schedule_dispatch(&app);
+
     Ok(updated > 0 || killed_via_registry)
 }
 
@@ -1551,20 +2539,17 @@ pub async fn get_live_session_output(
 /// Get real-time output for a running session by reading its JSONL file with live output fallback
 #[tauri::command]
 pub async fn get_session_output(
+    app: AppHandle,
     db: State<'_, AgentDb>,
     registry: State<'_, crate::process::ProcessRegistryState>,
     run_id: i64,
 ) -> Result<String, String> {
     // Get the session information
-    let run = get_agent_run(db, run_id).await?;
+    let run = get_agent_run(db.clone(), run_id).await?;
 
     // If no session ID yet, try to get live output from registry
     if run.session_id.is_empty() {
-        let live_output = registry.0.get_live_output(run_id)?;
-        if !live_output.is_empty() {
-            return Ok(live_output);
-        }
-        return Ok(String::new());
+        return live_or_persisted_output(&registry, &run);
     }
 
     // Get the Claude directory
@@ -1577,8 +2562,8 @@ pub async fn get_session_output(
     
     // Check if projects directory exists
     if !projects_dir.exists() {
-        log::error!("Projects directory not found at: {:?}", projects_dir);
-        return Err("Projects directory not found".to_string());
+        log::warn!("Projects directory not found at: {:?}", projects_dir);
+        return live_or_persisted_output(&registry, &run);
     }
 
     // Search for the session file in all project directories
@@ -1606,31 +2591,58 @@ pub async fn get_session_output(
         log::error!("Failed to read projects directory");
     }
 
+    // Claude writes the session file itself, so secrets are scrubbed when it is read
+    let env = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        agent_env::load_redaction(&app, &conn, run.agent_id)
+    };
+
     // If we found the session file, read it
     if let Some(session_path) = session_file_path {
         match tokio::fs::read_to_string(&session_path).await {
-            Ok(content) => Ok(content),
+            Ok(content) => Ok(env.redact(&content)),
             Err(e) => {
                 log::error!("Failed to read session file {}: {}", session_path.display(), e);
                 // Fallback to live output if file read fails
-                let live_output = registry.0.get_live_output(run_id)?;
-                Ok(live_output)
+                live_or_persisted_output(&registry, &run)
             }
         }
     } else {
         // If session file not found, try the old method as fallback
         log::warn!("Session file not found for {}, trying legacy method", run.session_id);
-        match read_session_jsonl(&run.session_id, &run.project_path).await {
-            Ok(content) => Ok(content),
+        match read_session_jsonl(&run.session_id, run.execution_path()).await {
+            Ok(content) => Ok(env.redact(&content)),
             Err(_) => {
-                // Final fallback to live output
-                let live_output = registry.0.get_live_output(run_id)?;
-                Ok(live_output)
+                // Final fallback to live output or the persisted run log
+                live_or_persisted_output(&registry, &run)
             }
         }
     }
 }
 
+/// Live output from the registry, falling back to the persisted run log once the process is gone
+fn live_or_persisted_output(
+    registry: &State<'_, crate::process::ProcessRegistryState>,
+    run: &AgentRun,
+) -> Result<String, String> {
+    let live_output = match run.id {
+        Some(run_id) => registry.0.get_live_output(run_id)?,
+        None => String::new(),
+    };
+    if !live_output.is_empty() {
+        return Ok(live_output);
+    }
+
+    match &run.output_log_path {
+        Some(log_path) => crate::process::run_log::read_run_log(std::path::Path::new(log_path))
+            .or_else(|e| {
+                log::warn!("Failed to read persisted output for run {:?}: {}", run.id, e);
+                Ok(String::new())
+            }),
+        None => Ok(String::new()),
+    }
+}
+
 /// Stream real-time session output by watching the JSONL file
 #[tauri::command]
 pub async fn stream_session_output(
@@ -1639,15 +2651,19 @@ pub async fn stream_session_output(
     run_id: i64,
 ) -> Result<(), String> {
     // Get the session information
-    let run = get_agent_run(db, run_id).await?;
+    let run = get_agent_run(db.clone(), run_id).await?;
 
     // If no session ID yet, can't stream
     if run.session_id.is_empty() {
         return Err("Session not started yet".to_string());
     }
+    let env = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        agent_env::load_redaction(&app, &conn, run.agent_id)
+    };
 
     let session_id = run.session_id.clone();
-    let project_path = run.project_path.clone();
+    let project_path = run.execution_path().to_string();
 
     // Spawn a task to monitor the file
     tokio::spawn(async move {
@@ -1671,8 +2687,10 @@ pub async fn stream_session_output(
                     if current_size > last_size {
                         // File has grown, read new content
                         if let Ok(content) = tokio::fs::read_to_string(&session_file).await {
-                            let _ = app
-                                .emit("session-output-update", &format!("{}:{}", run_id, content));
+                            let _ = app.emit(
+                                "session-output-update",
+                                &format!("{}:{}", run_id, env.redact(&content)),
+                            );
                         }
                         last_size = current_size;
                     }
@@ -1724,29 +2742,33 @@ pub async fn export_agent(db: State<'_, AgentDb>, id: i64) -> Result<String, Str
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     // Fetch the agent
-    let agent = conn
-        .query_row(
-            "SELECT name, icon, system_prompt, default_task, model, hooks FROM agents WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok(serde_json::json!({
-                    "name": row.get::<_, String>(0)?,
-                    "icon": row.get::<_, String>(1)?,
-                    "system_prompt": row.get::<_, String>(2)?,
-                    "default_task": row.get::<_, Option<String>>(3)?,
-                    "model": row.get::<_, String>(4)?,
-                    "hooks": row.get::<_, Option<String>>(5)?
-                }))
-            },
-        )
-        .map_err(|e| format!("Failed to fetch agent: {}", e))?;
+    let agent = load_agent(&conn, id).map_err(|e| format!("Failed to fetch agent: {}", e))?;
 
     // Create the export wrapper
-    let export_data = serde_json::json!({
-        "version": 1,
-        "exported_at": chrono::Utc::now().to_rfc3339(),
-        "agent": agent
-    });
+    let export_data = AgentExport {
+        version: AGENT_EXPORT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        agent: AgentData {
+            name: agent.name,
+            icon: agent.icon,
+            system_prompt: agent.system_prompt,
+            default_task: agent.default_task,
+            model: agent.model,
+            hooks: agent.hooks,
+            task_variables: agent.task_variables,
+            permissions: Some(AgentPermissions {
+                file_read: agent.enable_file_read,
+                file_write: agent.enable_file_write,
+                network: agent.enable_network,
+            }),
+            required_mcp_servers: agent.required_mcp_servers,
+            slash_commands: agent.slash_commands,
+            min_claude_version: agent.min_claude_version,
+            env: agent_env::export_env_vars(&conn, id)?,
+            output_schema: agent.output_schema,
+            tags: agent.tags,
+        },
+    };
 
     // Convert to pretty JSON string
     serde_json::to_string_pretty(&export_data)
@@ -1941,21 +2963,34 @@ fn create_command_with_env(program: &str) -> Command {
 /// Import an agent from JSON data
 #[tauri::command]
 pub async fn import_agent(db: State<'_, AgentDb>, json_data: String) -> Result<Agent, String> {
-    // Parse the JSON data
-    let export_data: AgentExport =
-        serde_json::from_str(&json_data).map_err(|e| format!("Invalid JSON format: {}", e))?;
+    let export_data = parse_agent_export(&json_data)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    insert_imported_agent(&conn, export_data.agent)
+}
+
+/// Parse and validate an exported agent
+pub(crate) fn parse_agent_export(json_data: &str) -> Result<AgentExport, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json_data).map_err(|e| format!("Invalid JSON format: {}", e))?;
 
     // Validate version
-    if export_data.version != 1 {
+    let version = value.get("version").and_then(|v| v.as_u64());
+    if let Some(version) = version.filter(|v| *v == 0 || *v > AGENT_EXPORT_VERSION as u64) {
         return Err(format!(
-            "Unsupported export version: {}. This version of the app only supports version 1.",
-            export_data.version
+            "Unsupported export version: {}. This version of the app supports versions 1 to {}.",
+            version, AGENT_EXPORT_VERSION
         ));
     }
 
-    let agent_data = export_data.agent;
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    agent_export::validate_export(&value)?;
+    let export_data: AgentExport =
+        serde_json::from_value(value).map_err(|e| format!("Invalid agent file: {}", e))?;
+    task_templates::validate_variables(&export_data.agent.task_variables)?;
+    Ok(export_data)
+}
 
+/// Create an agent from exported data
+pub(crate) fn insert_imported_agent(conn: &Connection, agent_data: AgentData) -> Result<Agent, String> {
     // Check if an agent with the same name already exists
     let existing_count: i64 = conn
         .query_row(
@@ -1972,45 +3007,42 @@ pub async fn import_agent(db: State<'_, AgentDb>, json_data: String) -> Result<A
         agent_data.name
     };
 
+    // Version 1 files carry no permissions; keep the defaults they were always imported with
+    let permissions = agent_data.permissions.unwrap_or(AgentPermissions {
+        file_read: true,
+        file_write: true,
+        network: false,
+    });
+
     // Create the agent
     conn.execute(
-        "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks) VALUES (?1, ?2, ?3, ?4, ?5, 1, 1, 0, ?6)",
+        "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, task_variables, required_mcp_servers, slash_commands, min_claude_version, output_schema) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
         params![
             final_name,
             agent_data.icon,
             agent_data.system_prompt,
             agent_data.default_task,
             agent_data.model,
-            agent_data.hooks
+            permissions.file_read,
+            permissions.file_write,
+            permissions.network,
+            agent_data.hooks,
+            serialize_task_variables(&agent_data.task_variables),
+            serialize_json_list(&agent_data.required_mcp_servers),
+            serialize_json_list(&agent_data.slash_commands),
+            agent_data.min_claude_version,
+            agent_data.output_schema
         ],
     )
     .map_err(|e| format!("Failed to create agent: {}", e))?;
 
     let id = conn.last_insert_rowid();
+    agent_env::import_env_vars(conn, id, &agent_data.env)?;
+    agent_library::set_tags(conn, id, &agent_data.tags)?;
 
     // Fetch the created agent
-    let agent = conn
-        .query_row(
-            "SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok(Agent {
-                    id: Some(row.get(0)?),
-                    name: row.get(1)?,
-                    icon: row.get(2)?,
-                    system_prompt: row.get(3)?,
-                    default_task: row.get(4)?,
-                    model: row.get(5)?,
-                    enable_file_read: row.get(6)?,
-                    enable_file_write: row.get(7)?,
-                    enable_network: row.get(8)?,
-                    hooks: row.get(9)?,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                })
-            },
-        )
-        .map_err(|e| format!("Failed to fetch created agent: {}", e))?;
+    let agent = load_agent(conn, id).map_err(|e| format!("Failed to fetch created agent: {}", e))?;
+    ensure_current_revision(conn, &agent)?;
 
     Ok(agent)
 }
@@ -2029,154 +3061,33 @@ pub async fn import_agent_from_file(
     import_agent(db, json_data).await
 }
 
-// GitHub Agent Import functionality
-
-/// Represents a GitHub agent file from the API
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct GitHubAgentFile {
-    pub name: String,
-    pub path: String,
-    pub download_url: String,
-    pub size: i64,
-    pub sha: String,
-}
-
-/// Represents the GitHub API response for directory contents
-#[derive(Debug, Deserialize)]
-struct GitHubApiResponse {
-    name: String,
-    path: String,
-    sha: String,
-    size: i64,
-    download_url: Option<String>,
-    #[serde(rename = "type")]
-    file_type: String,
-}
-
-/// Fetch list of agents from GitHub repository
-#[tauri::command]
-pub async fn fetch_github_agents() -> Result<Vec<GitHubAgentFile>, String> {
-    info!("Fetching agents from GitHub repository...");
-
-    let client = reqwest::Client::new();
-    let url = "https://api.github.com/repos/getAsterisk/claudia/contents/cc_agents";
-
-    let response = client
-        .get(url)
-        .header("Accept", "application/vnd.github+json")
-        .header("User-Agent", "Claudia-App")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch from GitHub: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("GitHub API error ({}): {}", status, error_text));
-    }
-
-    let api_files: Vec<GitHubApiResponse> = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse GitHub response: {}", e))?;
-
-    // Filter only .claudia.json files
-    let agent_files: Vec<GitHubAgentFile> = api_files
-        .into_iter()
-        .filter(|f| f.name.ends_with(".claudia.json") && f.file_type == "file")
-        .filter_map(|f| {
-            f.download_url.map(|download_url| GitHubAgentFile {
-                name: f.name,
-                path: f.path,
-                download_url,
-                size: f.size,
-                sha: f.sha,
-            })
-        })
-        .collect();
-
-    info!("Found {} agents on GitHub", agent_files.len());
-    Ok(agent_files)
-}
-
-/// Fetch and preview a specific agent from GitHub
-#[tauri::command]
-pub async fn fetch_github_agent_content(download_url: String) -> Result<AgentExport, String> {
-    info!("Fetching agent content from: {}", download_url);
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&download_url)
-        .header("Accept", "application/json")
-        .header("User-Agent", "Claudia-App")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download agent: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to download agent: HTTP {}",
-            response.status()
-        ));
-    }
-
-    let json_text = response
-        .text()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-
-    // Parse and validate the agent data
-    let export_data: AgentExport = serde_json::from_str(&json_text)
-        .map_err(|e| format!("Invalid agent JSON format: {}", e))?;
-
-    // Validate version
-    if export_data.version != 1 {
-        return Err(format!(
-            "Unsupported agent version: {}",
-            export_data.version
-        ));
-    }
-
-    Ok(export_data)
-}
-
-/// Import an agent directly from GitHub
-#[tauri::command]
-pub async fn import_agent_from_github(
-    db: State<'_, AgentDb>,
-    download_url: String,
-) -> Result<Agent, String> {
-    info!("Importing agent from GitHub: {}", download_url);
-
-    // First, fetch the agent content
-    let export_data = fetch_github_agent_content(download_url).await?;
-
-    // Convert to JSON string and use existing import logic
-    let json_data = serde_json::to_string(&export_data)
-        .map_err(|e| format!("Failed to serialize agent data: {}", e))?;
-
-    // Import using existing function
-    import_agent(db, json_data).await
-}
-
 /// Load agent session history from JSONL file
 /// Similar to Claude Code's load_session_history, but searches across all project directories
 #[tauri::command]
 pub async fn load_agent_session_history(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
     session_id: String,
 ) -> Result<Vec<serde_json::Value>, String> {
     log::info!("Loading agent session history for session: {}", session_id);
 
+    // Scrub the secrets of the agent that produced the session
+    let env = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT agent_id FROM agent_runs WHERE session_id = ?1 ORDER BY id DESC LIMIT 1",
+            params![session_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|agent_id| agent_env::load_redaction(&app, &conn, agent_id))
+        .unwrap_or_default()
+    };
+
     let claude_dir = dirs::home_dir()
         .ok_or("Failed to get home directory")?
         .join(".claude");
 
     let projects_dir = claude_dir.join("projects");
-    
-    if !projects_dir.exists() {
-        log::error!("Projects directory not found at: {:?}", projects_dir);
-        return Err("Projects directory not found".to_string());
-    }
 
     // Search for the session file in all project directories
     let mut session_file_path = None;
@@ -2212,7 +3123,7 @@ pub async fn load_agent_session_history(
 
         for line in reader.lines() {
             if let Ok(line) = line {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
+                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&env.redact(&line)) {
                     messages.push(json);
                 }
             }
@@ -2220,6 +3131,27 @@ pub async fn load_agent_session_history(
 
         Ok(messages)
     } else {
-        Err(format!("Session file not found: {}", session_id))
+        // Fall back to the output persisted when the agent run finished
+        let log_path = {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            conn.query_row(
+                "SELECT output_log_path FROM agent_runs WHERE session_id = ?1 AND output_log_path IS NOT NULL ORDER BY id DESC LIMIT 1",
+                params![session_id],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+        };
+
+        match log_path {
+            Some(log_path) => {
+                log::info!("Session file not found, loading persisted run log: {}", log_path);
+                let content = crate::process::run_log::read_run_log(std::path::Path::new(&log_path))?;
+                Ok(content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+                    .collect())
+            }
+            None => Err(format!("Session file not found: {}", session_id)),
+        }
     }
 }
@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agents::{
+    insert_queued_run, load_agent, AgentDb, AgentRun, QueuedRunParams, AGENT_RUN_COLUMNS,
+};
+use crate::commands::task_templates;
+use crate::process::budget::RunBudget;
+
+/// One project's run within a batch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentBatchRun {
+    pub run_id: i64,
+    pub project_path: String,
+    pub status: String,
+    pub status_reason: Option<String>,
+    pub session_id: String,
+    pub cost_usd: Option<f64>,
+    pub completed_at: Option<String>,
+}
+
+/// An agent fanned out across several projects, with the aggregate state of its runs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentBatch {
+    pub id: i64,
+    pub agent_id: i64,
+    pub agent_name: String,
+    pub task: String,
+    pub model: String,
+    pub created_at: String,
+    /// 'queued', 'running', 'completed', 'completed_with_failures', 'failed', 'cancelled'
+    pub status: String,
+    pub total: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub total_cost_usd: f64,
+    pub runs: Vec<AgentBatchRun>,
+}
+
+/// Counts of runs per outcome
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct BatchCounts {
+    queued: usize,
+    running: usize,
+    succeeded: usize,
+    failed: usize,
+    cancelled: usize,
+}
+
+impl BatchCounts {
+    fn add(&mut self, status: &str) {
+        match status {
+            // Rate limited runs wait to be queued again
+            "queued" | "rate_limited" => self.queued += 1,
+            "pending" | "running" => self.running += 1,
+            "completed" => self.succeeded += 1,
+            "cancelled" => self.cancelled += 1,
+            // Failures, budget and timeout kills
+            _ => self.failed += 1,
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        if self.running > 0 {
+            "running"
+        } else if self.queued > 0 {
+            // Some runs may have finished while others still wait for a slot
+            if self.succeeded + self.failed + self.cancelled > 0 {
+                "running"
+            } else {
+                "queued"
+            }
+        } else if self.failed > 0 {
+            if self.succeeded > 0 {
+                "completed_with_failures"
+            } else {
+                "failed"
+            }
+        } else if self.succeeded > 0 {
+            "completed"
+        } else {
+            "cancelled"
+        }
+    }
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Project directories from an explicit list and/or a glob, deduplicated in order
+fn resolve_projects(project_paths: &[String], project_glob: Option<&str>) -> Result<Vec<String>, String> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    for path in project_paths {
+        let path = PathBuf::from(expand_home(path.trim()));
+        if !path.is_dir() {
+            return Err(format!("Project directory not found: {}", path.display()));
+        }
+        candidates.push(path);
+    }
+    if let Some(pattern) = project_glob.map(str::trim).filter(|p| !p.is_empty()) {
+        let matches = glob::glob(&expand_home(pattern))
+            .map_err(|e| format!("Invalid project pattern: {}", e))?;
+        let mut found: Vec<PathBuf> = matches
+            .filter_map(Result::ok)
+            .filter(|path| path.is_dir())
+            .collect();
+        found.sort();
+        candidates.extend(found);
+    }
+
+    let mut seen = HashSet::new();
+    let projects: Vec<String> = candidates
+        .into_iter()
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .filter(|path| seen.insert(path.clone()))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    if projects.is_empty() {
+        return Err("No project directories matched".to_string());
+    }
+    Ok(projects)
+}
+
+fn load_batch_runs(conn: &Connection, batch_id: i64) -> Result<Vec<AgentRun>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_runs WHERE batch_id = ?1 ORDER BY id",
+            AGENT_RUN_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let runs = stmt
+        .query_map(params![batch_id], AgentRun::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(runs)
+}
+
+/// Load a batch with its runs; costs are summed from the stored run results
+fn load_batch(app: &AppHandle, batch_id: i64) -> Result<AgentBatch, String> {
+    let (header, runs) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let header = conn
+            .query_row(
+                "SELECT agent_id, agent_name, task, model, created_at FROM agent_run_batches WHERE id = ?1",
+                params![batch_id],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                    ))
+                },
+            )
+            .map_err(|e| format!("Batch not found: {}", e))?;
+        (header, load_batch_runs(&conn, batch_id)?)
+    };
+
+    let mut counts = BatchCounts::default();
+    let mut total_cost_usd = 0.0;
+    let mut batch_runs = Vec::with_capacity(runs.len());
+    for run in runs {
+        counts.add(&run.status);
+        let cost_usd = run.result.as_ref().and_then(|r| r.total_cost_usd);
+        total_cost_usd += cost_usd.unwrap_or(0.0);
+        batch_runs.push(AgentBatchRun {
+            run_id: run.id.unwrap_or_default(),
+            project_path: run.project_path,
+            status: run.status,
+            status_reason: run.status_reason,
+            session_id: run.session_id,
+            cost_usd,
+            completed_at: run.completed_at,
+        });
+    }
+
+    let (agent_id, agent_name, task, model, created_at) = header;
+    Ok(AgentBatch {
+        id: batch_id,
+        agent_id,
+        agent_name,
+        task,
+        model,
+        created_at,
+        status: counts.status().to_string(),
+        total: batch_runs.len(),
+        queued: counts.queued,
+        running: counts.running,
+        succeeded: counts.succeeded,
+        failed: counts.failed,
+        cancelled: counts.cancelled,
+        total_cost_usd,
+        runs: batch_runs,
+    })
+}
+
+/// Queue one run of an agent per project under a shared batch. Projects come from
+/// `project_paths` and/or `project_glob` (e.g. `~/code/*`); the runs are started by the
+/// queue, so its concurrency limits apply as for any other run.
+#[tauri::command]
+pub async fn execute_agent_batch(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    project_paths: Vec<String>,
+    project_glob: Option<String>,
+    task: String,
+    model: Option<String>,
+    variables: Option<HashMap<String, String>>,
+    budget: Option<RunBudget>,
+    use_worktree: Option<bool>,
+) -> Result<AgentBatch, String> {
+    let projects = resolve_projects(&project_paths, project_glob.as_deref())?;
+
+    let (batch_id, run_ids) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let agent = load_agent(&conn, agent_id)?;
+        let execution_model = model.unwrap_or_else(|| agent.model.clone());
+        let rendered_task = task_templates::render_task(
+            &task,
+            &agent.task_variables,
+            &variables.unwrap_or_default(),
+        )?;
+
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO agent_run_batches (agent_id, agent_name, task, model) VALUES (?1, ?2, ?3, ?4)",
+            params![agent_id, agent.name, rendered_task, execution_model],
+        )
+        .map_err(|e| e.to_string())?;
+        let batch_id = tx.last_insert_rowid();
+
+        let mut run_ids = Vec::with_capacity(projects.len());
+        for project_path in &projects {
+            run_ids.push(insert_queued_run(
+                &tx,
+                &agent,
+                &QueuedRunParams {
+                    project_path: project_path.clone(),
+                    task: rendered_task.clone(),
+                    task_template: Some(task.clone()),
+                    model: execution_model.clone(),
+                    budget: budget.unwrap_or_default(),
+                    use_worktree: use_worktree.unwrap_or(false),
+                    batch_id: Some(batch_id),
+                    ..Default::default()
+                },
+            )?);
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        (batch_id, run_ids)
+    };
+    info!(
+        "Queued batch {} of agent {} across {} projects",
+        batch_id,
+        agent_id,
+        run_ids.len()
+    );
+
+    for run_id in &run_ids {
+        let _ = app.emit(&format!("agent-queued:{}", run_id), run_id);
+    }
+    crate::commands::queue::schedule_dispatch(&app);
+
+    load_batch(&app, batch_id)
+}
+
+/// Get a batch with its aggregate status, cost and per-project runs
+#[tauri::command]
+pub async fn get_agent_batch(app: AppHandle, id: i64) -> Result<AgentBatch, String> {
+    load_batch(&app, id)
+}
+
+/// List batches, newest first, optionally for one agent
+#[tauri::command]
+pub async fn list_agent_batches(
+    app: AppHandle,
+    agent_id: Option<i64>,
+) -> Result<Vec<AgentBatch>, String> {
+    let ids = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT id FROM agent_run_batches WHERE ?1 IS NULL OR agent_id = ?1 ORDER BY id DESC")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map(params![agent_id], |row| row.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    let mut batches = Vec::with_capacity(ids.len());
+    for id in ids {
+        batches.push(load_batch(&app, id)?);
+    }
+    Ok(batches)
+}
+
+/// Cancel the queued runs of a batch and kill the ones already running
+#[tauri::command]
+pub async fn cancel_agent_batch(app: AppHandle, id: i64) -> Result<AgentBatch, String> {
+    let active_runs = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE batch_id = ?1 AND status IN ('queued', 'rate_limited')",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut stmt = conn
+            .prepare("SELECT id FROM agent_runs WHERE batch_id = ?1 AND status IN ('pending', 'running')")
+            .map_err(|e| e.to_string())?;
+        let active_runs = stmt
+            .query_map(params![id], |row| row.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        active_runs
+    };
+
+    for run_id in active_runs {
+        if let Err(e) = crate::commands::agents::kill_agent_session(
+            app.clone(),
+            app.state::<AgentDb>(),
+            app.state::<crate::process::ProcessRegistryState>(),
+            run_id,
+        )
+        .await
+        {
+            warn!("Failed to cancel agent run {} of batch {}: {}", run_id, id, e);
+        }
+    }
+    crate::commands::queue::schedule_dispatch(&app);
+
+    load_batch(&app, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_status() {
+        let mut counts = BatchCounts::default();
+        for status in ["queued", "queued"] {
+            counts.add(status);
+        }
+        assert_eq!(counts.status(), "queued");
+
+        counts.add("completed");
+        assert_eq!(counts.status(), "running");
+
+        let mut counts = BatchCounts::default();
+        for status in ["completed", "failed", "budget_exceeded"] {
+            counts.add(status);
+        }
+        assert_eq!(counts.failed, 2);
+        assert_eq!(counts.status(), "completed_with_failures");
+    }
+
+    #[test]
+    fn test_resolve_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["api", "web"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        let api = dir.path().join("api").to_string_lossy().to_string();
+        let pattern = format!("{}/*", dir.path().display());
+        let projects = resolve_projects(&[api], Some(&pattern)).unwrap();
+
+        assert_eq!(projects.len(), 2);
+        assert!(projects[0].ends_with("api"));
+        assert!(projects[1].ends_with("web"));
+        assert!(resolve_projects(&[], Some(&format!("{}/none-*", dir.path().display()))).is_err());
+    }
+}
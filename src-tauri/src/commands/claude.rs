@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
+use std::future::Future;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::SystemTime;
@@ -10,16 +13,26 @@ use tauri::{AppHandle, Emitter, Manager};
 use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
 
+use crate::commands::agents::AgentDb;
+use crate::commands::notifications::{self, NotificationEvent, NotificationSubject};
+use crate::commands::webhooks::{self, WebhookEvent};
+use crate::process::usage_limit::{self, UsageLimit, UsageLimitTracker};
+use crate::process::watchdog::{self, load_process_timeouts, ActivityMonitor};
+
 
 /// Global state to track current Claude process
 pub struct ClaudeProcessState {
     pub current_process: Arc<Mutex<Option<Child>>>,
+    /// Sessions stopped by a usage limit that resume once it resets; also stored in
+    /// session_resumes so they are rescheduled after a restart
+    pub scheduled_resumes: Arc<Mutex<HashSet<String>>>,
 }
 
 impl Default for ClaudeProcessState {
     fn default() -> Self {
         Self {
             current_process: Arc::new(Mutex::new(None)),
+            scheduled_resumes: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 }
@@ -1066,6 +1079,46 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
     let session_id_holder: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     let run_id_holder: Arc<Mutex<Option<i64>>> = Arc::new(Mutex::new(None));
 
+    // Stop the session if it never starts, stalls or runs past the wall-clock limit
+    let timeouts = app
+        .state::<crate::commands::agents::AgentDb>()
+        .0
+        .lock()
+        .map(|conn| load_process_timeouts(&conn))
+        .unwrap_or_default();
+    let activity = ActivityMonitor::new();
+    let usage_limits = UsageLimitTracker::default();
+    let timed_out = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    {
+        let app_handle = app.clone();
+        let session_id_holder = session_id_holder.clone();
+        let run_id_holder = run_id_holder.clone();
+        let registry = app.state::<crate::process::ProcessRegistryState>().0.clone();
+        let timed_out = timed_out.clone();
+        watchdog::spawn_watchdog(timeouts, activity.clone(), move |reason| async move {
+            let reason = reason.to_string();
+            log::warn!("Stopping Claude process {}: {}", pid, reason);
+            timed_out.store(true, std::sync::atomic::Ordering::Relaxed);
+            if let Some(ref session_id) = *session_id_holder.lock().unwrap() {
+                let _ = app_handle.emit(&format!("claude-timeout:{}", session_id), &reason);
+            }
+            let _ = app_handle.emit("claude-timeout", &reason);
+
+            // Sessions are only registered once Claude has sent its init message
+            let run_id = *run_id_holder.lock().unwrap();
+            match run_id {
+                Some(run_id) => {
+                    if let Err(e) = registry.terminate_process(run_id, watchdog::TERMINATION_GRACE).await {
+                        log::error!("Failed to terminate Claude session {}: {}", run_id, e);
+                    }
+                }
+                None => {
+                    crate::process::ProcessRegistry::terminate_pid(pid, watchdog::TERMINATION_GRACE).await;
+                }
+            }
+        });
+    }
+
     // Store the child process in the global state (for backward compatibility)
     let claude_state = app.state::<ClaudeProcessState>();
     {
@@ -1087,10 +1140,24 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
     let project_path_clone = project_path.clone();
     let prompt_clone = prompt.clone();
     let model_clone = model.clone();
+    let stdout_activity = activity.clone();
+    let stdout_usage_limits = usage_limits.clone();
+    let started = std::time::Instant::now();
+    let session_name = format!(
+        "Claude session in {}",
+        std::path::Path::new(&project_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| project_path.clone())
+    );
+    let session_cost: Arc<Mutex<Option<f64>>> = Arc::new(Mutex::new(None));
+    let stdout_session_cost = session_cost.clone();
     let stdout_task = tokio::spawn(async move {
         let mut lines = stdout_reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
             log::debug!("Claude stdout: {}", line);
+            stdout_activity.touch();
+            stdout_usage_limits.observe(&line);
             
             // Parse the line to check for init message with session ID
             if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
@@ -1118,9 +1185,24 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
                                     log::error!("Failed to register Claude session: {}", e);
                                 }
                             }
+                            webhooks::notify(
+                                &app_handle,
+                                WebhookEvent::SessionStarted,
+                                serde_json::json!({
+                                    "session_id": claude_session_id,
+                                    "project_path": project_path_clone,
+                                    "model": model_clone,
+                                    "prompt": webhooks::excerpt(&prompt_clone),
+                                }),
+                            );
                         }
                     }
                 }
+                if msg["type"] == "result" {
+                    if let Some(cost) = msg["total_cost_usd"].as_f64() {
+                        *stdout_session_cost.lock().unwrap() = Some(cost);
+                    }
+                }
             }
             
             // Store live output in registry if we have a run_id
@@ -1139,10 +1221,14 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
 
     let app_handle_stderr = app.clone();
     let session_id_holder_clone2 = session_id_holder.clone();
+    let stderr_activity = activity.clone();
+    let stderr_usage_limits = usage_limits.clone();
     let stderr_task = tokio::spawn(async move {
         let mut lines = stderr_reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
             log::error!("Claude stderr: {}", line);
+            stderr_activity.touch();
+            stderr_usage_limits.observe_stderr(&line);
             // Emit error lines to the frontend with session isolation if we have session ID
             if let Some(ref session_id) = *session_id_holder_clone2.lock().unwrap() {
                 let _ = app_handle_stderr.emit(&format!("claude-error:{}", session_id), &line);
@@ -1161,13 +1247,17 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
     tokio::spawn(async move {
         let _ = stdout_task.await;
         let _ = stderr_task.await;
+        activity.finish();
 
         // Get the child from the state to wait on it
         let mut current_process = claude_state_wait.lock().await;
+        // Stays None for sessions that were cancelled
+        let mut exit_success = None;
         if let Some(mut child) = current_process.take() {
             match child.wait().await {
                 Ok(status) => {
                     log::info!("Claude process exited with status: {}", status);
+                    exit_success = Some(status.success());
                     // Add a small delay to ensure all messages are processed
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                     if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
@@ -1181,6 +1271,7 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
                 }
                 Err(e) => {
                     log::error!("Failed to wait for Claude process: {}", e);
+                    exit_success = Some(false);
                     // Add a small delay to ensure all messages are processed
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                     if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
@@ -1200,11 +1291,173 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
 
         // Clear the process from state
         *current_process = None;
+        drop(current_process);
+
+        let session_id = session_id_holder_clone3.lock().unwrap().clone();
+        if let (Some(session_id), Some(success)) = (&session_id, exit_success) {
+            let event = if success {
+                WebhookEvent::SessionCompleted
+            } else {
+                WebhookEvent::SessionFailed
+            };
+            webhooks::notify(
+                &app_handle_wait,
+                event,
+                serde_json::json!({
+                    "session_id": session_id,
+                    "project_path": project_path,
+                    "model": model,
+                    "rate_limited": usage_limits.detected().is_some(),
+                }),
+            );
+        }
+        if let Some(success) = exit_success {
+            let event = if timed_out.load(std::sync::atomic::Ordering::Relaxed) {
+                NotificationEvent::TimedOut
+            } else if success {
+                NotificationEvent::Completed
+            } else {
+                NotificationEvent::Failed
+            };
+            let subject = NotificationSubject {
+                name: session_name,
+                duration: Some(started.elapsed()),
+                cost_usd: *session_cost.lock().unwrap(),
+                detail: usage_limits.detected().map(|limit| limit.reason()),
+            };
+            notifications::notify(&app_handle_wait, event, &subject);
+        }
+
+        // Sessions stopped by a usage limit continue once it resets
+        if let (Some(limit), Some(session_id)) = (usage_limits.detected(), session_id) {
+            schedule_session_resume(app_handle_wait, project_path, session_id, model, limit);
+        }
     });
 
     Ok(())
 }
 
+/// Store a scheduled session resume so that it survives an app restart
+fn save_session_resume(app: &AppHandle, project_path: &str, session_id: &str, model: &str, limit: &UsageLimit) -> Result<(), String> {
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO session_resumes (session_id, project_path, model, reset_at, message) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![session_id, project_path, model, limit.reset_at.to_rfc3339(), limit.message],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn delete_session_resume(app: &AppHandle, session_id: &str) {
+    let db = app.state::<AgentDb>();
+    let deleted = db.0.lock().map_err(|e| e.to_string()).and_then(|conn| {
+        conn.execute("DELETE FROM session_resumes WHERE session_id = ?1", rusqlite::params![session_id])
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = deleted {
+        log::warn!("Failed to delete stored resume of session {}: {}", session_id, e);
+    }
+}
+
+/// Reschedule the session resumes that were still waiting when the app last quit
+pub fn restore_session_resumes(app: &AppHandle) -> Result<usize, String> {
+    let resumes = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT session_id, project_path, model, reset_at, message FROM session_resumes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let count = resumes.len();
+    for (session_id, project_path, model, reset_at, message) in resumes {
+        let reset_at = chrono::DateTime::parse_from_rfc3339(&reset_at)
+            .map(|reset_at| reset_at.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+        log::info!("Restoring scheduled resume of session {}", session_id);
+        schedule_session_resume(app.clone(), project_path, session_id, model, UsageLimit { message, reset_at });
+    }
+    Ok(count)
+}
+
+/// Resume a session stopped by a usage limit once the limit resets, unless the resume
+/// is cancelled first. Waits for any other session that is running at that time.
+fn schedule_session_resume(app: AppHandle, project_path: String, session_id: String, model: String, limit: UsageLimit) {
+    // Boxed because resuming spawns another Claude process, which may schedule a resume again
+    let resume: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(async move {
+        {
+            let state = app.state::<ClaudeProcessState>();
+            if !state.scheduled_resumes.lock().await.insert(session_id.clone()) {
+                return;
+            }
+        }
+        if let Err(e) = save_session_resume(&app, &project_path, &session_id, &model, &limit) {
+            log::warn!("Failed to store resume of session {}, it won't survive a restart: {}", session_id, e);
+        }
+        log::info!("Session {} will resume at {}", session_id, limit.reset_at);
+        let payload = serde_json::json!({
+            "session_id": session_id,
+            "reset_at": limit.reset_at,
+            "message": limit.message,
+        });
+        let _ = app.emit(&format!("claude-rate-limited:{}", session_id), &payload);
+        let _ = app.emit("claude-rate-limited", &payload);
+
+        let wait = (limit.reset_at - chrono::Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        loop {
+            let state = app.state::<ClaudeProcessState>();
+            if !state.scheduled_resumes.lock().await.contains(&session_id) {
+                log::info!("Resume of session {} was cancelled", session_id);
+                return;
+            }
+            let idle = match state.current_process.try_lock() {
+                Ok(current_process) => current_process.is_none(),
+                Err(_) => false,
+            };
+            if idle {
+                state.scheduled_resumes.lock().await.remove(&session_id);
+                delete_session_resume(&app, &session_id);
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        }
+
+        log::info!("Usage limit has reset, resuming session {}", session_id);
+        let _ = app.emit(&format!("claude-resumed:{}", session_id), &session_id);
+        if let Err(e) = resume_claude_code(app, project_path, session_id.clone(), usage_limit::RESUME_PROMPT.to_string(), model).await {
+            log::error!("Failed to resume session {}: {}", session_id, e);
+        }
+    });
+    tauri::async_runtime::spawn(resume);
+}
+
+/// Cancel the automatic resume of a session stopped by a usage limit.
+/// Returns false if no resume was scheduled.
+#[tauri::command]
+pub async fn cancel_session_resume(app: AppHandle, session_id: String) -> Result<bool, String> {
+    let state = app.state::<ClaudeProcessState>();
+    let cancelled = state.scheduled_resumes.lock().await.remove(&session_id);
+    delete_session_resume(&app, &session_id);
+    Ok(cancelled)
+}
+
 
 /// Lists files and directories in a given path
 #[tauri::command]
@@ -0,0 +1,445 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agents::{
+    get_agent_run_with_metrics, insert_queued_run, load_agent, AgentDb, AgentRun, AgentRunMetrics,
+    QueuedRunParams, AGENT_RUN_COLUMNS,
+};
+use crate::commands::evals::copy_dir;
+use crate::commands::task_templates;
+use crate::commands::worktrees::{git, parse_numstat, run_worktree_diff, WorktreeFileChange};
+use crate::process::budget::RunBudget;
+
+/// One model's run within a comparison
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ComparisonEntry {
+    pub model: String,
+    pub run_id: i64,
+    pub status: String,
+    pub status_reason: Option<String>,
+    /// 'worktree' for git projects, 'copy' for a temporary copy of other projects
+    pub isolation: String,
+    pub metrics: Option<AgentRunMetrics>,
+    /// Final answer of the run
+    pub answer: Option<String>,
+    pub files: Vec<WorktreeFileChange>,
+    pub patch: Option<String>,
+    /// Why the changes could not be read, if they couldn't
+    pub diff_error: Option<String>,
+}
+
+/// The same agent and task run under several models
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentComparison {
+    pub id: i64,
+    pub agent_id: i64,
+    pub agent_name: String,
+    pub task: String,
+    pub project_path: String,
+    pub created_at: String,
+    /// 'running' until every run has finished, then 'completed'
+    pub status: String,
+    pub entries: Vec<ComparisonEntry>,
+}
+
+/// A run of a comparison with the temporary copy it ran in, if any
+struct ComparisonRun {
+    model: String,
+    copy_path: Option<String>,
+    copy_base_commit: Option<String>,
+    run: AgentRun,
+}
+
+/// Copy a project into `to` and commit it to a fresh git repository there, so the
+/// run's changes can be diffed against the returned commit
+fn create_project_copy(project_path: &Path, to: &Path) -> Result<String, String> {
+    copy_dir(project_path, to)?;
+    git(to, &["init", "-q"])?;
+    git(to, &["add", "-A"])?;
+    git(
+        to,
+        &[
+            "-c",
+            "user.name=Claudia",
+            "-c",
+            "user.email=claudia@localhost",
+            "commit",
+            "-q",
+            "--no-verify",
+            "--allow-empty",
+            "-m",
+            "Comparison baseline",
+        ],
+    )?;
+    git(to, &["rev-parse", "HEAD"])
+}
+
+fn is_git_repository(path: &Path) -> bool {
+    git(path, &["rev-parse", "--verify", "HEAD"]).is_ok()
+}
+
+/// Changes a run made in its temporary copy
+fn copy_diff(
+    copy_path: &Path,
+    base_commit: &str,
+) -> Result<(Vec<WorktreeFileChange>, String), String> {
+    // Record new files as intent-to-add so they show up in the diff
+    git(copy_path, &["add", "-A", "--intent-to-add"])?;
+    let files = parse_numstat(&git(copy_path, &["diff", "--numstat", base_commit])?);
+    let patch = git(copy_path, &["diff", base_commit])?;
+    Ok((files, patch))
+}
+
+/// Files and patch of a run, or why they are not available
+fn entry_diff(entry: &ComparisonRun) -> Result<Option<(Vec<WorktreeFileChange>, String)>, String> {
+    if entry.run.status == "queued" {
+        return Ok(None);
+    }
+    if let (Some(copy_path), Some(base_commit)) = (&entry.copy_path, &entry.copy_base_commit) {
+        return copy_diff(Path::new(copy_path), base_commit).map(Some);
+    }
+    match &entry.run.worktree {
+        Some(worktree)
+            if ["active", "ready", "kept", "merged"].contains(&worktree.status.as_str()) =>
+        {
+            run_worktree_diff(&entry.run, worktree.clone())
+                .map(|diff| Some((diff.files, diff.patch)))
+        }
+        // Branches without changes are discarded when the worktree closes
+        Some(worktree) if worktree.status == "discarded" && entry.run.status == "completed" => {
+            Ok(Some((Vec::new(), String::new())))
+        }
+        Some(worktree) if worktree.status == "discarded" => {
+            Err("The worktree of this run was discarded".to_string())
+        }
+        _ => Ok(None),
+    }
+}
+
+fn load_comparison_runs(
+    conn: &Connection,
+    comparison_id: i64,
+) -> Result<Vec<ComparisonRun>, String> {
+    let columns = AGENT_RUN_COLUMNS
+        .split(", ")
+        .map(|column| format!("r.{}", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}, c.model, c.copy_path, c.copy_base_commit FROM agent_comparison_runs c JOIN agent_runs r ON r.id = c.run_id WHERE c.comparison_id = ?1 ORDER BY c.position",
+            columns
+        ))
+        .map_err(|e| e.to_string())?;
+    let column_count = columns.split(", ").count();
+    let runs = stmt
+        .query_map(params![comparison_id], |row| {
+            Ok(ComparisonRun {
+                run: AgentRun::from_row(row)?,
+                model: row.get(column_count)?,
+                copy_path: row.get(column_count + 1)?,
+                copy_base_commit: row.get(column_count + 2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(runs)
+}
+
+/// Load a comparison; metrics and diffs are read outside the database lock
+async fn load_comparison(app: &AppHandle, id: i64) -> Result<AgentComparison, String> {
+    let (header, runs) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let header = conn
+            .query_row(
+                "SELECT agent_id, agent_name, task, project_path, created_at FROM agent_comparisons WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                    ))
+                },
+            )
+            .map_err(|e| format!("Comparison not found: {}", e))?;
+        (header, load_comparison_runs(&conn, id)?)
+    };
+
+    let mut entries = Vec::with_capacity(runs.len());
+    for entry in runs {
+        let (files, patch, diff_error) = match entry_diff(&entry) {
+            Ok(Some((files, patch))) => (files, Some(patch), None),
+            Ok(None) => (Vec::new(), None, None),
+            Err(e) => (Vec::new(), None, Some(e)),
+        };
+        let run = entry.run;
+        let metrics = if run.session_id.is_empty() {
+            None
+        } else {
+            get_agent_run_with_metrics(run.clone()).await.metrics
+        };
+        entries.push(ComparisonEntry {
+            model: entry.model,
+            run_id: run.id.unwrap_or_default(),
+            status: run.status,
+            status_reason: run.status_reason,
+            isolation: if entry.copy_path.is_some() {
+                "copy"
+            } else {
+                "worktree"
+            }
+            .to_string(),
+            metrics,
+            answer: run.result.and_then(|result| result.result),
+            files,
+            patch,
+            diff_error,
+        });
+    }
+
+    let running = entries
+        .iter()
+        .any(|entry| ["queued", "pending", "running", "rate_limited"].contains(&entry.status.as_str()));
+    let (agent_id, agent_name, task, project_path, created_at) = header;
+    Ok(AgentComparison {
+        id,
+        agent_id,
+        agent_name,
+        task,
+        project_path,
+        created_at,
+        status: if running { "running" } else { "completed" }.to_string(),
+        entries,
+    })
+}
+
+/// Run an agent on the same task under several models, each isolated from the project
+/// and the other runs: in a worktree for git projects, in a temporary copy otherwise.
+/// The runs are queued together, so the queue's concurrency limits decide how many
+/// of them run at the same time.
+#[tauri::command]
+pub async fn compare_agent_models(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    project_path: String,
+    task: String,
+    models: Vec<String>,
+    variables: Option<HashMap<String, String>>,
+    budget: Option<RunBudget>,
+) -> Result<AgentComparison, String> {
+    let mut seen = HashSet::new();
+    let models: Vec<String> = models
+        .into_iter()
+        .map(|model| model.trim().to_string())
+        .filter(|model| !model.is_empty() && seen.insert(model.clone()))
+        .collect();
+    if models.len() < 2 {
+        return Err("Pick at least two different models to compare".to_string());
+    }
+    let project = PathBuf::from(&project_path);
+    if !project.is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+
+    let (agent, rendered_task) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let agent = load_agent(&conn, agent_id)?;
+        let rendered_task = task_templates::render_task(
+            &task,
+            &agent.task_variables,
+            &variables.unwrap_or_default(),
+        )?;
+        (agent, rendered_task)
+    };
+
+    // Git projects get a worktree per run from the queue; others are copied up front
+    let mut copies: Vec<Option<(String, String)>> = vec![None; models.len()];
+    if !is_git_repository(&project) {
+        let root = std::env::temp_dir()
+            .join("claudia-compare")
+            .join(uuid::Uuid::new_v4().to_string());
+        for (position, copy) in copies.iter_mut().enumerate() {
+            let copy_path = root.join(position.to_string());
+            match create_project_copy(&project, &copy_path) {
+                Ok(base_commit) => {
+                    *copy = Some((copy_path.to_string_lossy().to_string(), base_commit));
+                }
+                Err(e) => {
+                    let _ = std::fs::remove_dir_all(&root);
+                    return Err(format!("Failed to copy the project: {}", e));
+                }
+            }
+        }
+    }
+
+    let (comparison_id, run_ids) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO agent_comparisons (agent_id, agent_name, task, project_path) VALUES (?1, ?2, ?3, ?4)",
+            params![agent_id, agent.name, rendered_task, project_path],
+        )
+        .map_err(|e| e.to_string())?;
+        let comparison_id = tx.last_insert_rowid();
+
+        let mut run_ids = Vec::with_capacity(models.len());
+        for (position, (model, copy)) in models.iter().zip(&copies).enumerate() {
+            let run_id = insert_queued_run(
+                &tx,
+                &agent,
+                &QueuedRunParams {
+                    project_path: copy
+                        .as_ref()
+                        .map(|(copy_path, _)| copy_path.clone())
+                        .unwrap_or_else(|| project_path.clone()),
+                    task: rendered_task.clone(),
+                    task_template: Some(task.clone()),
+                    model: model.clone(),
+                    budget: budget.unwrap_or_default(),
+                    use_worktree: copy.is_none(),
+                    ..Default::default()
+                },
+            )?;
+            tx.execute(
+                "INSERT INTO agent_comparison_runs (comparison_id, position, model, run_id, copy_path, copy_base_commit) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    comparison_id,
+                    position as i64,
+                    model,
+                    run_id,
+                    copy.as_ref().map(|(copy_path, _)| copy_path),
+                    copy.as_ref().map(|(_, base_commit)| base_commit)
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            run_ids.push(run_id);
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        (comparison_id, run_ids)
+    };
+    info!(
+        "Queued comparison {} of agent {} across models {}",
+        comparison_id,
+        agent_id,
+        models.join(", ")
+    );
+
+    for run_id in &run_ids {
+        let _ = app.emit(&format!("agent-queued:{}", run_id), run_id);
+    }
+    crate::commands::queue::schedule_dispatch(&app);
+
+    load_comparison(&app, comparison_id).await
+}
+
+/// Get a comparison with the metrics, answer and changes of each model's run
+#[tauri::command]
+pub async fn get_agent_comparison(app: AppHandle, id: i64) -> Result<AgentComparison, String> {
+    load_comparison(&app, id).await
+}
+
+/// List comparisons, newest first, optionally for one agent
+#[tauri::command]
+pub async fn list_agent_comparisons(
+    app: AppHandle,
+    agent_id: Option<i64>,
+) -> Result<Vec<AgentComparison>, String> {
+    let ids = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT id FROM agent_comparisons WHERE ?1 IS NULL OR agent_id = ?1 ORDER BY id DESC")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map(params![agent_id], |row| row.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    let mut comparisons = Vec::with_capacity(ids.len());
+    for id in ids {
+        comparisons.push(load_comparison(&app, id).await?);
+    }
+    Ok(comparisons)
+}
+
+/// Delete a finished comparison and its temporary copies. The runs themselves are kept,
+/// as are the branches of worktree runs.
+#[tauri::command]
+pub async fn delete_agent_comparison(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let runs = load_comparison_runs(&conn, id)?;
+    if runs
+        .iter()
+        .any(|entry| ["queued", "pending", "running", "rate_limited"].contains(&entry.run.status.as_str()))
+    {
+        return Err("Cancel the comparison's runs before deleting it".to_string());
+    }
+
+    conn.execute(
+        "DELETE FROM agent_comparison_runs WHERE comparison_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM agent_comparisons WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    for copy_path in runs.iter().filter_map(|entry| entry.copy_path.as_deref()) {
+        if let Err(e) = std::fs::remove_dir_all(copy_path) {
+            warn!("Failed to remove comparison copy {}: {}", copy_path, e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_copy_diff() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("a.txt"), "one\n").unwrap();
+        std::fs::write(project.path().join("b.txt"), "two\n").unwrap();
+        assert!(!is_git_repository(project.path()));
+
+        let target = tempfile::tempdir().unwrap();
+        let copy = target.path().join("copy");
+        let base_commit = create_project_copy(project.path(), &copy).unwrap();
+        assert!(is_git_repository(&copy));
+
+        std::fs::write(copy.join("a.txt"), "one\nmore\n").unwrap();
+        std::fs::remove_file(copy.join("b.txt")).unwrap();
+        std::fs::write(copy.join("c.txt"), "new\n").unwrap();
+        let (files, patch) = copy_diff(&copy, &base_commit).unwrap();
+
+        let stats: Vec<(&str, Option<i64>, Option<i64>)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.additions, f.deletions))
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                ("a.txt", Some(1), Some(0)),
+                ("b.txt", Some(0), Some(1)),
+                ("c.txt", Some(1), Some(0))
+            ]
+        );
+        assert!(patch.contains("+more"));
+        // The original project is untouched
+        assert!(project.path().join("b.txt").exists());
+    }
+}
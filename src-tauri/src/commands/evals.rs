@@ -0,0 +1,916 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agent_revisions::ensure_current_revision;
+use crate::commands::agents::{
+    insert_queued_run, load_agent, AgentDb, AgentRun, QueuedRunParams, AGENT_RUN_COLUMNS,
+};
+use crate::commands::run_results::RunResult;
+
+/// Time a command assertion may take unless it sets its own limit
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 300;
+
+/// A check applied to a case's working copy and run result once the agent has finished
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EvalAssertion {
+    /// A file, relative to the working copy, must exist
+    FileExists { path: String },
+    /// The agent's final answer must match a regex
+    OutputMatches { pattern: String },
+    /// A shell command run in the working copy must exit with status 0
+    CommandSucceeds {
+        command: String,
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+    /// The run must not cost more than this
+    MaxCostUsd { max_cost_usd: f64 },
+}
+
+/// One test case of an eval suite
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvalCase {
+    pub name: String,
+    /// Project directory copied into a temporary working copy for every run
+    pub fixture_path: String,
+    pub task: String,
+    pub model: Option<String>, // Falls back to the run's or agent's model when unset
+    #[serde(default)]
+    pub assertions: Vec<EvalAssertion>,
+}
+
+/// A set of test cases for one agent
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvalSuite {
+    pub id: Option<i64>,
+    pub agent_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub cases: Vec<EvalCase>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Result of one assertion
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssertionOutcome {
+    pub assertion: EvalAssertion,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Outcome of one case within an eval run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvalCaseResult {
+    pub id: i64,
+    pub position: i64,
+    pub case_name: String,
+    /// 'running', 'evaluating', 'passed', 'failed' or 'cancelled'
+    pub status: String,
+    pub agent_run_id: Option<i64>,
+    pub outcomes: Vec<AssertionOutcome>,
+    /// Set when the case failed for a reason other than an assertion
+    pub error: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub completed_at: Option<String>,
+}
+
+/// An execution of an eval suite against one agent revision
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvalRun {
+    pub id: i64,
+    pub suite_id: i64,
+    pub suite_name: String,
+    pub agent_id: i64,
+    pub revision_id: i64,
+    pub model: Option<String>,
+    /// 'running', 'passed', 'failed' or 'cancelled'
+    pub status: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub total_cost_usd: f64,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub cases: Vec<EvalCaseResult>,
+}
+
+const EVAL_CASE_RESULT_COLUMNS: &str =
+    "id, position, case_name, status, agent_run_id, outcomes, error, cost_usd, completed_at";
+
+impl EvalCaseResult {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(EvalCaseResult {
+            id: row.get(0)?,
+            position: row.get(1)?,
+            case_name: row.get(2)?,
+            status: row.get(3)?,
+            agent_run_id: row.get(4)?,
+            outcomes: row
+                .get::<_, Option<String>>(5)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            error: row.get(6)?,
+            cost_usd: row.get(7)?,
+            completed_at: row.get(8)?,
+        })
+    }
+}
+
+fn validate_cases(cases: &[EvalCase]) -> Result<(), String> {
+    if cases.is_empty() {
+        return Err("An eval suite needs at least one case".to_string());
+    }
+    let mut names = HashSet::new();
+    for case in cases {
+        if case.name.trim().is_empty() {
+            return Err("Every case needs a name".to_string());
+        }
+        if !names.insert(case.name.as_str()) {
+            return Err(format!("Duplicate case name '{}'", case.name));
+        }
+        if !Path::new(&case.fixture_path).is_dir() {
+            return Err(format!(
+                "Case '{}': fixture directory not found: {}",
+                case.name, case.fixture_path
+            ));
+        }
+        if case.task.trim().is_empty() {
+            return Err(format!("Case '{}' has no task", case.name));
+        }
+        for assertion in &case.assertions {
+            match assertion {
+                EvalAssertion::OutputMatches { pattern } => {
+                    regex::Regex::new(pattern)
+                        .map_err(|e| format!("Case '{}': invalid pattern: {}", case.name, e))?;
+                }
+                EvalAssertion::CommandSucceeds { command, .. } if command.trim().is_empty() => {
+                    return Err(format!("Case '{}' has an empty command", case.name));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn save_cases(conn: &Connection, suite_id: i64, cases: &[EvalCase]) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM eval_cases WHERE suite_id = ?1",
+        params![suite_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for (position, case) in cases.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO eval_cases (suite_id, position, name, fixture_path, task, model, assertions) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                suite_id,
+                position as i64,
+                case.name,
+                case.fixture_path,
+                case.task,
+                case.model,
+                serde_json::to_string(&case.assertions).map_err(|e| e.to_string())?
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn load_suite(conn: &Connection, id: i64) -> Result<EvalSuite, String> {
+    let mut suite = conn
+        .query_row(
+            "SELECT id, agent_id, name, description, created_at, updated_at FROM eval_suites WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(EvalSuite {
+                    id: Some(row.get(0)?),
+                    agent_id: row.get(1)?,
+                    name: row.get(2)?,
+                    description: row.get(3)?,
+                    cases: Vec::new(),
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                })
+            },
+        )
+        .map_err(|e| format!("Eval suite not found: {}", e))?;
+
+    let mut stmt = conn
+        .prepare("SELECT name, fixture_path, task, model, assertions FROM eval_cases WHERE suite_id = ?1 ORDER BY position")
+        .map_err(|e| e.to_string())?;
+    suite.cases = stmt
+        .query_map(params![id], |row| {
+            Ok(EvalCase {
+                name: row.get(0)?,
+                fixture_path: row.get(1)?,
+                task: row.get(2)?,
+                model: row.get(3)?,
+                assertions: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(suite)
+}
+
+fn load_eval_run(conn: &Connection, id: i64) -> Result<EvalRun, String> {
+    let mut eval_run = conn
+        .query_row(
+            "SELECT id, suite_id, suite_name, agent_id, revision_id, model, status, created_at, completed_at FROM eval_runs WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(EvalRun {
+                    id: row.get(0)?,
+                    suite_id: row.get(1)?,
+                    suite_name: row.get(2)?,
+                    agent_id: row.get(3)?,
+                    revision_id: row.get(4)?,
+                    model: row.get(5)?,
+                    status: row.get(6)?,
+                    passed: 0,
+                    failed: 0,
+                    total_cost_usd: 0.0,
+                    created_at: row.get(7)?,
+                    completed_at: row.get(8)?,
+                    cases: Vec::new(),
+                })
+            },
+        )
+        .map_err(|e| format!("Eval run not found: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM eval_run_cases WHERE eval_run_id = ?1 ORDER BY position",
+            EVAL_CASE_RESULT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    eval_run.cases = stmt
+        .query_map(params![id], EvalCaseResult::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    eval_run.passed = eval_run
+        .cases
+        .iter()
+        .filter(|c| c.status == "passed")
+        .count();
+    eval_run.failed = eval_run
+        .cases
+        .iter()
+        .filter(|c| c.status == "failed")
+        .count();
+    eval_run.total_cost_usd = eval_run.cases.iter().filter_map(|c| c.cost_usd).sum();
+    Ok(eval_run)
+}
+
+/// Copy a directory tree, including hidden files and symlinks
+pub(crate) fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    for entry in walkdir::WalkDir::new(from).follow_links(false) {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        let relative = entry.path().strip_prefix(from).map_err(|e| e.to_string())?;
+        let target = to.join(relative);
+        let file_type = entry.file_type();
+
+        let copied = if file_type.is_dir() {
+            std::fs::create_dir_all(&target)
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            {
+                std::fs::read_link(entry.path())
+                    .and_then(|link| std::os::unix::fs::symlink(link, &target))
+            }
+            #[cfg(not(unix))]
+            {
+                std::fs::copy(entry.path(), &target).map(|_| ())
+            }
+        } else {
+            std::fs::copy(entry.path(), &target).map(|_| ())
+        };
+        copied.map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+    }
+    Ok(())
+}
+
+/// Check the assertions that don't run a command
+fn check_assertion(
+    assertion: &EvalAssertion,
+    workdir: &Path,
+    result: Option<&RunResult>,
+) -> Option<AssertionOutcome> {
+    let (passed, message) = match assertion {
+        EvalAssertion::FileExists { path } => {
+            let exists = workdir.join(path).exists();
+            (
+                exists,
+                if exists {
+                    format!("{} exists", path)
+                } else {
+                    format!("{} does not exist", path)
+                },
+            )
+        }
+        EvalAssertion::OutputMatches { pattern } => {
+            let answer = result.and_then(|r| r.result.as_deref()).unwrap_or_default();
+            match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(answer) => (true, format!("Output matches {}", pattern)),
+                Ok(_) => (false, format!("Output does not match {}", pattern)),
+                Err(e) => (false, format!("Invalid pattern: {}", e)),
+            }
+        }
+        EvalAssertion::MaxCostUsd { max_cost_usd } => match result.and_then(|r| r.total_cost_usd) {
+            Some(cost) if cost <= *max_cost_usd => (
+                true,
+                format!("Cost ${:.4} is within ${:.4}", cost, max_cost_usd),
+            ),
+            Some(cost) => (
+                false,
+                format!("Cost ${:.4} exceeds ${:.4}", cost, max_cost_usd),
+            ),
+            None => (false, "The run reported no cost".to_string()),
+        },
+        EvalAssertion::CommandSucceeds { .. } => return None,
+    };
+    Some(AssertionOutcome {
+        assertion: assertion.clone(),
+        passed,
+        message,
+    })
+}
+
+/// Run a command assertion in the working copy
+async fn run_command(command: &str, timeout_secs: Option<u64>, workdir: &Path) -> (bool, String) {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    cmd.current_dir(workdir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS));
+    match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            let tail: String = text
+                .trim()
+                .chars()
+                .rev()
+                .take(500)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .collect();
+            match output.status.code() {
+                Some(0) => (true, format!("`{}` passed", command)),
+                code => (
+                    false,
+                    format!("`{}` exited with {:?}: {}", command, code, tail),
+                ),
+            }
+        }
+        Ok(Err(e)) => (false, format!("Failed to run `{}`: {}", command, e)),
+        Err(_) => (
+            false,
+            format!("`{}` timed out after {}s", command, timeout.as_secs()),
+        ),
+    }
+}
+
+/// Evaluate a case whose agent run has finished, then complete the eval run if it was the last
+async fn evaluate_case(app: AppHandle, case_id: i64) {
+    let loaded = {
+        let db = app.state::<AgentDb>();
+        let conn = match db.0.lock() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!(
+                    "Failed to lock database to evaluate case {}: {}",
+                    case_id, e
+                );
+                return;
+            }
+        };
+        conn.query_row(
+            "SELECT eval_run_id, workdir, assertions, agent_run_id FROM eval_run_cases WHERE id = ?1",
+            params![case_id],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            },
+        )
+        .and_then(|(eval_run_id, workdir, assertions, run_id)| {
+            let run = conn.query_row(
+                &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
+                params![run_id],
+                AgentRun::from_row,
+            )?;
+            Ok((eval_run_id, workdir, assertions, run))
+        })
+    };
+    let (eval_run_id, workdir, assertions, run) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("Failed to load eval case {}: {}", case_id, e);
+            return;
+        }
+    };
+
+    let workdir = PathBuf::from(workdir);
+    let assertions: Vec<EvalAssertion> = serde_json::from_str(&assertions).unwrap_or_default();
+    let mut outcomes = Vec::with_capacity(assertions.len());
+    for assertion in assertions {
+        let outcome = match &assertion {
+            EvalAssertion::CommandSucceeds {
+                command,
+                timeout_secs,
+            } => {
+                let (passed, message) = run_command(command, *timeout_secs, &workdir).await;
+                AssertionOutcome {
+                    assertion: assertion.clone(),
+                    passed,
+                    message,
+                }
+            }
+            _ => match check_assertion(&assertion, &workdir, run.result.as_ref()) {
+                Some(outcome) => outcome,
+                None => continue,
+            },
+        };
+        outcomes.push(outcome);
+    }
+
+    let error = match run.status.as_str() {
+        "completed" => None,
+        status => Some(format!("Agent run ended with status '{}'", status)),
+    };
+    let status = if run.status == "cancelled" {
+        "cancelled"
+    } else if error.is_none() && outcomes.iter().all(|o| o.passed) {
+        "passed"
+    } else {
+        "failed"
+    };
+    if let Err(e) = std::fs::remove_dir_all(&workdir) {
+        warn!("Failed to remove eval working copy {:?}: {}", workdir, e);
+    }
+
+    let db = app.state::<AgentDb>();
+    let Ok(conn) = db.0.lock() else {
+        return;
+    };
+    let updated = conn.execute(
+        "UPDATE eval_run_cases SET status = ?1, outcomes = ?2, error = ?3, cost_usd = ?4, completed_at = CURRENT_TIMESTAMP WHERE id = ?5 AND status = 'evaluating'",
+        params![
+            status,
+            serde_json::to_string(&outcomes).unwrap_or_default(),
+            error,
+            run.result.as_ref().and_then(|r| r.total_cost_usd),
+            case_id
+        ],
+    );
+    if let Err(e) = updated {
+        error!("Failed to record eval case {}: {}", case_id, e);
+        return;
+    }
+    info!(
+        "Eval case {} of eval run {} {}",
+        case_id, eval_run_id, status
+    );
+
+    // The eval run is over once no case is left running or being evaluated
+    let remaining: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM eval_run_cases WHERE eval_run_id = ?1 AND status IN ('running', 'evaluating')",
+            params![eval_run_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(1);
+    if remaining == 0 {
+        let _ = conn.execute(
+            "UPDATE eval_runs SET status = CASE WHEN EXISTS (SELECT 1 FROM eval_run_cases WHERE eval_run_id = ?1 AND status != 'passed') THEN 'failed' ELSE 'passed' END, completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+            params![eval_run_id],
+        );
+        let _ = app.emit(&format!("eval-run-complete:{}", eval_run_id), eval_run_id);
+    }
+    let _ = app.emit(&format!("eval-run-updated:{}", eval_run_id), eval_run_id);
+}
+
+/// Start evaluating cases whose agent run has finished; called by the run queue
+pub(crate) fn advance_eval_runs(app: &AppHandle) {
+    let finished = {
+        let db = app.state::<AgentDb>();
+        let Ok(conn) = db.0.lock() else {
+            return;
+        };
+        let finished = conn
+            .prepare("SELECT c.id FROM eval_run_cases c JOIN agent_runs r ON r.id = c.agent_run_id WHERE c.status = 'running' AND r.status NOT IN ('queued', 'pending', 'running', 'rate_limited')")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, i64>(0))?
+                    .collect::<Result<Vec<_>, _>>()
+            });
+        let finished = match finished {
+            Ok(finished) => finished,
+            Err(e) => {
+                error!("Failed to load finished eval cases: {}", e);
+                return;
+            }
+        };
+        for case_id in &finished {
+            let _ = conn.execute(
+                "UPDATE eval_run_cases SET status = 'evaluating' WHERE id = ?1",
+                params![case_id],
+            );
+        }
+        finished
+    };
+
+    for case_id in finished {
+        tauri::async_runtime::spawn(evaluate_case(app.clone(), case_id));
+    }
+}
+
+/// List eval suites, optionally for one agent
+#[tauri::command]
+pub async fn list_eval_suites(
+    db: State<'_, AgentDb>,
+    agent_id: Option<i64>,
+) -> Result<Vec<EvalSuite>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let ids = conn
+        .prepare("SELECT id FROM eval_suites WHERE ?1 IS NULL OR agent_id = ?1 ORDER BY name")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![agent_id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    ids.into_iter().map(|id| load_suite(&conn, id)).collect()
+}
+
+/// Get an eval suite with its cases
+#[tauri::command]
+pub async fn get_eval_suite(db: State<'_, AgentDb>, id: i64) -> Result<EvalSuite, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_suite(&conn, id)
+}
+
+/// Create an eval suite for an agent
+#[tauri::command]
+pub async fn create_eval_suite(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    name: String,
+    description: Option<String>,
+    cases: Vec<EvalCase>,
+) -> Result<EvalSuite, String> {
+    validate_cases(&cases)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_agent(&conn, agent_id)?;
+
+    conn.execute(
+        "INSERT INTO eval_suites (agent_id, name, description) VALUES (?1, ?2, ?3)",
+        params![agent_id, name, description],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    save_cases(&conn, id, &cases)?;
+
+    load_suite(&conn, id)
+}
+
+/// Update an eval suite; reports of earlier runs keep the cases they ran with
+#[tauri::command]
+pub async fn update_eval_suite(
+    db: State<'_, AgentDb>,
+    id: i64,
+    name: String,
+    description: Option<String>,
+    cases: Vec<EvalCase>,
+) -> Result<EvalSuite, String> {
+    validate_cases(&cases)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let updated = conn
+        .execute(
+            "UPDATE eval_suites SET name = ?1, description = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+            params![name, description, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Eval suite not found".to_string());
+    }
+    save_cases(&conn, id, &cases)?;
+
+    load_suite(&conn, id)
+}
+
+/// Delete an eval suite; its run reports are kept
+#[tauri::command]
+pub async fn delete_eval_suite(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM eval_cases WHERE suite_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM eval_suites WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Run every case of a suite against the agent's current revision. Each case runs in a
+/// temporary copy of its fixture through the run queue; assertions are checked as cases finish.
+#[tauri::command]
+pub async fn run_eval_suite(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    suite_id: i64,
+    model: Option<String>,
+) -> Result<EvalRun, String> {
+    let (suite, agent) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let suite = load_suite(&conn, suite_id)?;
+        let agent = load_agent(&conn, suite.agent_id)?;
+        (suite, agent)
+    };
+
+    // Copy every fixture before queueing anything, so a bad fixture fails the whole run
+    let root = std::env::temp_dir()
+        .join("claudia-evals")
+        .join(uuid::Uuid::new_v4().to_string());
+    let mut workdirs = Vec::with_capacity(suite.cases.len());
+    for (position, case) in suite.cases.iter().enumerate() {
+        let workdir = root.join(position.to_string());
+        if let Err(e) = copy_dir(Path::new(&case.fixture_path), &workdir) {
+            let _ = std::fs::remove_dir_all(&root);
+            return Err(format!("Case '{}': {}", case.name, e));
+        }
+        workdirs.push(workdir);
+    }
+
+    let (eval_run_id, run_ids) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let revision_id = ensure_current_revision(&tx, &agent)?;
+        tx.execute(
+            "INSERT INTO eval_runs (suite_id, suite_name, agent_id, revision_id, model, status) VALUES (?1, ?2, ?3, ?4, ?5, 'running')",
+            params![suite_id, suite.name, suite.agent_id, revision_id, model],
+        )
+        .map_err(|e| e.to_string())?;
+        let eval_run_id = tx.last_insert_rowid();
+
+        let mut run_ids = Vec::with_capacity(suite.cases.len());
+        for ((position, case), workdir) in suite.cases.iter().enumerate().zip(&workdirs) {
+            let workdir = workdir.to_string_lossy().to_string();
+            let run_id = insert_queued_run(
+                &tx,
+                &agent,
+                &QueuedRunParams {
+                    project_path: workdir.clone(),
+                    task: case.task.clone(),
+                    model: model
+                        .clone()
+                        .or_else(|| case.model.clone())
+                        .unwrap_or_else(|| agent.model.clone()),
+                    revision_id: Some(revision_id),
+                    ..Default::default()
+                },
+            )?;
+            tx.execute(
+                "INSERT INTO eval_run_cases (eval_run_id, position, case_name, assertions, workdir, agent_run_id, status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'running')",
+                params![
+                    eval_run_id,
+                    position as i64,
+                    case.name,
+                    serde_json::to_string(&case.assertions).map_err(|e| e.to_string())?,
+                    workdir,
+                    run_id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            run_ids.push(run_id);
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        (eval_run_id, run_ids)
+    };
+    info!(
+        "Started eval run {} of suite '{}' with {} cases",
+        eval_run_id,
+        suite.name,
+        run_ids.len()
+    );
+
+    for run_id in &run_ids {
+        let _ = app.emit(&format!("agent-queued:{}", run_id), run_id);
+    }
+    crate::commands::queue::schedule_dispatch(&app);
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_eval_run(&conn, eval_run_id)
+}
+
+/// Get the report of an eval run
+#[tauri::command]
+pub async fn get_eval_run(db: State<'_, AgentDb>, id: i64) -> Result<EvalRun, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_eval_run(&conn, id)
+}
+
+/// List eval runs, newest first, optionally for one suite or agent
+#[tauri::command]
+pub async fn list_eval_runs(
+    db: State<'_, AgentDb>,
+    suite_id: Option<i64>,
+    agent_id: Option<i64>,
+) -> Result<Vec<EvalRun>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let ids = conn
+        .prepare("SELECT id FROM eval_runs WHERE (?1 IS NULL OR suite_id = ?1) AND (?2 IS NULL OR agent_id = ?2) ORDER BY id DESC")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![suite_id, agent_id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    ids.into_iter().map(|id| load_eval_run(&conn, id)).collect()
+}
+
+/// Cancel an eval run: queued cases are dropped and running agents are killed
+#[tauri::command]
+pub async fn cancel_eval_run(app: AppHandle, id: i64) -> Result<EvalRun, String> {
+    let (active_runs, workdirs) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let updated = conn
+            .execute(
+                "UPDATE eval_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+                params![id],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return load_eval_run(&conn, id);
+        }
+
+        let cases = conn
+            .prepare("SELECT agent_run_id, workdir FROM eval_run_cases WHERE eval_run_id = ?1 AND status = 'running'")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE eval_run_cases SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE eval_run_id = ?1 AND status = 'running'",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut active_runs = Vec::new();
+        for (run_id, _) in &cases {
+            let status: Option<String> = conn
+                .query_row(
+                    "SELECT status FROM agent_runs WHERE id = ?1",
+                    params![run_id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            match status.as_deref() {
+                Some("queued") | Some("rate_limited") => {
+                    conn.execute(
+                        "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status IN ('queued', 'rate_limited')",
+                        params![run_id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                Some("pending") | Some("running") => active_runs.push(*run_id),
+                _ => {}
+            }
+        }
+        (
+            active_runs,
+            cases
+                .into_iter()
+                .map(|(_, workdir)| workdir)
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    for run_id in active_runs {
+        if let Err(e) = crate::commands::agents::kill_agent_session(
+            app.clone(),
+            app.state::<AgentDb>(),
+            app.state::<crate::process::ProcessRegistryState>(),
+            run_id,
+        )
+        .await
+        {
+            warn!(
+                "Failed to cancel agent run {} of eval run {}: {}",
+                run_id, id, e
+            );
+        }
+    }
+    for workdir in workdirs {
+        let _ = std::fs::remove_dir_all(workdir);
+    }
+    crate::commands::queue::schedule_dispatch(&app);
+
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_eval_run(&conn, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assertion_format() {
+        let assertions: Vec<EvalAssertion> = serde_json::from_str(
+            r#"[{"type":"file_exists","path":"src/lib.rs"},{"type":"command_succeeds","command":"cargo test"},{"type":"max_cost_usd","max_cost_usd":0.5}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            assertions[1],
+            EvalAssertion::CommandSucceeds {
+                command: "cargo test".to_string(),
+                timeout_secs: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_assertion() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("out.txt"), "").unwrap();
+        let result = RunResult {
+            result: Some("All 12 tests pass".to_string()),
+            is_error: false,
+            total_cost_usd: Some(0.2),
+            ..Default::default()
+        };
+        let check = |assertion: EvalAssertion| {
+            check_assertion(&assertion, dir.path(), Some(&result))
+                .unwrap()
+                .passed
+        };
+
+        assert!(check(EvalAssertion::FileExists {
+            path: "out.txt".to_string()
+        }));
+        assert!(!check(EvalAssertion::FileExists {
+            path: "missing.txt".to_string()
+        }));
+        assert!(check(EvalAssertion::OutputMatches {
+            pattern: r"\d+ tests pass".to_string()
+        }));
+        assert!(!check(EvalAssertion::MaxCostUsd { max_cost_usd: 0.1 }));
+    }
+
+    #[test]
+    fn test_copy_dir() {
+        let fixture = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(fixture.path().join("src")).unwrap();
+        std::fs::write(fixture.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(fixture.path().join(".env"), "A=1").unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let copy = target.path().join("case");
+        copy_dir(fixture.path(), &copy).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(copy.join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(copy.join(".env").exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_command() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(run_command("exit 0", None, dir.path()).await.0);
+        assert!(!run_command("exit 3", None, dir.path()).await.0);
+    }
+}
@@ -1,7 +1,28 @@
 pub mod agents;
+pub mod agent_library;
+pub mod agent_revisions;
 pub mod claude;
 pub mod mcp;
 pub mod usage;
 pub mod storage;
 pub mod slash_commands;
 pub mod proxy;
+pub mod queue;
+pub mod schedules;
+pub mod task_templates;
+pub mod pipelines;
+pub mod worktrees;
+pub mod registries;
+pub mod agent_updates;
+pub mod agent_export;
+pub mod subagents;
+pub mod agent_env;
+pub mod reruns;
+pub mod batches;
+pub mod run_results;
+pub mod evals;
+pub mod comparisons;
+pub mod run_annotations;
+pub mod run_changes;
+pub mod webhooks;
+pub mod notifications;
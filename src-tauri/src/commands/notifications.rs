@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::agents::{AgentDb, AgentRun, AGENT_RUN_COLUMNS};
+
+/// Events that raise a desktop notification, each of which can be turned off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationEvent {
+    Completed,
+    Failed,
+    BudgetExceeded,
+    TimedOut,
+}
+
+impl NotificationEvent {
+    /// app_settings key of the event's preference
+    fn setting_key(self) -> &'static str {
+        match self {
+            NotificationEvent::Completed => "notify_on_completed",
+            NotificationEvent::Failed => "notify_on_failed",
+            NotificationEvent::BudgetExceeded => "notify_on_budget_exceeded",
+            NotificationEvent::TimedOut => "notify_on_timed_out",
+        }
+    }
+
+    /// Event of a run that ended with `status`. Cancelled runs and runs waiting for a
+    /// usage limit to reset have none.
+    pub fn for_run_status(status: &str) -> Option<Self> {
+        match status {
+            "completed" => Some(NotificationEvent::Completed),
+            "failed" => Some(NotificationEvent::Failed),
+            "budget_exceeded" => Some(NotificationEvent::BudgetExceeded),
+            "timed_out" => Some(NotificationEvent::TimedOut),
+            _ => None,
+        }
+    }
+}
+
+const NOTIFICATION_EVENTS: [NotificationEvent; 4] = [
+    NotificationEvent::Completed,
+    NotificationEvent::Failed,
+    NotificationEvent::BudgetExceeded,
+    NotificationEvent::TimedOut,
+];
+
+/// Which events raise a desktop notification; all are enabled by default
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct NotificationPreferences {
+    pub completed: bool,
+    pub failed: bool,
+    pub budget_exceeded: bool,
+    pub timed_out: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            completed: true,
+            failed: true,
+            budget_exceeded: true,
+            timed_out: true,
+        }
+    }
+}
+
+impl NotificationPreferences {
+    fn enabled_mut(&mut self, event: NotificationEvent) -> &mut bool {
+        match event {
+            NotificationEvent::Completed => &mut self.completed,
+            NotificationEvent::Failed => &mut self.failed,
+            NotificationEvent::BudgetExceeded => &mut self.budget_exceeded,
+            NotificationEvent::TimedOut => &mut self.timed_out,
+        }
+    }
+
+    pub fn allows(&self, event: NotificationEvent) -> bool {
+        match event {
+            NotificationEvent::Completed => self.completed,
+            NotificationEvent::Failed => self.failed,
+            NotificationEvent::BudgetExceeded => self.budget_exceeded,
+            NotificationEvent::TimedOut => self.timed_out,
+        }
+    }
+}
+
+/// Load notification preferences from app_settings, falling back to defaults
+pub fn load_notification_preferences(conn: &Connection) -> NotificationPreferences {
+    let mut preferences = NotificationPreferences::default();
+    for event in NOTIFICATION_EVENTS {
+        if let Ok(value) = conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![event.setting_key()],
+            |row| row.get::<_, String>(0),
+        ) {
+            *preferences.enabled_mut(event) = value != "false";
+        }
+    }
+    preferences
+}
+
+/// The run or session a notification is about
+#[derive(Debug, Clone, Default)]
+pub struct NotificationSubject {
+    pub name: String,
+    pub duration: Option<Duration>,
+    pub cost_usd: Option<f64>,
+    /// Why it stopped
+    pub detail: Option<String>,
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Title and body of a notification
+fn notification_text(event: NotificationEvent, subject: &NotificationSubject) -> (String, String) {
+    let title = match event {
+        NotificationEvent::Completed => format!("✅ {} finished", subject.name),
+        NotificationEvent::Failed => format!("❌ {} failed", subject.name),
+        NotificationEvent::BudgetExceeded => format!("💸 {} exceeded its budget", subject.name),
+        NotificationEvent::TimedOut => format!("⏰ {} timed out", subject.name),
+    };
+
+    let mut stats = Vec::new();
+    if let Some(duration) = subject.duration {
+        stats.push(format!("Took {}", format_duration(duration)));
+    }
+    if let Some(cost) = subject.cost_usd {
+        stats.push(format!("Cost ${:.2}", cost));
+    }
+    let mut lines = Vec::new();
+    if !stats.is_empty() {
+        lines.push(stats.join(" · "));
+    }
+    if let Some(detail) = subject.detail.as_deref().filter(|d| !d.trim().is_empty()) {
+        lines.push(detail.trim().to_string());
+    }
+    (title, lines.join("\n"))
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+        .map_err(|e| e.to_string())
+}
+
+/// Show a desktop notification for `event` unless it is turned off
+pub fn notify(app: &AppHandle, event: NotificationEvent, subject: &NotificationSubject) {
+    let allowed = app
+        .state::<AgentDb>()
+        .0
+        .lock()
+        .map(|conn| load_notification_preferences(&conn).allows(event))
+        .unwrap_or(true);
+    if !allowed {
+        return;
+    }
+
+    let (title, body) = notification_text(event, subject);
+    if let Err(e) = show(app, &title, &body) {
+        warn!("Failed to show notification '{}': {}", title, e);
+    }
+}
+
+fn load_run(app: &AppHandle, run_id: i64) -> Option<AgentRun> {
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().ok()?;
+    conn.query_row(
+        &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
+        params![run_id],
+        AgentRun::from_row,
+    )
+    .ok()
+}
+
+/// Notify that a run has ended, with the event matching its final status
+pub fn notify_run_finished(app: &AppHandle, run_id: i64) {
+    let Some(run) = load_run(app, run_id) else {
+        return;
+    };
+    let Some(event) = NotificationEvent::for_run_status(&run.status) else {
+        return;
+    };
+
+    // Wall-clock time of the last process; resumed runs count from their resume
+    let duration = run
+        .process_started_at
+        .as_deref()
+        .and_then(|started| chrono::DateTime::parse_from_rfc3339(started).ok())
+        .and_then(|started| {
+            (chrono::Utc::now() - started.with_timezone(&chrono::Utc))
+                .to_std()
+                .ok()
+        });
+    let subject = NotificationSubject {
+        name: run.agent_name.clone(),
+        duration,
+        cost_usd: run.result.as_ref().and_then(|r| r.total_cost_usd),
+        detail: run.status_reason.clone(),
+    };
+    notify(app, event, &subject);
+}
+
+/// Get the desktop notification preferences
+#[tauri::command]
+pub async fn get_notification_preferences(
+    db: State<'_, AgentDb>,
+) -> Result<NotificationPreferences, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_notification_preferences(&conn))
+}
+
+/// Save the desktop notification preferences
+#[tauri::command]
+pub async fn save_notification_preferences(
+    db: State<'_, AgentDb>,
+    preferences: NotificationPreferences,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    for event in NOTIFICATION_EVENTS {
+        let key = event.setting_key();
+        conn.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+            params![key, preferences.allows(event).to_string()],
+        )
+        .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+    }
+    Ok(())
+}
+
+/// Show a sample notification, regardless of the preferences
+#[tauri::command]
+pub async fn send_test_notification(app: AppHandle) -> Result<(), String> {
+    info!("Sending test notification");
+    let subject = NotificationSubject {
+        name: "Test run".to_string(),
+        duration: Some(Duration::from_secs(252)),
+        cost_usd: Some(0.42),
+        detail: None,
+    };
+    let (title, body) = notification_text(NotificationEvent::Completed, &subject);
+    show(&app, &title, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_text() {
+        let subject = NotificationSubject {
+            name: "Reviewer".to_string(),
+            duration: Some(Duration::from_secs(252)),
+            cost_usd: Some(0.4213),
+            detail: Some("Token budget of 10000 exceeded".to_string()),
+        };
+        assert_eq!(
+            notification_text(NotificationEvent::BudgetExceeded, &subject),
+            (
+                "💸 Reviewer exceeded its budget".to_string(),
+                "Took 4m 12s · Cost $0.42\nToken budget of 10000 exceeded".to_string()
+            )
+        );
+        assert_eq!(
+            notification_text(
+                NotificationEvent::Completed,
+                &NotificationSubject::default()
+            )
+            .1,
+            ""
+        );
+        assert_eq!(
+            format_duration(Duration::from_secs(3 * 3600 + 120)),
+            "3h 2m"
+        );
+    }
+}
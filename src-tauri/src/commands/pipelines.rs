@@ -0,0 +1,911 @@
+use std::collections::{HashMap, HashSet};
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agents::{insert_queued_run, load_agent, AgentDb, QueuedRunParams};
+use crate::commands::task_templates::{self, TaskVariable};
+
+/// What happens to the pipeline when a step fails
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepFailurePolicy {
+    /// Don't start any further steps and mark the pipeline run as failed
+    Stop,
+    /// Keep going; dependent steps still run and see the failed step's output
+    Continue,
+    /// Re-run the step up to `max_retries` times, then stop
+    Retry,
+}
+
+impl StepFailurePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepFailurePolicy::Stop => "stop",
+            StepFailurePolicy::Continue => "continue",
+            StepFailurePolicy::Retry => "retry",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "continue" => StepFailurePolicy::Continue,
+            "retry" => StepFailurePolicy::Retry,
+            _ => StepFailurePolicy::Stop,
+        }
+    }
+}
+
+/// A single agent invocation within a pipeline
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PipelineStep {
+    /// Identifier referenced by `depends_on` and by `{{steps.<key>.output}}` placeholders
+    pub key: String,
+    pub agent_id: i64,
+    /// Task sent to the agent; may reference outputs of earlier steps
+    pub task_template: String,
+    pub model: Option<String>, // Falls back to the agent's model when unset
+    /// Keys of the steps this one waits for. `None` means the previous step in the list,
+    /// which makes a plain ordered list of steps a simple chain.
+    pub depends_on: Option<Vec<String>>,
+    pub on_failure: StepFailurePolicy,
+    #[serde(default)]
+    pub max_retries: u32,
+}
+
+/// A named chain (or DAG) of agent steps
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Pipeline {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Vec<PipelineStep>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Progress of one step within a pipeline run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PipelineRunStep {
+    pub id: i64,
+    pub step_key: String,
+    pub position: i64,
+    pub agent_id: i64,
+    pub status: String, // 'waiting', 'running', 'completed', 'failed', 'skipped', 'cancelled'
+    pub attempts: i64,
+    pub agent_run_id: Option<i64>, // Latest attempt
+    pub output: Option<String>,    // Final result message of the step
+    pub error: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+/// An execution of a pipeline against a project
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PipelineRun {
+    pub id: i64,
+    pub pipeline_id: i64,
+    pub pipeline_name: String,
+    pub project_path: String,
+    pub status: String, // 'running', 'completed', 'failed', 'cancelled'
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub steps: Vec<PipelineRunStep>,
+}
+
+const PIPELINE_RUN_COLUMNS: &str =
+    "id, pipeline_id, pipeline_name, project_path, status, created_at, completed_at";
+
+const PIPELINE_RUN_STEP_COLUMNS: &str =
+    "id, step_key, position, agent_id, status, attempts, agent_run_id, output, error, completed_at";
+
+impl PipelineRunStep {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(PipelineRunStep {
+            id: row.get(0)?,
+            step_key: row.get(1)?,
+            position: row.get(2)?,
+            agent_id: row.get(3)?,
+            status: row.get(4)?,
+            attempts: row.get(5)?,
+            agent_run_id: row.get(6)?,
+            output: row.get(7)?,
+            error: row.get(8)?,
+            completed_at: row.get(9)?,
+        })
+    }
+}
+
+/// Parse the `{{steps.<key>.output}}` references in a task template
+fn template_references(template: &str) -> Vec<String> {
+    task_templates::placeholders(template)
+        .into_iter()
+        .filter_map(|name| step_output_key(&name).map(String::from))
+        .collect()
+}
+
+fn step_output_key(placeholder: &str) -> Option<&str> {
+    placeholder
+        .strip_prefix("steps.")
+        .and_then(|p| p.strip_suffix(".output"))
+}
+
+/// Replace `{{steps.<key>.output}}` and `{{project_path}}` placeholders, then the
+/// agent's own task variables with their defaults. Unknown placeholders are left untouched.
+fn render_step_task(
+    template: &str,
+    project_path: &str,
+    outputs: &HashMap<String, String>,
+    variables: &[TaskVariable],
+) -> String {
+    task_templates::render(template, |name| {
+        if name == "project_path" {
+            return Some(project_path.to_string());
+        }
+        if let Some(key) = step_output_key(name) {
+            return Some(outputs.get(key).cloned().unwrap_or_default());
+        }
+        variables
+            .iter()
+            .find(|v| v.name == name)
+            .and_then(|v| v.default_value.clone())
+    })
+}
+
+/// Resolve each step's dependencies to step indices
+fn resolve_dependencies(steps: &[PipelineStep]) -> Result<Vec<Vec<usize>>, String> {
+    let index: HashMap<&str, usize> = steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| (step.key.as_str(), i))
+        .collect();
+
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| match &step.depends_on {
+            None => Ok(if i == 0 { vec![] } else { vec![i - 1] }),
+            Some(keys) => keys
+                .iter()
+                .map(|key| match index.get(key.as_str()) {
+                    Some(&dep) if dep == i => {
+                        Err(format!("Step '{}' cannot depend on itself", step.key))
+                    }
+                    Some(&dep) => Ok(dep),
+                    None => Err(format!(
+                        "Step '{}' depends on unknown step '{}'",
+                        step.key, key
+                    )),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// All transitive dependencies of a step
+fn ancestors(deps: &[Vec<usize>], step: usize) -> HashSet<usize> {
+    let mut seen = HashSet::new();
+    let mut stack = deps[step].clone();
+    while let Some(dep) = stack.pop() {
+        if seen.insert(dep) {
+            stack.extend(deps[dep].iter().copied());
+        }
+    }
+    seen
+}
+
+/// Validate a pipeline definition and return the resolved dependencies
+fn validate_steps(steps: &[PipelineStep]) -> Result<Vec<Vec<usize>>, String> {
+    if steps.is_empty() {
+        return Err("A pipeline needs at least one step".to_string());
+    }
+
+    let mut keys = HashSet::new();
+    for step in steps {
+        if step.key.is_empty()
+            || !step
+                .key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "Invalid step key '{}': use letters, digits, '-' and '_'",
+                step.key
+            ));
+        }
+        if !keys.insert(step.key.as_str()) {
+            return Err(format!("Duplicate step key '{}'", step.key));
+        }
+    }
+
+    let deps = resolve_dependencies(steps)?;
+
+    // Kahn's algorithm to reject cycles
+    let mut remaining: Vec<usize> = deps.iter().map(|d| d.len()).collect();
+    let mut ready: Vec<usize> = (0..steps.len()).filter(|&i| remaining[i] == 0).collect();
+    let mut visited = 0;
+    while let Some(i) = ready.pop() {
+        visited += 1;
+        for (j, step_deps) in deps.iter().enumerate() {
+            for _ in step_deps.iter().filter(|&&d| d == i) {
+                remaining[j] -= 1;
+                if remaining[j] == 0 {
+                    ready.push(j);
+                }
+            }
+        }
+    }
+    if visited != steps.len() {
+        return Err("Pipeline steps contain a dependency cycle".to_string());
+    }
+
+    // Templates may only use outputs of steps that are guaranteed to have finished
+    for (i, step) in steps.iter().enumerate() {
+        let step_ancestors = ancestors(&deps, i);
+        for reference in template_references(&step.task_template) {
+            let is_ancestor = steps
+                .iter()
+                .position(|s| s.key == reference)
+                .is_some_and(|r| step_ancestors.contains(&r));
+            if !is_ancestor {
+                return Err(format!(
+                    "Step '{}' references the output of '{}', which is not one of its dependencies",
+                    step.key, reference
+                ));
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+/// Steps that can start now and steps that will never run, given the current step statuses
+fn plan_steps(
+    steps: &[PipelineStep],
+    deps: &[Vec<usize>],
+    statuses: &[String],
+) -> (Vec<usize>, Vec<usize>) {
+    let is_blocking_failure =
+        |i: usize| statuses[i] == "failed" && steps[i].on_failure != StepFailurePolicy::Continue;
+
+    let waiting = (0..steps.len()).filter(|&i| statuses[i] == "waiting");
+
+    // A failed step without the continue policy stops the whole pipeline
+    if (0..steps.len()).any(is_blocking_failure) {
+        return (vec![], waiting.collect());
+    }
+
+    let mut ready = Vec::new();
+    let mut skipped = Vec::new();
+    for i in waiting {
+        let settled = deps[i]
+            .iter()
+            .all(|&d| statuses[d] == "completed" || statuses[d] == "failed");
+        let unreachable = deps[i]
+            .iter()
+            .any(|&d| statuses[d] == "skipped" || statuses[d] == "cancelled");
+        if unreachable {
+            skipped.push(i);
+        } else if settled {
+            ready.push(i);
+        }
+    }
+
+    (ready, skipped)
+}
+
+fn load_steps(conn: &Connection, pipeline_id: i64) -> Result<Vec<PipelineStep>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT step_key, agent_id, task_template, model, depends_on, on_failure, max_retries FROM pipeline_steps WHERE pipeline_id = ?1 ORDER BY position",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let steps = stmt
+        .query_map(params![pipeline_id], |row| {
+            let depends_on: Option<String> = row.get(4)?;
+            Ok(PipelineStep {
+                key: row.get(0)?,
+                agent_id: row.get(1)?,
+                task_template: row.get(2)?,
+                model: row.get(3)?,
+                depends_on: depends_on.and_then(|d| serde_json::from_str(&d).ok()),
+                on_failure: StepFailurePolicy::parse(&row.get::<_, String>(5)?),
+                max_retries: row.get::<_, i64>(6)?.max(0) as u32,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(steps)
+}
+
+fn save_steps(conn: &Connection, pipeline_id: i64, steps: &[PipelineStep]) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM pipeline_steps WHERE pipeline_id = ?1",
+        params![pipeline_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    for (position, step) in steps.iter().enumerate() {
+        let depends_on = step
+            .depends_on
+            .as_ref()
+            .map(|d| serde_json::to_string(d).unwrap_or_default());
+        conn.execute(
+            "INSERT INTO pipeline_steps (pipeline_id, position, step_key, agent_id, task_template, model, depends_on, on_failure, max_retries) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                pipeline_id,
+                position as i64,
+                step.key,
+                step.agent_id,
+                step.task_template,
+                step.model,
+                depends_on,
+                step.on_failure.as_str(),
+                step.max_retries as i64
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn load_pipeline(conn: &Connection, id: i64) -> Result<Pipeline, String> {
+    let mut pipeline = conn
+        .query_row(
+            "SELECT id, name, description, created_at, updated_at FROM pipelines WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(Pipeline {
+                    id: Some(row.get(0)?),
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    steps: Vec::new(),
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    pipeline.steps = load_steps(conn, id)?;
+    Ok(pipeline)
+}
+
+/// Validate steps, including that every referenced agent exists
+fn validate_pipeline(conn: &Connection, steps: &[PipelineStep]) -> Result<(), String> {
+    validate_steps(steps)?;
+    for step in steps {
+        load_agent(conn, step.agent_id)
+            .map_err(|_| format!("Step '{}' uses an agent that does not exist", step.key))?;
+    }
+    Ok(())
+}
+
+fn load_pipeline_run(conn: &Connection, id: i64) -> Result<PipelineRun, String> {
+    let mut run = conn
+        .query_row(
+            &format!("SELECT {} FROM pipeline_runs WHERE id = ?1", PIPELINE_RUN_COLUMNS),
+            params![id],
+            |row| {
+                Ok(PipelineRun {
+                    id: row.get(0)?,
+                    pipeline_id: row.get(1)?,
+                    pipeline_name: row.get(2)?,
+                    project_path: row.get(3)?,
+                    status: row.get(4)?,
+                    created_at: row.get(5)?,
+                    completed_at: row.get(6)?,
+                    steps: Vec::new(),
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM pipeline_run_steps WHERE pipeline_run_id = ?1 ORDER BY position",
+            PIPELINE_RUN_STEP_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    run.steps = stmt
+        .query_map(params![id], PipelineRunStep::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(run)
+}
+
+/// List all pipelines
+#[tauri::command]
+pub async fn list_pipelines(db: State<'_, AgentDb>) -> Result<Vec<Pipeline>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let ids = conn
+        .prepare("SELECT id FROM pipelines ORDER BY created_at DESC")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    ids.into_iter().map(|id| load_pipeline(&conn, id)).collect()
+}
+
+/// Get a single pipeline with its steps
+#[tauri::command]
+pub async fn get_pipeline(db: State<'_, AgentDb>, id: i64) -> Result<Pipeline, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_pipeline(&conn, id)
+}
+
+/// Create a new pipeline
+#[tauri::command]
+pub async fn create_pipeline(
+    db: State<'_, AgentDb>,
+    name: String,
+    description: Option<String>,
+    steps: Vec<PipelineStep>,
+) -> Result<Pipeline, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    validate_pipeline(&conn, &steps)?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO pipelines (name, description) VALUES (?1, ?2)",
+        params![name, description],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+    save_steps(&tx, id, &steps)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    load_pipeline(&conn, id)
+}
+
+/// Update a pipeline; runs already in progress keep the definition they started with
+#[tauri::command]
+pub async fn update_pipeline(
+    db: State<'_, AgentDb>,
+    id: i64,
+    name: String,
+    description: Option<String>,
+    steps: Vec<PipelineStep>,
+) -> Result<Pipeline, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    validate_pipeline(&conn, &steps)?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE pipelines SET name = ?1, description = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+        params![name, description, id],
+    )
+    .map_err(|e| e.to_string())?;
+    save_steps(&tx, id, &steps)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    load_pipeline(&conn, id)
+}
+
+/// Delete a pipeline; past pipeline runs are kept
+#[tauri::command]
+pub async fn delete_pipeline(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM pipeline_steps WHERE pipeline_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM pipelines WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Start a pipeline run; root steps are queued right away
+#[tauri::command]
+pub async fn run_pipeline(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+    project_path: String,
+) -> Result<PipelineRun, String> {
+    let run = {
+        let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+        let pipeline = load_pipeline(&conn, id)?;
+        validate_pipeline(&conn, &pipeline.steps)?;
+
+        let definition = serde_json::to_string(&pipeline.steps)
+            .map_err(|e| format!("Failed to serialize pipeline: {}", e))?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO pipeline_runs (pipeline_id, pipeline_name, project_path, status, definition) VALUES (?1, ?2, ?3, 'running', ?4)",
+            params![id, pipeline.name, project_path, definition],
+        )
+        .map_err(|e| e.to_string())?;
+        let run_id = tx.last_insert_rowid();
+
+        for (position, step) in pipeline.steps.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO pipeline_run_steps (pipeline_run_id, position, step_key, agent_id, status) VALUES (?1, ?2, ?3, ?4, 'waiting')",
+                params![run_id, position as i64, step.key, step.agent_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+
+        info!("Started pipeline '{}' as pipeline run {}", pipeline.name, run_id);
+        load_pipeline_run(&conn, run_id)?
+    };
+
+    // The dispatcher advances pipeline runs before picking queued agent runs
+    crate::commands::queue::schedule_dispatch(&app);
+
+    Ok(run)
+}
+
+/// List pipeline runs (optionally filtered by pipeline_id)
+#[tauri::command]
+pub async fn list_pipeline_runs(
+    db: State<'_, AgentDb>,
+    pipeline_id: Option<i64>,
+) -> Result<Vec<PipelineRun>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let ids = match pipeline_id {
+        Some(pid) => conn
+            .prepare("SELECT id FROM pipeline_runs WHERE pipeline_id = ?1 ORDER BY id DESC")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![pid], |row| row.get::<_, i64>(0))?
+                    .collect::<Result<Vec<_>, _>>()
+            }),
+        None => conn
+            .prepare("SELECT id FROM pipeline_runs ORDER BY id DESC")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, i64>(0))?
+                    .collect::<Result<Vec<_>, _>>()
+            }),
+    }
+    .map_err(|e| e.to_string())?;
+
+    ids.into_iter().map(|id| load_pipeline_run(&conn, id)).collect()
+}
+
+/// Get a single pipeline run with the state of every step
+#[tauri::command]
+pub async fn get_pipeline_run(db: State<'_, AgentDb>, id: i64) -> Result<PipelineRun, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_pipeline_run(&conn, id)
+}
+
+/// Cancel a pipeline run and the agent runs of its active steps
+#[tauri::command]
+pub async fn cancel_pipeline_run(app: AppHandle, id: i64) -> Result<(), String> {
+    let active_runs = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+        let updated = conn
+            .execute(
+                "UPDATE pipeline_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'running'",
+                params![id],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Ok(());
+        }
+
+        let active_runs = conn
+            .prepare("SELECT agent_run_id FROM pipeline_run_steps WHERE pipeline_run_id = ?1 AND status = 'running' AND agent_run_id IS NOT NULL")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id], |row| row.get::<_, i64>(0))?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "UPDATE pipeline_run_steps SET status = CASE WHEN status = 'running' THEN 'cancelled' ELSE 'skipped' END, completed_at = CURRENT_TIMESTAMP WHERE pipeline_run_id = ?1 AND status IN ('waiting', 'running')",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        active_runs
+    };
+
+    for run_id in active_runs {
+        if let Err(e) = crate::commands::agents::kill_agent_session(
+            app.clone(),
+            app.state::<AgentDb>(),
+            app.state::<crate::process::ProcessRegistryState>(),
+            run_id,
+        )
+        .await
+        {
+            warn!("Failed to cancel agent run {} of pipeline run {}: {}", run_id, id, e);
+        }
+    }
+
+    info!("Cancelled pipeline run {}", id);
+    let _ = app.emit(&format!("pipeline-run-complete:{}", id), "cancelled");
+
+    Ok(())
+}
+
+/// Record the outcome of steps whose agent run has finished, queue the steps that
+/// became ready and finish pipeline runs with nothing left to do.
+/// Called by the run queue before every dispatch.
+pub(crate) fn advance_pipeline_runs(app: &AppHandle, conn: &Connection) -> Result<(), String> {
+    let active: Vec<(i64, String, String)> = conn
+        .prepare("SELECT id, project_path, definition FROM pipeline_runs WHERE status = 'running'")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    for (pipeline_run_id, project_path, definition) in active {
+        let steps: Vec<PipelineStep> = match serde_json::from_str(&definition) {
+            Ok(steps) => steps,
+            Err(e) => {
+                error!("Invalid definition for pipeline run {}: {}", pipeline_run_id, e);
+                let _ = conn.execute(
+                    "UPDATE pipeline_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+                    params![pipeline_run_id],
+                );
+                continue;
+            }
+        };
+
+        if let Err(e) = advance_pipeline_run(app, conn, pipeline_run_id, &project_path, &steps) {
+            error!("Failed to advance pipeline run {}: {}", pipeline_run_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+fn advance_pipeline_run(
+    app: &AppHandle,
+    conn: &Connection,
+    pipeline_run_id: i64,
+    project_path: &str,
+    steps: &[PipelineStep],
+) -> Result<(), String> {
+    let deps = resolve_dependencies(steps)?;
+    let mut changed = false;
+
+    let run = load_pipeline_run(conn, pipeline_run_id)?;
+    let mut states = run.steps;
+    if states.len() != steps.len() {
+        return Err("Pipeline run steps do not match its definition".to_string());
+    }
+
+    // Collect the outcome of finished agent runs
+    for (i, state) in states.iter_mut().enumerate() {
+        if state.status != "running" {
+            continue;
+        }
+        let Some(agent_run_id) = state.agent_run_id else {
+            continue;
+        };
+
+        let (status, result_text, result_is_error): (String, Option<String>, Option<bool>) = conn
+            .query_row(
+                "SELECT status, result_text, result_is_error FROM agent_runs WHERE id = ?1",
+                params![agent_run_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| e.to_string())?;
+
+        if matches!(status.as_str(), "queued" | "pending" | "running" | "rate_limited") {
+            continue;
+        }
+
+        // The result recorded when the run finished; no text means it ended without a result message
+        let result = result_text.map(|text| (text, result_is_error.unwrap_or(false)));
+
+        let error = match (&status[..], &result) {
+            ("completed", Some((_, false))) => None,
+            ("completed", Some((message, true))) => Some(message.clone()),
+            ("completed", None) => Some("The agent finished without a result message".to_string()),
+            (other, _) => Some(format!("Agent run {}", other)),
+        };
+        let output = result.map(|(message, _)| message);
+
+        let retry = error.is_some()
+            && status != "cancelled"
+            && status != "budget_exceeded"
+            && steps[i].on_failure == StepFailurePolicy::Retry
+            && state.attempts <= steps[i].max_retries as i64;
+
+        if retry {
+            warn!(
+                "Step '{}' of pipeline run {} failed, retrying (attempt {})",
+                state.step_key,
+                pipeline_run_id,
+                state.attempts + 1
+            );
+            state.status = "waiting".to_string();
+            conn.execute(
+                "UPDATE pipeline_run_steps SET status = 'waiting', error = ?1 WHERE id = ?2",
+                params![error, state.id],
+            )
+            .map_err(|e| e.to_string())?;
+        } else {
+            state.status = if error.is_some() { "failed" } else { "completed" }.to_string();
+            state.output = output;
+            conn.execute(
+                "UPDATE pipeline_run_steps SET status = ?1, output = ?2, error = ?3, completed_at = CURRENT_TIMESTAMP WHERE id = ?4",
+                params![state.status, state.output, error, state.id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        changed = true;
+    }
+
+    // Queue steps whose dependencies have settled and skip the ones that can no longer run
+    let statuses: Vec<String> = states.iter().map(|s| s.status.clone()).collect();
+    let (ready, skipped) = plan_steps(steps, &deps, &statuses);
+
+    for i in skipped {
+        states[i].status = "skipped".to_string();
+        conn.execute(
+            "UPDATE pipeline_run_steps SET status = 'skipped', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![states[i].id],
+        )
+        .map_err(|e| e.to_string())?;
+        changed = true;
+    }
+
+    let outputs: HashMap<String, String> = states
+        .iter()
+        .filter_map(|s| s.output.clone().map(|output| (s.step_key.clone(), output)))
+        .collect();
+
+    for i in ready {
+        let step = &steps[i];
+        let queued = load_agent(conn, step.agent_id).and_then(|agent| {
+            insert_queued_run(
+                conn,
+                &agent,
+                &QueuedRunParams {
+                    project_path: project_path.to_string(),
+                    task: render_step_task(&step.task_template, project_path, &outputs, &agent.task_variables),
+                    task_template: Some(step.task_template.clone()),
+                    model: step.model.clone().unwrap_or_else(|| agent.model.clone()),
+                    pipeline_run_id: Some(pipeline_run_id),
+                    ..Default::default()
+                },
+            )
+        });
+
+        match queued {
+            Ok(agent_run_id) => {
+                info!(
+                    "Pipeline run {} queued step '{}' as agent run {}",
+                    pipeline_run_id, step.key, agent_run_id
+                );
+                states[i].status = "running".to_string();
+                conn.execute(
+                    "UPDATE pipeline_run_steps SET status = 'running', agent_run_id = ?1, attempts = attempts + 1 WHERE id = ?2",
+                    params![agent_run_id, states[i].id],
+                )
+                .map_err(|e| e.to_string())?;
+                let _ = app.emit(&format!("agent-queued:{}", agent_run_id), agent_run_id);
+            }
+            Err(e) => {
+                error!("Failed to queue step '{}' of pipeline run {}: {}", step.key, pipeline_run_id, e);
+                states[i].status = "failed".to_string();
+                conn.execute(
+                    "UPDATE pipeline_run_steps SET status = 'failed', error = ?1, completed_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                    params![e, states[i].id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+        changed = true;
+    }
+
+    // Finish the pipeline run once no step is waiting or running
+    let finished = states
+        .iter()
+        .all(|s| s.status != "waiting" && s.status != "running");
+    if finished {
+        let status = if states.iter().all(|s| s.status == "completed") {
+            "completed"
+        } else {
+            "failed"
+        };
+        conn.execute(
+            "UPDATE pipeline_runs SET status = ?1, completed_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![status, pipeline_run_id],
+        )
+        .map_err(|e| e.to_string())?;
+        info!("Pipeline run {} finished with status {}", pipeline_run_id, status);
+        let _ = app.emit(&format!("pipeline-run-complete:{}", pipeline_run_id), status);
+    } else if changed {
+        let _ = app.emit(&format!("pipeline-run-updated:{}", pipeline_run_id), pipeline_run_id);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(key: &str, depends_on: Option<Vec<&str>>, task: &str) -> PipelineStep {
+        PipelineStep {
+            key: key.to_string(),
+            agent_id: 1,
+            task_template: task.to_string(),
+            model: None,
+            depends_on: depends_on.map(|d| d.into_iter().map(String::from).collect()),
+            on_failure: StepFailurePolicy::Stop,
+            max_retries: 0,
+        }
+    }
+
+    fn statuses(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_steps() {
+        // Ordered list: each step depends on the previous one
+        let chain = vec![
+            step("scan", None, "Scan"),
+            step("fix", None, "Fix: {{steps.scan.output}}"),
+            step("test", None, "Verify {{ steps.scan.output }}"),
+        ];
+        assert_eq!(validate_steps(&chain).unwrap(), vec![vec![], vec![0], vec![1]]);
+
+        // Referencing a step that is not an ancestor
+        let parallel = vec![
+            step("a", Some(vec![]), "A"),
+            step("b", Some(vec![]), "{{steps.a.output}}"),
+        ];
+        assert!(validate_steps(&parallel).is_err());
+
+        let cycle = vec![step("a", Some(vec!["b"]), "A"), step("b", Some(vec!["a"]), "B")];
+        assert!(validate_steps(&cycle).is_err());
+
+        let duplicate = vec![step("a", None, "A"), step("a", None, "A")];
+        assert!(validate_steps(&duplicate).is_err());
+    }
+
+    #[test]
+    fn test_render_step_task() {
+        let outputs = HashMap::from([("scan".to_string(), "2 issues".to_string())]);
+        let variables = vec![TaskVariable {
+            name: "branch".to_string(),
+            description: None,
+            default_value: Some("main".to_string()),
+        }];
+        assert_eq!(
+            render_step_task("Fix {{steps.scan.output}} in {{project_path}} on {{branch}} ({{other}})", "/repo", &outputs, &variables),
+            "Fix 2 issues in /repo on main ({{other}})"
+        );
+    }
+
+    #[test]
+    fn test_plan_steps() {
+        let mut steps = vec![
+            step("scan", Some(vec![]), "Scan"),
+            step("lint", Some(vec![]), "Lint"),
+            step("fix", Some(vec!["scan", "lint"]), "Fix"),
+        ];
+        let deps = resolve_dependencies(&steps).unwrap();
+
+        assert_eq!(plan_steps(&steps, &deps, &statuses(&["waiting", "waiting", "waiting"])), (vec![0, 1], vec![]));
+        assert_eq!(plan_steps(&steps, &deps, &statuses(&["completed", "running", "waiting"])), (vec![], vec![]));
+        assert_eq!(plan_steps(&steps, &deps, &statuses(&["completed", "completed", "waiting"])), (vec![2], vec![]));
+
+        // A failed step stops the pipeline unless it is allowed to continue
+        assert_eq!(plan_steps(&steps, &deps, &statuses(&["completed", "failed", "waiting"])), (vec![], vec![2]));
+        steps[1].on_failure = StepFailurePolicy::Continue;
+        assert_eq!(plan_steps(&steps, &deps, &statuses(&["completed", "failed", "waiting"])), (vec![2], vec![]));
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agents::{AgentDb, AgentRun};
+use crate::commands::notifications;
+use crate::commands::webhooks;
+use crate::process::watchdog::{load_process_timeouts, ProcessTimeouts, TIMEOUT_KEYS};
+
+/// Order in which queued runs are dispatched
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    /// Oldest queued run first
+    Fifo,
+    /// Highest priority first, oldest first within the same priority
+    Priority,
+}
+
+/// Concurrency limits for agent runs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueueSettings {
+    /// Maximum number of agent runs executing at the same time
+    pub max_concurrent_runs: u32,
+    /// Optional limit of concurrent runs within the same project path
+    pub max_runs_per_project: Option<u32>,
+    /// Optional limit of concurrent runs of the same agent
+    pub max_runs_per_agent: Option<u32>,
+    pub order: QueueOrder,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_runs: 3,
+            max_runs_per_project: None,
+            max_runs_per_agent: None,
+            order: QueueOrder::Fifo,
+        }
+    }
+}
+
+/// Serialises dispatching so two completions can't start more runs than allowed
+#[derive(Default)]
+pub struct AgentQueueState(pub tokio::sync::Mutex<()>);
+
+/// A run that occupies or waits for an execution slot
+#[derive(Debug, Clone)]
+struct QueueSlot {
+    run_id: i64,
+    agent_id: i64,
+    project_path: String,
+}
+
+/// Load queue settings from app_settings, falling back to defaults
+pub fn load_queue_settings(conn: &Connection) -> QueueSettings {
+    let mut settings = QueueSettings::default();
+
+    let keys = vec![
+        ("queue_max_concurrent", "max_concurrent_runs"),
+        ("queue_max_per_project", "max_runs_per_project"),
+        ("queue_max_per_agent", "max_runs_per_agent"),
+        ("queue_order", "order"),
+    ];
+
+    for (db_key, field) in keys {
+        if let Ok(value) = conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![db_key],
+            |row| row.get::<_, String>(0),
+        ) {
+            match field {
+                "max_concurrent_runs" => {
+                    if let Ok(limit) = value.parse::<u32>() {
+                        settings.max_concurrent_runs = limit.max(1);
+                    }
+                }
+                "max_runs_per_project" => settings.max_runs_per_project = value.parse().ok(),
+                "max_runs_per_agent" => settings.max_runs_per_agent = value.parse().ok(),
+                "order" => {
+                    settings.order = if value == "priority" {
+                        QueueOrder::Priority
+                    } else {
+                        QueueOrder::Fifo
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    settings
+}
+
+/// Get the run queue settings
+#[tauri::command]
+pub async fn get_queue_settings(db: State<'_, AgentDb>) -> Result<QueueSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_queue_settings(&conn))
+}
+
+/// Save the run queue settings and dispatch runs the new limits allow
+#[tauri::command]
+pub async fn save_queue_settings(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    settings: QueueSettings,
+) -> Result<(), String> {
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+        let values = vec![
+            ("queue_max_concurrent", settings.max_concurrent_runs.max(1).to_string()),
+            (
+                "queue_max_per_project",
+                settings.max_runs_per_project.map(|l| l.to_string()).unwrap_or_default(),
+            ),
+            (
+                "queue_max_per_agent",
+                settings.max_runs_per_agent.map(|l| l.to_string()).unwrap_or_default(),
+            ),
+            (
+                "queue_order",
+                match settings.order {
+                    QueueOrder::Fifo => "fifo".to_string(),
+                    QueueOrder::Priority => "priority".to_string(),
+                },
+            ),
+        ];
+
+        for (key, value) in values {
+            conn.execute(
+                "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+        }
+    }
+
+    schedule_dispatch(&app);
+    Ok(())
+}
+
+/// Get the startup, idle and wall-clock timeouts of Claude processes
+#[tauri::command]
+pub async fn get_process_timeouts(db: State<'_, AgentDb>) -> Result<ProcessTimeouts, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    Ok(load_process_timeouts(&conn))
+}
+
+/// Save the process timeouts; they apply to processes started afterwards
+#[tauri::command]
+pub async fn save_process_timeouts(
+    db: State<'_, AgentDb>,
+    timeouts: ProcessTimeouts,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let values = [
+        timeouts.startup_secs,
+        timeouts.idle_secs,
+        timeouts.max_duration_secs,
+    ];
+    for (key, value) in TIMEOUT_KEYS.iter().zip(values) {
+        conn.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+            params![key, value.map(|secs| secs.to_string()).unwrap_or_default()],
+        )
+        .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+    }
+    Ok(())
+}
+
+/// List runs waiting in the queue, in dispatch order
+#[tauri::command]
+pub async fn list_queued_runs(db: State<'_, AgentDb>) -> Result<Vec<AgentRun>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let settings = load_queue_settings(&conn);
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM agent_runs WHERE status = 'queued' ORDER BY {}",
+            crate::commands::agents::AGENT_RUN_COLUMNS,
+            queue_order_clause(settings.order)
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let runs = stmt
+        .query_map([], AgentRun::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(runs)
+}
+
+/// Cancel a run that has not been started yet
+#[tauri::command]
+pub async fn cancel_queued_run(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<bool, String> {
+    let updated = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE agent_runs SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status IN ('queued', 'rate_limited')",
+            params![run_id],
+        )
+        .map_err(|e| e.to_string())?
+    };
+
+    if updated > 0 {
+        info!("Cancelled queued agent run {}", run_id);
+        let _ = app.emit(&format!("agent-cancelled:{}", run_id), true);
+        schedule_dispatch(&app);
+    }
+
+    Ok(updated > 0)
+}
+
+fn queue_order_clause(order: QueueOrder) -> &'static str {
+    match order {
+        QueueOrder::Fifo => "id ASC",
+        QueueOrder::Priority => "priority DESC, id ASC",
+    }
+}
+
+/// Pick the first candidate that fits within the global, per-project and per-agent limits
+fn select_next_run(
+    running: &[QueueSlot],
+    candidates: &[QueueSlot],
+    settings: &QueueSettings,
+) -> Option<i64> {
+    if running.len() >= settings.max_concurrent_runs as usize {
+        return None;
+    }
+
+    candidates
+        .iter()
+        .find(|candidate| {
+            let project_ok = settings.max_runs_per_project.is_none_or(|limit| {
+                running
+                    .iter()
+                    .filter(|slot| slot.project_path == candidate.project_path)
+                    .count()
+                    < limit as usize
+            });
+            let agent_ok = settings.max_runs_per_agent.is_none_or(|limit| {
+                running
+                    .iter()
+                    .filter(|slot| slot.agent_id == candidate.agent_id)
+                    .count()
+                    < limit as usize
+            });
+            project_ok && agent_ok
+        })
+        .map(|candidate| candidate.run_id)
+}
+
+fn load_slots(conn: &Connection, status: &str, order: QueueOrder) -> Result<Vec<QueueSlot>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, agent_id, project_path FROM agent_runs WHERE status = ?1 ORDER BY {}",
+            queue_order_clause(order)
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let slots = stmt
+        .query_map(params![status], |row| {
+            Ok(QueueSlot {
+                run_id: row.get(0)?,
+                agent_id: row.get(1)?,
+                project_path: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(slots)
+}
+
+/// Put runs left 'pending' by a dispatch that never reached their process start, e.g. because
+/// the app quit in between, back on the queue. Runs with a live process are left alone.
+pub fn requeue_orphaned_runs(app: &AppHandle) -> Result<usize, String> {
+    let live_run_ids: HashSet<i64> = app
+        .state::<crate::process::ProcessRegistryState>()
+        .0
+        .get_running_agent_processes()
+        .unwrap_or_default()
+        .iter()
+        .map(|p| p.run_id)
+        .collect();
+
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM agent_runs WHERE status = 'pending'")
+        .map_err(|e| e.to_string())?;
+    let pending = stmt
+        .query_map([], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut requeued = 0;
+    for run_id in pending.into_iter().filter(|id| !live_run_ids.contains(id)) {
+        requeued += conn
+            .execute(
+                "UPDATE agent_runs SET status = 'queued' WHERE id = ?1 AND status = 'pending'",
+                params![run_id],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    if requeued > 0 {
+        info!("Requeued {} run(s) that were never started", requeued);
+    }
+    Ok(requeued)
+}
+
+/// Dispatch queued runs on a background task.
+/// The future is boxed so run monitors can trigger a dispatch without a recursive future type.
+pub fn schedule_dispatch(app: &AppHandle) {
+    let app = app.clone();
+    let dispatch: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(async move {
+        dispatch_queued_runs(&app).await;
+    });
+    tauri::async_runtime::spawn(dispatch);
+}
+
+/// Start as many queued runs as the concurrency limits allow
+pub async fn dispatch_queued_runs(app: &AppHandle) {
+    let queue = app.state::<AgentQueueState>();
+    let _guard = queue.0.lock().await;
+
+    // Commit and remove the worktrees of runs that finished since the last dispatch
+    crate::commands::worktrees::finalize_run_worktrees(app);
+    // Check the assertions of eval cases whose run finished
+    crate::commands::evals::advance_eval_runs(app);
+
+    loop {
+        let next_run = {
+            let db = app.state::<AgentDb>();
+            let registry = app.state::<crate::process::ProcessRegistryState>();
+            let conn = match db.0.lock() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!("Failed to lock database for run queue: {}", e);
+                    return;
+                }
+            };
+            let settings = load_queue_settings(&conn);
+
+            // Finished steps may have unblocked the next steps of a pipeline
+            if let Err(e) = crate::commands::pipelines::advance_pipeline_runs(app, &conn) {
+                error!("Failed to advance pipeline runs: {}", e);
+            }
+
+            // Only count runs that still have a live process; rows left 'running'
+            // by a previous app session must not block the queue forever
+            let live_run_ids: HashSet<i64> = registry
+                .0
+                .get_running_agent_processes()
+                .unwrap_or_default()
+                .iter()
+                .map(|p| p.run_id)
+                .collect();
+
+            let slots = load_slots(&conn, "running", settings.order).and_then(|running| {
+                load_slots(&conn, "queued", settings.order).map(|queued| (running, queued))
+            });
+
+            match slots {
+                Ok((running, queued)) => {
+                    let running: Vec<QueueSlot> = running
+                        .into_iter()
+                        .filter(|slot| live_run_ids.contains(&slot.run_id))
+                        .collect();
+                    select_next_run(&running, &queued, &settings)
+                }
+                Err(e) => {
+                    error!("Failed to load the run queue: {}", e);
+                    None
+                }
+            }
+        };
+
+        let Some(run_id) = next_run else {
+            break;
+        };
+
+        // Mark the run as starting so it is no longer picked from the queue
+        {
+            let db = app.state::<AgentDb>();
+            let conn = match db.0.lock() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!("Failed to lock database for run queue: {}", e);
+                    return;
+                }
+            };
+            let _ = conn.execute(
+                "UPDATE agent_runs SET status = 'pending' WHERE id = ?1",
+                params![run_id],
+            );
+        }
+
+        info!("Dispatching queued agent run {}", run_id);
+        let _ = app.emit(&format!("agent-started:{}", run_id), run_id);
+
+        match crate::commands::agents::start_agent_run(app, run_id).await {
+            Ok(()) => webhooks::notify_run_started(app, run_id),
+            Err(e) => {
+                warn!("Failed to start queued agent run {}: {}", run_id, e);
+                let db = app.state::<AgentDb>();
+                if let Ok(conn) = db.0.lock() {
+                    // Runs cancelled while they were being dispatched keep their status
+                    let _ = conn.execute(
+                        "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'pending'",
+                        params![run_id],
+                    );
+                }
+                let _ = app.emit(&format!("agent-error:{}", run_id), &e);
+                let _ = app.emit(&format!("agent-complete:{}", run_id), false);
+                webhooks::notify_run_finished(app, run_id);
+                notifications::notify_run_finished(app, run_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(run_id: i64, agent_id: i64, project_path: &str) -> QueueSlot {
+        QueueSlot {
+            run_id,
+            agent_id,
+            project_path: project_path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select_next_run_respects_limits() {
+        let settings = QueueSettings {
+            max_concurrent_runs: 2,
+            max_runs_per_project: Some(1),
+            max_runs_per_agent: None,
+            order: QueueOrder::Fifo,
+        };
+        let running = vec![slot(1, 10, "/repo/a")];
+        let queued = vec![slot(2, 11, "/repo/a"), slot(3, 10, "/repo/b")];
+
+        // Run 2 targets a project that is already at its limit
+        assert_eq!(select_next_run(&running, &queued, &settings), Some(3));
+
+        // Global limit reached
+        let running = vec![slot(1, 10, "/repo/a"), slot(3, 10, "/repo/b")];
+        assert_eq!(select_next_run(&running, &queued, &settings), None);
+    }
+
+    #[test]
+    fn test_select_next_run_per_agent_limit() {
+        let settings = QueueSettings {
+            max_concurrent_runs: 5,
+            max_runs_per_project: None,
+            max_runs_per_agent: Some(1),
+            order: QueueOrder::Fifo,
+        };
+        let running = vec![slot(1, 10, "/repo/a")];
+        let queued = vec![slot(2, 10, "/repo/b")];
+
+        assert_eq!(select_next_run(&running, &queued, &settings), None);
+        assert_eq!(select_next_run(&[], &queued, &settings), Some(2));
+    }
+}
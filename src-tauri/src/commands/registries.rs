@@ -0,0 +1,708 @@
+use std::path::Path;
+
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+use crate::commands::agents::{insert_imported_agent, load_agent, parse_agent_export, Agent, AgentDb, AgentExport};
+
+/// Where a registry's agents are listed and downloaded from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RegistrySource {
+    /// A directory of `.claudia.json` files in a GitHub repository
+    Github {
+        owner: String,
+        repo: String,
+        /// Default branch of the repository when unset
+        branch: Option<String>,
+        path: String,
+    },
+    /// A host with a GitHub-compatible contents API, e.g. Gitee (`https://gitee.com/api/v5`)
+    /// or a self-hosted Gitea mirroring the repository
+    Mirror {
+        api_base_url: String,
+        owner: String,
+        repo: String,
+        branch: Option<String>,
+        path: String,
+    },
+    /// A JSON index of agents served over HTTP
+    HttpIndex { url: String },
+    /// A directory of `.claudia.json` files on this machine
+    Local { path: String },
+}
+
+impl RegistrySource {
+    /// The registry every installation starts with
+    pub fn default_source() -> Self {
+        RegistrySource::Github {
+            owner: "getAsterisk".to_string(),
+            repo: "claudia".to_string(),
+            branch: None,
+            path: "cc_agents".to_string(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let required = |value: &str, field: &str| {
+            if value.trim().is_empty() {
+                Err(format!("Registry {} is required", field))
+            } else {
+                Ok(())
+            }
+        };
+        let url = |value: &str| {
+            reqwest::Url::parse(value)
+                .map(|_| ())
+                .map_err(|e| format!("Invalid registry URL '{}': {}", value, e))
+        };
+
+        match self {
+            RegistrySource::Github { owner, repo, .. } => {
+                required(owner, "owner")?;
+                required(repo, "repository")
+            }
+            RegistrySource::Mirror { api_base_url, owner, repo, .. } => {
+                url(api_base_url)?;
+                required(owner, "owner")?;
+                required(repo, "repository")
+            }
+            RegistrySource::HttpIndex { url: index_url } => url(index_url),
+            RegistrySource::Local { path } => {
+                if Path::new(path).is_dir() {
+                    Ok(())
+                } else {
+                    Err(format!("Directory not found: {}", path))
+                }
+            }
+        }
+    }
+}
+
+/// A configured source of shareable agents
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentRegistry {
+    pub id: Option<i64>,
+    pub name: String,
+    pub source: RegistrySource,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+const REGISTRY_COLUMNS: &str = "id, name, source, enabled, created_at";
+
+impl AgentRegistry {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let source: String = row.get(2)?;
+        Ok(AgentRegistry {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            source: serde_json::from_str(&source).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+            })?,
+            enabled: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
+}
+
+/// An agent file offered by a registry
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistryAgentFile {
+    pub registry_id: i64,
+    pub name: String,
+    /// Identifies the agent within its registry
+    pub path: String,
+    /// HTTP URL, or a file path for local registries
+    pub download_url: String,
+    pub size: Option<i64>,
+    /// Content hash reported by the source: the git blob SHA for repositories,
+    /// SHA-256 for local files. Unset if an HTTP index doesn't provide one.
+    pub sha: Option<String>,
+}
+
+/// The agents of one registry
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistryListing {
+    pub registry: AgentRegistry,
+    pub agents: Vec<RegistryAgentFile>,
+    /// The source could not be reached and the listing comes from the offline cache
+    pub from_cache: bool,
+    pub fetched_at: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Entry of a GitHub-style directory contents response
+#[derive(Debug, Deserialize)]
+struct ContentsEntry {
+    name: String,
+    path: String,
+    sha: String,
+    size: i64,
+    download_url: Option<String>,
+    #[serde(rename = "type")]
+    file_type: String,
+}
+
+/// An HTTP index is either `{"agents": [...]}` or a bare array
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HttpIndex {
+    Wrapped { agents: Vec<HttpIndexEntry> },
+    List(Vec<HttpIndexEntry>),
+}
+
+#[derive(Debug, Deserialize)]
+struct HttpIndexEntry {
+    name: String,
+    path: Option<String>,
+    /// Absolute, or relative to the index URL
+    #[serde(alias = "url")]
+    download_url: String,
+    size: Option<i64>,
+    sha: Option<String>,
+}
+
+/// Last listing fetched from a remote registry
+struct CachedListing {
+    etag: Option<String>,
+    agents: Vec<RegistryAgentFile>,
+    fetched_at: String,
+}
+
+/// Result of asking a remote source for its listing
+enum Fetched {
+    Listing {
+        agents: Vec<RegistryAgentFile>,
+        etag: Option<String>,
+    },
+    NotModified,
+}
+
+pub(crate) fn sha256_hex(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn is_agent_file(name: &str) -> bool {
+    name.ends_with(".claudia.json")
+}
+
+pub(crate) fn load_registry(conn: &Connection, id: i64) -> Result<AgentRegistry, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM agent_registries WHERE id = ?1", REGISTRY_COLUMNS),
+        params![id],
+        AgentRegistry::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn load_cache(
+    conn: &Connection,
+    registry_id: i64,
+) -> Result<Option<CachedListing>, String> {
+    let cached: Option<(Option<String>, String, String)> = conn
+        .query_row(
+            "SELECT etag, agents, fetched_at FROM agent_registry_cache WHERE registry_id = ?1",
+            params![registry_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    Ok(cached.and_then(|(etag, agents, fetched_at)| {
+        serde_json::from_str(&agents)
+            .ok()
+            .map(|agents| CachedListing {
+                etag,
+                agents,
+                fetched_at,
+            })
+    }))
+}
+
+/// Parse a GitHub-style contents listing, keeping only agent files
+fn parse_contents(registry_id: i64, body: &str) -> Result<Vec<RegistryAgentFile>, String> {
+    let entries: Vec<ContentsEntry> =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse registry listing: {}", e))?;
+
+    Ok(entries
+        .into_iter()
+        .filter(|e| e.file_type == "file" && is_agent_file(&e.name))
+        .filter_map(|e| {
+            e.download_url.map(|download_url| RegistryAgentFile {
+                registry_id,
+                name: e.name,
+                path: e.path,
+                download_url,
+                size: Some(e.size),
+                sha: Some(e.sha),
+            })
+        })
+        .collect())
+}
+
+/// Parse an HTTP index, resolving relative download URLs against the index URL
+fn parse_http_index(
+    registry_id: i64,
+    index_url: &str,
+    body: &str,
+) -> Result<Vec<RegistryAgentFile>, String> {
+    let index: HttpIndex =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse agent index: {}", e))?;
+    let entries = match index {
+        HttpIndex::Wrapped { agents } => agents,
+        HttpIndex::List(agents) => agents,
+    };
+    let base = reqwest::Url::parse(index_url).map_err(|e| e.to_string())?;
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let download_url = base
+                .join(&entry.download_url)
+                .map_err(|e| format!("Invalid download URL '{}': {}", entry.download_url, e))?;
+            Ok(RegistryAgentFile {
+                registry_id,
+                path: entry.path.unwrap_or_else(|| entry.name.clone()),
+                name: entry.name,
+                download_url: download_url.to_string(),
+                size: entry.size,
+                sha: entry.sha,
+            })
+        })
+        .collect()
+}
+
+fn list_local(registry_id: i64, dir: &str) -> Result<Vec<RegistryAgentFile>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read directory {}: {}", dir, e))?;
+
+    let mut agents = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_file() || !is_agent_file(&name) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        agents.push(RegistryAgentFile {
+            registry_id,
+            path: name.clone(),
+            name,
+            download_url: path.to_string_lossy().to_string(),
+            size: Some(content.len() as i64),
+            sha: Some(sha256_hex(&content)),
+        });
+    }
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(agents)
+}
+
+/// Fetch the listing of a remote source, revalidating the cached copy with its ETag
+async fn fetch_remote(
+    registry_id: i64,
+    source: &RegistrySource,
+    etag: Option<&str>,
+) -> Result<Fetched, String> {
+    let (url, accept) = match source {
+        RegistrySource::Github { owner, repo, branch, path } => {
+            (contents_url("https://api.github.com", owner, repo, branch.as_deref(), path)?, "application/vnd.github+json")
+        }
+        RegistrySource::Mirror { api_base_url, owner, repo, branch, path } => {
+            (contents_url(api_base_url, owner, repo, branch.as_deref(), path)?, "application/json")
+        }
+        RegistrySource::HttpIndex { url } => (url.clone(), "application/json"),
+        RegistrySource::Local { .. } => return Err("Local registries are not fetched".to_string()),
+    };
+
+    let mut request = reqwest::Client::new()
+        .get(&url)
+        .header("Accept", accept)
+        .header("User-Agent", "Claudia-App");
+    if let Some(etag) = etag {
+        request = request.header("If-None-Match", etag);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Registry error ({}): {}", status, error_text));
+    }
+
+    let etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read registry listing: {}", e))?;
+
+    let agents = match source {
+        RegistrySource::HttpIndex { url } => parse_http_index(registry_id, url, &body)?,
+        _ => parse_contents(registry_id, &body)?,
+    };
+    Ok(Fetched::Listing { agents, etag })
+}
+
+fn contents_url(
+    api_base_url: &str,
+    owner: &str,
+    repo: &str,
+    branch: Option<&str>,
+    path: &str,
+) -> Result<String, String> {
+    let mut url = reqwest::Url::parse(&format!(
+        "{}/repos/{}/{}/contents/{}",
+        api_base_url.trim_end_matches('/'),
+        owner,
+        repo,
+        path.trim_matches('/')
+    ))
+    .map_err(|e| e.to_string())?;
+    if let Some(branch) = branch.filter(|b| !b.is_empty()) {
+        url.query_pairs_mut().append_pair("ref", branch);
+    }
+    Ok(url.to_string())
+}
+
+/// Current listing of a registry. Remote listings are cached so they stay available
+/// offline; when the source can't be reached the cached copy is returned.
+pub(crate) async fn registry_listing(
+    db: &State<'_, AgentDb>,
+    registry: AgentRegistry,
+) -> Result<RegistryListing, String> {
+    let registry_id = registry.id.ok_or("Registry has no ID")?;
+
+    if let RegistrySource::Local { path } = &registry.source {
+        let agents = list_local(registry_id, path)?;
+        return Ok(RegistryListing {
+            registry,
+            agents,
+            from_cache: false,
+            fetched_at: Some(chrono::Utc::now().to_rfc3339()),
+            error: None,
+        });
+    }
+
+    let cached = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_cache(&conn, registry_id)?
+    };
+    let etag = cached.as_ref().and_then(|c| c.etag.as_deref());
+
+    match fetch_remote(registry_id, &registry.source, etag).await {
+        Ok(Fetched::Listing { agents, etag }) => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            conn.execute(
+                "INSERT OR REPLACE INTO agent_registry_cache (registry_id, etag, agents, fetched_at) VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
+                params![
+                    registry_id,
+                    etag,
+                    serde_json::to_string(&agents).map_err(|e| e.to_string())?
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            info!("Fetched {} agents from registry '{}'", agents.len(), registry.name);
+            Ok(RegistryListing {
+                registry,
+                agents,
+                from_cache: false,
+                fetched_at: Some(chrono::Utc::now().to_rfc3339()),
+                error: None,
+            })
+        }
+        Ok(Fetched::NotModified) => {
+            let agents = cached
+                .ok_or("Registry returned 304 without a cached listing")?
+                .agents;
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            let _ = conn.execute(
+                "UPDATE agent_registry_cache SET fetched_at = CURRENT_TIMESTAMP WHERE registry_id = ?1",
+                params![registry_id],
+            );
+            Ok(RegistryListing {
+                registry,
+                agents,
+                from_cache: false,
+                fetched_at: Some(chrono::Utc::now().to_rfc3339()),
+                error: None,
+            })
+        }
+        Err(e) => match cached {
+            Some(cached) => {
+                warn!("Using cached listing of registry '{}': {}", registry.name, e);
+                Ok(RegistryListing {
+                    registry,
+                    agents: cached.agents,
+                    from_cache: true,
+                    fetched_at: Some(cached.fetched_at),
+                    error: Some(e),
+                })
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Find an agent in a registry, preferring the cached listing
+pub(crate) async fn find_registry_agent(
+    db: &State<'_, AgentDb>,
+    registry_id: i64,
+    path: &str,
+) -> Result<RegistryAgentFile, String> {
+    let (registry, cached) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (load_registry(&conn, registry_id)?, load_cache(&conn, registry_id)?)
+    };
+
+    if let Some(file) = cached.and_then(|c| c.agents.into_iter().find(|a| a.path == path)) {
+        return Ok(file);
+    }
+
+    registry_listing(db, registry)
+        .await?
+        .agents
+        .into_iter()
+        .find(|a| a.path == path)
+        .ok_or_else(|| format!("Agent '{}' not found in registry", path))
+}
+
+/// Download the raw content of a registry agent
+pub(crate) async fn download_agent_file(
+    db: &State<'_, AgentDb>,
+    file: &RegistryAgentFile,
+) -> Result<String, String> {
+    let is_local = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        matches!(load_registry(&conn, file.registry_id)?.source, RegistrySource::Local { .. })
+    };
+    if is_local {
+        return std::fs::read_to_string(&file.download_url)
+            .map_err(|e| format!("Failed to read agent file: {}", e));
+    }
+
+    let response = reqwest::Client::new()
+        .get(&file.download_url)
+        .header("Accept", "application/json")
+        .header("User-Agent", "Claudia-App")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download agent: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Failed to download agent: HTTP {}", response.status()));
+    }
+
+    response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))
+}
+
+/// List the configured agent registries
+#[tauri::command]
+pub async fn list_agent_registries(db: State<'_, AgentDb>) -> Result<Vec<AgentRegistry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM agent_registries ORDER BY id", REGISTRY_COLUMNS))
+        .map_err(|e| e.to_string())?;
+
+    let registries = stmt
+        .query_map([], AgentRegistry::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(registries)
+}
+
+/// Add an agent registry
+#[tauri::command]
+pub async fn create_agent_registry(
+    db: State<'_, AgentDb>,
+    name: String,
+    source: RegistrySource,
+) -> Result<AgentRegistry, String> {
+    source.validate()?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO agent_registries (name, source) VALUES (?1, ?2)",
+        params![name, serde_json::to_string(&source).map_err(|e| e.to_string())?],
+    )
+    .map_err(|e| e.to_string())?;
+
+    load_registry(&conn, conn.last_insert_rowid())
+}
+
+/// Update an agent registry. Changing the source drops its cached listing.
+#[tauri::command]
+pub async fn update_agent_registry(
+    db: State<'_, AgentDb>,
+    id: i64,
+    name: String,
+    source: RegistrySource,
+    enabled: bool,
+) -> Result<AgentRegistry, String> {
+    source.validate()?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let current = load_registry(&conn, id)?;
+
+    conn.execute(
+        "UPDATE agent_registries SET name = ?1, source = ?2, enabled = ?3 WHERE id = ?4",
+        params![name, serde_json::to_string(&source).map_err(|e| e.to_string())?, enabled, id],
+    )
+    .map_err(|e| e.to_string())?;
+    if current.source != source {
+        conn.execute("DELETE FROM agent_registry_cache WHERE registry_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    load_registry(&conn, id)
+}
+
+/// Delete an agent registry. Agents imported from it are kept.
+#[tauri::command]
+pub async fn delete_agent_registry(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM agent_registry_cache WHERE registry_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM agent_registries WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// List the agents of one registry, or of all enabled registries. A registry that
+/// fails without a cached listing is reported through its `error` instead of failing the call.
+#[tauri::command]
+pub async fn fetch_registry_agents(
+    db: State<'_, AgentDb>,
+    registry_id: Option<i64>,
+) -> Result<Vec<RegistryListing>, String> {
+    let registries = match registry_id {
+        Some(id) => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            vec![load_registry(&conn, id)?]
+        }
+        None => list_agent_registries(db.clone())
+            .await?
+            .into_iter()
+            .filter(|r| r.enabled)
+            .collect(),
+    };
+
+    let mut listings = Vec::new();
+    for registry in registries {
+        let listing = match registry_listing(&db, registry.clone()).await {
+            Ok(listing) => listing,
+            Err(e) => {
+                warn!("Failed to list registry '{}': {}", registry.name, e);
+                RegistryListing {
+                    registry,
+                    agents: Vec::new(),
+                    from_cache: false,
+                    fetched_at: None,
+                    error: Some(e),
+                }
+            }
+        };
+        listings.push(listing);
+    }
+
+    Ok(listings)
+}
+
+/// Fetch and preview an agent from a registry
+#[tauri::command]
+pub async fn fetch_registry_agent_content(
+    db: State<'_, AgentDb>,
+    registry_id: i64,
+    path: String,
+) -> Result<AgentExport, String> {
+    let file = find_registry_agent(&db, registry_id, &path).await?;
+    parse_agent_export(&download_agent_file(&db, &file).await?)
+}
+
+/// Import an agent from a registry, pinning it to the content it was imported from
+#[tauri::command]
+pub async fn import_agent_from_registry(
+    db: State<'_, AgentDb>,
+    registry_id: i64,
+    path: String,
+) -> Result<Agent, String> {
+    info!("Importing agent {} from registry {}", path, registry_id);
+
+    let file = find_registry_agent(&db, registry_id, &path).await?;
+    let content = download_agent_file(&db, &file).await?;
+    let export_data = parse_agent_export(&content)?;
+    let sha = file.sha.clone().unwrap_or_else(|| sha256_hex(&content));
+
+    // Keep what was imported to tell local edits apart from updates at the source
+    let snapshot = serde_json::to_string(&export_data.agent).map_err(|e| e.to_string())?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let agent = insert_imported_agent(&conn, export_data.agent)?;
+    let agent_id = agent.id.ok_or("Agent has no ID")?;
+    conn.execute(
+        "UPDATE agents SET source_registry_id = ?1, source_path = ?2, source_url = ?3, source_sha = ?4, latest_source_sha = ?4, source_snapshot = ?5, imported_at = CURRENT_TIMESTAMP WHERE id = ?6",
+        params![registry_id, file.path, file.download_url, sha, snapshot, agent_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    load_agent(&conn, agent_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http_index() {
+        let body = r#"{"agents": [
+            {"name": "reviewer.claudia.json", "download_url": "agents/reviewer.claudia.json", "sha": "abc"},
+            {"name": "tester.claudia.json", "url": "https://cdn.example.com/tester.claudia.json"}
+        ]}"#;
+        let agents = parse_http_index(1, "https://mirror.example.com/claudia/index.json", body).unwrap();
+
+        assert_eq!(agents.len(), 2);
+        assert_eq!(
+            agents[0].download_url,
+            "https://mirror.example.com/claudia/agents/reviewer.claudia.json"
+        );
+        assert_eq!(agents[0].path, "reviewer.claudia.json");
+        assert_eq!(agents[1].download_url, "https://cdn.example.com/tester.claudia.json");
+        assert_eq!(agents[1].sha, None);
+
+        // A bare array is accepted too
+        assert_eq!(parse_http_index(1, "https://example.com/", r#"[]"#).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_contents_url() {
+        assert_eq!(
+            contents_url("https://gitee.com/api/v5/", "acme", "agents", Some("release"), "/cc_agents/").unwrap(),
+            "https://gitee.com/api/v5/repos/acme/agents/contents/cc_agents?ref=release"
+        );
+    }
+
+    #[test]
+    fn test_source_serialization() {
+        let json = serde_json::to_value(RegistrySource::default_source()).unwrap();
+        assert_eq!(json["type"], "github");
+        assert_eq!(json["path"], "cc_agents");
+    }
+}
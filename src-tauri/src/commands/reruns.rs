@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use log::info;
+use rusqlite::{params_from_iter, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::agents::{
+    insert_queued_run, load_agent, AgentDb, AgentRun, QueuedRunParams, AGENT_RUN_COLUMNS,
+    FINISHED_STATUSES,
+};
+use crate::commands::task_templates;
+use crate::process::budget::RunBudget;
+
+/// Upper bound on runs enqueued by one bulk rerun without an explicit limit
+const DEFAULT_BULK_LIMIT: i64 = 100;
+
+/// Changes applied to a run when it is repeated; unset fields keep the original value
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RerunOverrides {
+    /// New task; rendered against the agent's task variable defaults
+    pub task: Option<String>,
+    pub model: Option<String>,
+    pub project_path: Option<String>,
+    pub priority: Option<i64>,
+    pub budget: Option<RunBudget>,
+    pub use_worktree: Option<bool>,
+    /// Run with the agent's current configuration instead of the original revision
+    #[serde(default)]
+    pub use_latest_revision: bool,
+}
+
+/// Selects finished runs for a bulk rerun
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AgentRunFilter {
+    pub agent_id: Option<i64>,
+    /// Statuses to include; defaults to all finished statuses
+    #[serde(default)]
+    pub statuses: Vec<String>,
+    /// Only runs created within this many hours
+    pub within_hours: Option<i64>,
+    pub project_path: Option<String>,
+    pub model: Option<String>,
+    /// Include runs that were already rerun
+    #[serde(default)]
+    pub include_rerun: bool,
+    pub limit: Option<i64>,
+}
+
+/// WHERE clause and its parameters for the runs selected by `filter`
+fn filter_clause(filter: &AgentRunFilter) -> Result<(String, Vec<Value>), String> {
+    let statuses: Vec<&str> = if filter.statuses.is_empty() {
+        FINISHED_STATUSES.to_vec()
+    } else {
+        filter.statuses.iter().map(String::as_str).collect()
+    };
+    if let Some(status) = statuses.iter().find(|s| !FINISHED_STATUSES.contains(s)) {
+        return Err(format!("Only finished runs can be rerun, not '{}' runs", status));
+    }
+
+    let mut clauses = vec![format!(
+        "status IN ({})",
+        vec!["?"; statuses.len()].join(", ")
+    )];
+    let mut values: Vec<Value> = statuses.iter().map(|s| Value::Text(s.to_string())).collect();
+
+    if let Some(agent_id) = filter.agent_id {
+        clauses.push("agent_id = ?".to_string());
+        values.push(Value::Integer(agent_id));
+    }
+    if let Some(hours) = filter.within_hours {
+        clauses.push("created_at >= datetime('now', ?)".to_string());
+        values.push(Value::Text(format!("-{} hours", hours.max(0))));
+    }
+    if let Some(project_path) = &filter.project_path {
+        clauses.push("project_path = ?".to_string());
+        values.push(Value::Text(project_path.clone()));
+    }
+    if let Some(model) = &filter.model {
+        clauses.push("model = ?".to_string());
+        values.push(Value::Text(model.clone()));
+    }
+    if !filter.include_rerun {
+        clauses.push(
+            "NOT EXISTS (SELECT 1 FROM agent_runs r WHERE r.rerun_of = agent_runs.id)".to_string(),
+        );
+    }
+    Ok((clauses.join(" AND "), values))
+}
+
+/// Finished runs matching a filter, oldest first
+fn find_runs(conn: &Connection, filter: &AgentRunFilter) -> Result<Vec<AgentRun>, String> {
+    let (clause, mut values) = filter_clause(filter)?;
+    values.push(Value::Integer(filter.limit.unwrap_or(DEFAULT_BULK_LIMIT).max(0)));
+
+    let query = format!(
+        "SELECT {} FROM agent_runs WHERE {} ORDER BY created_at ASC, id ASC LIMIT ?",
+        AGENT_RUN_COLUMNS, clause
+    );
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let runs = stmt
+        .query_map(params_from_iter(values), AgentRun::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(runs)
+}
+
+/// Queue a copy of `run` with `overrides` applied and return the new run's ID
+fn enqueue_rerun(conn: &Connection, run: &AgentRun, overrides: &RerunOverrides) -> Result<i64, String> {
+    let run_id = run.id.ok_or("Run has no ID")?;
+    let agent = load_agent(conn, run.agent_id)
+        .map_err(|e| format!("Cannot rerun run {}: {}", run_id, e))?;
+
+    let (task, task_template) = match &overrides.task {
+        Some(template) => (
+            task_templates::render_task(template, &agent.task_variables, &HashMap::new())?,
+            Some(template.clone()),
+        ),
+        None => (run.task.clone(), run.task_template.clone()),
+    };
+
+    insert_queued_run(
+        conn,
+        &agent,
+        &QueuedRunParams {
+            project_path: overrides
+                .project_path
+                .clone()
+                .unwrap_or_else(|| run.project_path.clone()),
+            task,
+            task_template,
+            model: overrides.model.clone().unwrap_or_else(|| run.model.clone()),
+            priority: overrides.priority.unwrap_or(run.priority),
+            budget: overrides.budget.unwrap_or(run.budget),
+            use_worktree: overrides.use_worktree.unwrap_or(run.worktree.is_some()),
+            revision_id: if overrides.use_latest_revision {
+                None
+            } else {
+                run.revision_id
+            },
+            rerun_of: Some(run_id),
+            ..Default::default()
+        },
+    )
+}
+
+/// Queue a run with the same revision, task, model and project as an earlier run
+#[tauri::command]
+pub async fn rerun_agent_run(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    run_id: i64,
+    overrides: Option<RerunOverrides>,
+) -> Result<i64, String> {
+    let new_run_id = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let run = conn
+            .query_row(
+                &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
+                [run_id],
+                AgentRun::from_row,
+            )
+            .map_err(|e| format!("Agent run not found: {}", e))?;
+        if !FINISHED_STATUSES.contains(&run.status.as_str()) {
+            return Err(format!("Run {} is still {}", run_id, run.status));
+        }
+        enqueue_rerun(&conn, &run, &overrides.unwrap_or_default())?
+    };
+    info!("Queued run {} as a rerun of run {}", new_run_id, run_id);
+
+    let _ = app.emit(&format!("agent-queued:{}", new_run_id), new_run_id);
+    crate::commands::queue::schedule_dispatch(&app);
+    Ok(new_run_id)
+}
+
+/// Queue a rerun of every finished run matching `filter` and return the new run IDs
+#[tauri::command]
+pub async fn rerun_agent_runs(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    filter: AgentRunFilter,
+    overrides: Option<RerunOverrides>,
+) -> Result<Vec<i64>, String> {
+    let overrides = overrides.unwrap_or_default();
+    let new_run_ids = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        // All or nothing, so a failed bulk rerun can simply be retried
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let runs = find_runs(&tx, &filter)?;
+        let new_run_ids = runs
+            .iter()
+            .map(|run| enqueue_rerun(&tx, run, &overrides))
+            .collect::<Result<Vec<_>, _>>()?;
+        tx.commit().map_err(|e| e.to_string())?;
+        new_run_ids
+    };
+    info!("Queued {} reruns", new_run_ids.len());
+
+    for run_id in &new_run_ids {
+        let _ = app.emit(&format!("agent-queued:{}", run_id), run_id);
+    }
+    if !new_run_ids.is_empty() {
+        crate::commands::queue::schedule_dispatch(&app);
+    }
+    Ok(new_run_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_clause() {
+        let filter = AgentRunFilter {
+            statuses: vec!["failed".to_string()],
+            within_hours: Some(24),
+            include_rerun: true,
+            ..Default::default()
+        };
+        let (clause, values) = filter_clause(&filter).unwrap();
+
+        assert_eq!(clause, "status IN (?) AND created_at >= datetime('now', ?)");
+        assert_eq!(
+            values,
+            vec![Value::Text("failed".to_string()), Value::Text("-24 hours".to_string())]
+        );
+
+        let (clause, values) = filter_clause(&AgentRunFilter::default()).unwrap();
+        let placeholders = vec!["?"; FINISHED_STATUSES.len()].join(", ");
+        assert!(clause.starts_with(&format!("status IN ({}) AND NOT EXISTS", placeholders)));
+        assert_eq!(values.len(), FINISHED_STATUSES.len());
+    }
+
+    #[test]
+    fn test_filter_clause_rejects_active_statuses() {
+        let filter = AgentRunFilter {
+            statuses: vec!["running".to_string()],
+            ..Default::default()
+        };
+        assert!(filter_clause(&filter).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use log::info;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agent_export::{parse_json_list, serialize_json_list};
+use crate::commands::agents::{AgentDb, AgentRun, AGENT_RUN_COLUMNS, FINISHED_STATUSES};
+
+/// A user's judgement of a run
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RunAnnotation {
+    /// 1 (bad) to 5 (good)
+    pub rating: Option<i64>,
+    pub notes: Option<String>,
+    /// Lowercase labels such as "hallucinated" or "perfect"
+    pub tags: Vec<String>,
+    pub annotated_at: String,
+}
+
+impl RunAnnotation {
+    /// Build from a row at `index` with the columns annotated_at, annotation_rating,
+    /// annotation_notes, annotation_tags; None if the run was never annotated
+    pub(crate) fn from_row(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Option<Self>> {
+        let Some(annotated_at) = row.get::<_, Option<String>>(index)? else {
+            return Ok(None);
+        };
+        Ok(Some(RunAnnotation {
+            annotated_at,
+            rating: row.get(index + 1)?,
+            notes: row.get(index + 2)?,
+            tags: parse_json_list(row.get(index + 3)?),
+        }))
+    }
+}
+
+/// Run counts and ratings of one agent revision
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RunStats {
+    /// None for runs from before revisions were recorded
+    pub revision_id: Option<i64>,
+    pub revision: Option<i64>,
+    pub total_runs: i64,
+    pub finished_runs: i64,
+    pub completed_runs: i64,
+    /// Completed share of finished runs; None until a run has finished
+    pub success_rate: Option<f64>,
+    pub rated_runs: i64,
+    pub average_rating: Option<f64>,
+}
+
+/// Aggregates of an agent's runs, overall and per revision
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentRunStats {
+    pub agent_id: i64,
+    pub overall: RunStats,
+    /// Newest revision first
+    pub revisions: Vec<RunStats>,
+}
+
+impl RunStats {
+    fn finish(mut self, rating_sum: f64) -> Self {
+        self.success_rate = (self.finished_runs > 0)
+            .then(|| self.completed_runs as f64 / self.finished_runs as f64);
+        self.average_rating = (self.rated_runs > 0).then(|| rating_sum / self.rated_runs as f64);
+        self
+    }
+}
+
+/// Combine per-revision stats into the agent's overall stats
+fn combine_stats(revisions: &[RunStats]) -> RunStats {
+    let mut overall = RunStats::default();
+    let mut rating_sum = 0.0;
+    for stats in revisions {
+        overall.total_runs += stats.total_runs;
+        overall.finished_runs += stats.finished_runs;
+        overall.completed_runs += stats.completed_runs;
+        overall.rated_runs += stats.rated_runs;
+        rating_sum += stats.average_rating.unwrap_or(0.0) * stats.rated_runs as f64;
+    }
+    overall.finish(rating_sum)
+}
+
+/// Trim, lowercase and deduplicate tags, keeping their order
+pub(crate) fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+fn load_run(conn: &Connection, run_id: i64) -> Result<AgentRun, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
+        params![run_id],
+        AgentRun::from_row,
+    )
+    .map_err(|e| format!("Agent run not found: {}", e))
+}
+
+/// Rate, describe and tag a run. Clearing every field removes the annotation.
+#[tauri::command]
+pub async fn set_agent_run_annotation(
+    db: State<'_, AgentDb>,
+    run_id: i64,
+    rating: Option<i64>,
+    notes: Option<String>,
+    tags: Vec<String>,
+) -> Result<AgentRun, String> {
+    if let Some(rating) = rating {
+        if !(1..=5).contains(&rating) {
+            return Err("Rating must be between 1 and 5".to_string());
+        }
+    }
+    let notes = notes.filter(|n| !n.trim().is_empty());
+    let tags = normalize_tags(tags);
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let updated = if rating.is_none() && notes.is_none() && tags.is_empty() {
+        conn.execute(
+            "UPDATE agent_runs SET annotated_at = NULL, annotation_rating = NULL, annotation_notes = NULL, annotation_tags = NULL WHERE id = ?1",
+            params![run_id],
+        )
+    } else {
+        conn.execute(
+            "UPDATE agent_runs SET annotated_at = CURRENT_TIMESTAMP, annotation_rating = ?1, annotation_notes = ?2, annotation_tags = ?3 WHERE id = ?4",
+            params![rating, notes, serialize_json_list(&tags), run_id],
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Agent run not found".to_string());
+    }
+    info!(
+        "Annotated run {} (rating: {:?}, tags: {:?})",
+        run_id, rating, tags
+    );
+
+    load_run(&conn, run_id)
+}
+
+/// All tags used on runs, most used first
+#[tauri::command]
+pub async fn list_agent_run_tags(db: State<'_, AgentDb>) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.value FROM agent_runs r, json_each(r.annotation_tags) t
+             WHERE r.annotation_tags IS NOT NULL
+             GROUP BY t.value ORDER BY COUNT(*) DESC, t.value",
+        )
+        .map_err(|e| e.to_string())?;
+    let tags = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+/// Success rate and average rating of an agent's runs, overall and per revision
+#[tauri::command]
+pub async fn get_agent_run_stats(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+) -> Result<AgentRunStats, String> {
+    let finished_statuses = FINISHED_STATUSES
+        .iter()
+        .map(|s| format!("'{}'", s))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT r.revision_id, v.revision, COUNT(*),
+                    SUM(r.status IN ({})), SUM(r.status = 'completed'),
+                    COUNT(r.annotation_rating), TOTAL(r.annotation_rating)
+             FROM agent_runs r LEFT JOIN agent_revisions v ON v.id = r.revision_id
+             WHERE r.agent_id = ?1
+             GROUP BY r.revision_id
+             ORDER BY v.revision DESC",
+            finished_statuses
+        ))
+        .map_err(|e| e.to_string())?;
+    let revisions = stmt
+        .query_map(params![agent_id], |row| {
+            let stats = RunStats {
+                revision_id: row.get(0)?,
+                revision: row.get(1)?,
+                total_runs: row.get(2)?,
+                finished_runs: row.get(3)?,
+                completed_runs: row.get(4)?,
+                rated_runs: row.get(5)?,
+                ..Default::default()
+            };
+            Ok(stats.finish(row.get(6)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(AgentRunStats {
+        agent_id,
+        overall: combine_stats(&revisions),
+        revisions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " Perfect ".to_string(),
+            "perfect".to_string(),
+            "".to_string(),
+            "hallucinated".to_string(),
+        ];
+        assert_eq!(normalize_tags(tags), vec!["perfect", "hallucinated"]);
+    }
+
+    #[test]
+    fn test_combine_stats() {
+        let first = RunStats {
+            revision_id: Some(1),
+            total_runs: 4,
+            finished_runs: 4,
+            completed_runs: 1,
+            rated_runs: 2,
+            ..Default::default()
+        }
+        .finish(4.0);
+        let second = RunStats {
+            revision_id: Some(2),
+            total_runs: 3,
+            finished_runs: 2,
+            completed_runs: 2,
+            rated_runs: 1,
+            ..Default::default()
+        }
+        .finish(5.0);
+
+        assert_eq!(first.success_rate, Some(0.25));
+        assert_eq!(first.average_rating, Some(2.0));
+
+        let overall = combine_stats(&[first, second]);
+        assert_eq!(overall.total_runs, 7);
+        assert_eq!(overall.success_rate, Some(0.5));
+        assert_eq!(overall.average_rating, Some(3.0));
+        assert_eq!(RunStats::default().finish(0.0).success_rate, None);
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::checkpoint::manager::CheckpointManager;
+use crate::checkpoint::storage::CheckpointStorage;
+use crate::checkpoint::{is_skipped_dir, FileSnapshot};
+use crate::commands::agent_revisions::diff_lines;
+use crate::commands::agents::AgentDb;
+
+/// Above this many line pairs, line stats are approximated instead of diffed
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A file an agent run added, modified or deleted
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunFileChange {
+    /// Relative to the project
+    pub path: String,
+    /// 'added', 'modified' or 'deleted'
+    pub change: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// Outcome of reverting a run's changes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunRestoreResult {
+    pub run_id: i64,
+    pub files_restored: usize,
+    pub warnings: Vec<String>,
+}
+
+fn claude_dir() -> Result<PathBuf, String> {
+    Ok(dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude"))
+}
+
+/// Checkpoint project and session a run's snapshot is stored under, next to Claude's own
+fn checkpoint_ids(project_path: &str, run_id: i64) -> (String, String) {
+    (
+        project_path.replace('/', "-"),
+        format!("agent-run-{}", run_id),
+    )
+}
+
+/// Snapshot the project before a run starts and return the checkpoint ID
+pub(crate) async fn snapshot_run_project(
+    project_path: &str,
+    run_id: i64,
+) -> Result<String, String> {
+    let (project_id, session_id) = checkpoint_ids(project_path, run_id);
+    let manager = CheckpointManager::new(
+        project_id,
+        session_id,
+        PathBuf::from(project_path),
+        claude_dir()?,
+    )
+    .await
+    .map_err(|e| format!("Failed to prepare checkpoint: {}", e))?;
+    let result = manager
+        .create_checkpoint(Some(format!("Before agent run {}", run_id)), None)
+        .await
+        .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
+    for warning in &result.warnings {
+        warn!("Snapshot of run {}: {}", run_id, warning);
+    }
+    info!(
+        "Snapshot {} files of {} before run {}",
+        result.files_processed, project_path, run_id
+    );
+    Ok(result.checkpoint.id)
+}
+
+fn load_snapshots(
+    claude_dir: PathBuf,
+    project_path: &str,
+    run_id: i64,
+    checkpoint_id: &str,
+) -> Result<Vec<FileSnapshot>, String> {
+    let (project_id, session_id) = checkpoint_ids(project_path, run_id);
+    let (_, snapshots, _) = CheckpointStorage::new(claude_dir)
+        .load_checkpoint(&project_id, &session_id, checkpoint_id)
+        .map_err(|e| format!("Failed to load snapshot: {}", e))?;
+    Ok(snapshots)
+}
+
+/// Files of a project the way checkpoints see them: hidden, dependency and build directories are skipped
+fn collect_project_files(dir: &Path, base: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let skipped = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(is_skipped_dir);
+            if !skipped {
+                collect_project_files(&path, base, files);
+            }
+        } else if path.is_file() {
+            if let Ok(relative) = path.strip_prefix(base) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+}
+
+/// Snapshots hold text only; binary files are stored empty and can't be compared or restored
+fn is_unreadable(snapshot: &FileSnapshot) -> bool {
+    snapshot.content.is_empty() && snapshot.size > 0
+}
+
+/// Lines added and removed between two versions of a file
+fn line_stats(old: &str, new: &str) -> (usize, usize) {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    if old.len().saturating_mul(new.len()) <= MAX_DIFF_CELLS {
+        let diff = diff_lines(&old.join("\n"), &new.join("\n"));
+        let added = diff.iter().filter(|line| line.kind == "added").count();
+        let removed = diff.iter().filter(|line| line.kind == "removed").count();
+        return (added, removed);
+    }
+
+    // Too large to diff: count lines that appear more often on one side
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in old {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in new {
+        *counts.entry(line).or_default() += 1;
+    }
+    let added = counts
+        .values()
+        .filter(|c| **c > 0)
+        .map(|c| *c as usize)
+        .sum();
+    let removed = counts
+        .values()
+        .filter(|c| **c < 0)
+        .map(|c| c.unsigned_abs())
+        .sum();
+    (added, removed)
+}
+
+/// Compare the project with the snapshot taken before the run
+fn compare_with_snapshot(snapshots: &[FileSnapshot], project_path: &Path) -> Vec<RunFileChange> {
+    let before: HashMap<&Path, &FileSnapshot> = snapshots
+        .iter()
+        .filter(|s| !s.is_deleted)
+        .map(|s| (s.file_path.as_path(), s))
+        .collect();
+    let mut current = Vec::new();
+    collect_project_files(project_path, project_path, &mut current);
+    let current_set: HashSet<&Path> = current.iter().map(PathBuf::as_path).collect();
+
+    let mut changes = Vec::new();
+    for path in &current {
+        let content = fs::read_to_string(project_path.join(path)).unwrap_or_default();
+        let change = match before.get(path.as_path()) {
+            None => {
+                let (additions, _) = line_stats("", &content);
+                ("added", additions, 0)
+            }
+            Some(snapshot) if is_unreadable(snapshot) || snapshot.content == content => continue,
+            Some(snapshot) => {
+                let (additions, deletions) = line_stats(&snapshot.content, &content);
+                ("modified", additions, deletions)
+            }
+        };
+        changes.push(RunFileChange {
+            path: path.to_string_lossy().to_string(),
+            change: change.0.to_string(),
+            additions: change.1,
+            deletions: change.2,
+        });
+    }
+    for (path, snapshot) in &before {
+        if !current_set.contains(path) {
+            changes.push(RunFileChange {
+                path: path.to_string_lossy().to_string(),
+                change: "deleted".to_string(),
+                additions: 0,
+                deletions: snapshot.content.lines().count(),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Compute and store the files a finished run changed relative to its snapshot
+pub(crate) fn record_file_changes(db_path: &Path, run_id: i64) {
+    let conn = match Connection::open(db_path) {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!(
+                "❌ Failed to open database to record file changes of run {}: {}",
+                run_id, e
+            );
+            return;
+        }
+    };
+    let run = conn.query_row(
+        "SELECT project_path, checkpoint_id FROM agent_runs WHERE id = ?1",
+        params![run_id],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+    );
+    let (project_path, checkpoint_id) = match run {
+        Ok((project_path, Some(checkpoint_id))) => (project_path, checkpoint_id),
+        Ok(_) => return,
+        Err(e) => {
+            error!(
+                "❌ Failed to load run {} to record its file changes: {}",
+                run_id, e
+            );
+            return;
+        }
+    };
+
+    let changes = claude_dir()
+        .and_then(|dir| load_snapshots(dir, &project_path, run_id, &checkpoint_id))
+        .map(|snapshots| compare_with_snapshot(&snapshots, Path::new(&project_path)));
+    let changes = match changes {
+        Ok(changes) => changes,
+        Err(e) => {
+            error!("❌ Failed to compute file changes of run {}: {}", run_id, e);
+            return;
+        }
+    };
+
+    match serde_json::to_string(&changes) {
+        Ok(json) => {
+            if let Err(e) = conn.execute(
+                "UPDATE agent_runs SET file_changes = ?1 WHERE id = ?2",
+                params![json, run_id],
+            ) {
+                error!("❌ Failed to record file changes of run {}: {}", run_id, e);
+            } else {
+                info!("Run {} changed {} files", run_id, changes.len());
+            }
+        }
+        Err(e) => error!(
+            "❌ Failed to serialize file changes of run {}: {}",
+            run_id, e
+        ),
+    }
+}
+
+/// Put the files a run changed back to their state before it started
+fn restore_files(
+    project_path: &Path,
+    snapshots: &[FileSnapshot],
+    changes: &[RunFileChange],
+) -> (usize, Vec<String>) {
+    let before: HashMap<&Path, &FileSnapshot> = snapshots
+        .iter()
+        .filter(|s| !s.is_deleted)
+        .map(|s| (s.file_path.as_path(), s))
+        .collect();
+    let mut restored = 0;
+    let mut warnings = Vec::new();
+
+    for change in changes {
+        let full_path = project_path.join(&change.path);
+        let result = if change.change == "added" {
+            match fs::remove_file(&full_path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                other => other,
+            }
+        } else {
+            let Some(snapshot) = before.get(Path::new(&change.path)) else {
+                warnings.push(format!("{}: not in the snapshot", change.path));
+                continue;
+            };
+            if is_unreadable(snapshot) {
+                warnings.push(format!("{}: binary files can't be restored", change.path));
+                continue;
+            }
+            full_path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&full_path, &snapshot.content))
+                .and_then(|_| {
+                    #[cfg(unix)]
+                    if let Some(mode) = snapshot.permissions {
+                        use std::os::unix::fs::PermissionsExt;
+                        fs::set_permissions(&full_path, fs::Permissions::from_mode(mode))?;
+                    }
+                    Ok(())
+                })
+        };
+        match result {
+            Ok(()) => restored += 1,
+            Err(e) => warnings.push(format!("{}: {}", change.path, e)),
+        }
+    }
+    (restored, warnings)
+}
+
+/// Revert the files a finished run added, modified or deleted to their state before
+/// the run. Later edits to those files are overwritten; other files are left alone.
+#[tauri::command]
+pub async fn restore_agent_run_changes(
+    db: State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<RunRestoreResult, String> {
+    let (project_path, checkpoint_id, changes) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let (status, project_path, checkpoint_id, changes, restored_at) = conn
+            .query_row(
+                "SELECT status, project_path, checkpoint_id, file_changes, changes_restored_at FROM agent_runs WHERE id = ?1",
+                params![run_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                    ))
+                },
+            )
+            .map_err(|e| format!("Agent run not found: {}", e))?;
+        if ["queued", "pending", "running", "rate_limited"].contains(&status.as_str()) {
+            return Err(format!("Run {} is still {}", run_id, status));
+        }
+        if let Some(restored_at) = restored_at {
+            return Err(format!(
+                "Changes of run {} were already restored at {}",
+                run_id, restored_at
+            ));
+        }
+        let (Some(checkpoint_id), Some(changes)) = (checkpoint_id, changes) else {
+            return Err("Run has no snapshot to restore".to_string());
+        };
+        let changes: Vec<RunFileChange> =
+            serde_json::from_str(&changes).map_err(|e| e.to_string())?;
+        (project_path, checkpoint_id, changes)
+    };
+
+    let snapshots = load_snapshots(claude_dir()?, &project_path, run_id, &checkpoint_id)?;
+    let (files_restored, warnings) = restore_files(Path::new(&project_path), &snapshots, &changes);
+    info!(
+        "Restored {} files changed by run {} ({} warnings)",
+        files_restored,
+        run_id,
+        warnings.len()
+    );
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agent_runs SET changes_restored_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![run_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(RunRestoreResult {
+        run_id,
+        files_restored,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_stats() {
+        assert_eq!(line_stats("a\nb\nc\n", "a\nx\nc\nd\n"), (2, 1));
+        assert_eq!(line_stats("", "one\ntwo\n"), (2, 0));
+        assert_eq!(line_stats("same\n", "same\n"), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_compare_and_restore() {
+        let claude = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn a() {}\n").unwrap();
+        fs::write(root.join("README.md"), "hello\n").unwrap();
+        fs::create_dir_all(root.join("node_modules/left-pad")).unwrap();
+        fs::write(root.join("node_modules/left-pad/index.js"), "module.exports = 1;\n").unwrap();
+
+        let project_path = root.to_string_lossy().to_string();
+        let (project_id, session_id) = checkpoint_ids(&project_path, 1);
+        let manager = CheckpointManager::new(
+            project_id,
+            session_id,
+            root.to_path_buf(),
+            claude.path().to_path_buf(),
+        )
+        .await
+        .unwrap();
+        let checkpoint_id = manager
+            .create_checkpoint(None, None)
+            .await
+            .unwrap()
+            .checkpoint
+            .id;
+
+        fs::write(root.join("src/lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        fs::remove_file(root.join("README.md")).unwrap();
+        fs::write(root.join("new.txt"), "x\n").unwrap();
+        fs::write(root.join("node_modules/left-pad/index.js"), "module.exports = 2;\n").unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("target/debug/app"), "bin\n").unwrap();
+
+        let snapshots = load_snapshots(
+            claude.path().to_path_buf(),
+            &project_path,
+            1,
+            &checkpoint_id,
+        )
+        .unwrap();
+        assert!(snapshots.iter().all(|s| !s.file_path.starts_with("node_modules")));
+        let changes = compare_with_snapshot(&snapshots, root);
+        let summary: Vec<(&str, &str, usize, usize)> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.change.as_str(), c.additions, c.deletions))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("README.md", "deleted", 0, 1),
+                ("new.txt", "added", 1, 0),
+                ("src/lib.rs", "modified", 1, 0),
+            ]
+        );
+
+        let (restored, warnings) = restore_files(root, &snapshots, &changes);
+        assert_eq!((restored, warnings.len()), (3, 0));
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "fn a() {}\n"
+        );
+        assert!(root.join("README.md").exists());
+        assert!(!root.join("new.txt").exists());
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use log::{error, info};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::commands::agent_export::{parse_json_list, serialize_json_list, validate_against_schema};
+use crate::commands::agent_revisions::load_revision;
+use crate::commands::agents::AgentDb;
+
+/// Final outcome of a run, taken from the `result` message of its stream-json output
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RunResult {
+    /// Final answer of the agent; None if the run ended without a result message
+    pub result: Option<String>,
+    pub is_error: bool,
+    pub num_turns: Option<i64>,
+    pub total_cost_usd: Option<f64>,
+    pub duration_ms: Option<i64>,
+    /// Files the run wrote or edited through its tools, relative to the project where possible
+    pub files_changed: Vec<String>,
+    /// Whether the answer matched the agent's output schema; None if it has none
+    pub output_valid: Option<bool>,
+    pub output_errors: Vec<String>,
+}
+
+impl RunResult {
+    /// Build from a row at `index` with the columns result_is_error, result_text,
+    /// result_num_turns, result_cost_usd, result_duration_ms, result_files,
+    /// result_output_valid, result_output_errors; None until the run has finished
+    pub(crate) fn from_row(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Option<Self>> {
+        let Some(is_error) = row.get::<_, Option<bool>>(index)? else {
+            return Ok(None);
+        };
+        Ok(Some(RunResult {
+            is_error,
+            result: row.get(index + 1)?,
+            num_turns: row.get(index + 2)?,
+            total_cost_usd: row.get(index + 3)?,
+            duration_ms: row.get(index + 4)?,
+            files_changed: parse_json_list(row.get(index + 5)?),
+            output_valid: row.get(index + 6)?,
+            output_errors: parse_json_list(row.get(index + 7)?),
+        }))
+    }
+
+    /// Extract the result from the stream-json output of a run
+    pub(crate) fn from_stream(output: &str, project_path: &str) -> Self {
+        let mut run_result = RunResult {
+            is_error: true,
+            ..Default::default()
+        };
+        let mut files = BTreeSet::new();
+
+        for line in output.lines() {
+            let Ok(json) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            match json.get("type").and_then(|t| t.as_str()) {
+                Some("assistant") => collect_changed_files(&json, project_path, &mut files),
+                Some("result") => {
+                    run_result.result = json.get("result").and_then(|r| r.as_str()).map(String::from);
+                    run_result.is_error = json.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false);
+                    run_result.num_turns = json.get("num_turns").and_then(|n| n.as_i64());
+                    run_result.total_cost_usd = json
+                        .get("total_cost_usd")
+                        .or_else(|| json.get("cost_usd"))
+                        .and_then(|c| c.as_f64());
+                    run_result.duration_ms = json.get("duration_ms").and_then(|d| d.as_i64());
+                }
+                _ => {}
+            }
+        }
+
+        run_result.files_changed = files.into_iter().collect();
+        run_result
+    }
+
+    /// Validate the answer against an output schema, accepting a fenced ```json block
+    fn validate_output(&mut self, schema: &Value) {
+        let answer = self.result.as_deref().map(extract_json).unwrap_or_default();
+        let errors = match serde_json::from_str::<Value>(answer) {
+            Ok(value) => validate_against_schema(schema, &value),
+            Err(e) => vec![format!("answer is not valid JSON: {}", e)],
+        };
+        self.output_valid = Some(errors.is_empty());
+        self.output_errors = errors;
+    }
+}
+
+/// Paths written by Write, Edit, MultiEdit and NotebookEdit tool calls in a message
+fn collect_changed_files(message: &Value, project_path: &str, files: &mut BTreeSet<String>) {
+    let Some(content) = message
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+    else {
+        return;
+    };
+
+    for item in content {
+        if item.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
+            continue;
+        }
+        let name = item.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+        let key = match name.to_lowercase().as_str() {
+            "write" | "edit" | "multiedit" => "file_path",
+            "notebookedit" => "notebook_path",
+            _ => continue,
+        };
+        if let Some(path) = item.get("input").and_then(|i| i.get(key)).and_then(|p| p.as_str()) {
+            let relative = Path::new(path)
+                .strip_prefix(project_path)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| path.to_string());
+            files.insert(relative);
+        }
+    }
+}
+
+/// The JSON document in an answer: the contents of a ```json fence if present, else the whole text
+fn extract_json(answer: &str) -> &str {
+    let trimmed = answer.trim();
+    if let Some(start) = trimmed.find("```") {
+        let after_fence = &trimmed[start + 3..];
+        let body_start = after_fence.find('\n').map(|i| i + 1).unwrap_or(0);
+        let body = &after_fence[body_start..];
+        if let Some(end) = body.find("```") {
+            return body[..end].trim();
+        }
+    }
+    trimmed
+}
+
+/// Parse and record the result of a finished run, validating it against the output schema of the
+/// agent revision it ran with (the agent's current schema for runs without a revision)
+pub(crate) fn record_run_result(db_path: &Path, run_id: i64, output: &str) {
+    let conn = match Connection::open(db_path) {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("❌ Failed to open database to record result of run {}: {}", run_id, e);
+            return;
+        }
+    };
+
+    let run = conn.query_row(
+        "SELECT COALESCE(r.worktree_path, r.project_path), r.revision_id, a.output_schema FROM agent_runs r LEFT JOIN agents a ON a.id = r.agent_id WHERE r.id = ?1",
+        params![run_id],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        },
+    );
+    let (project_path, revision_id, agent_schema) = match run {
+        Ok(run) => run,
+        Err(e) => {
+            error!("❌ Failed to load run {} to record its result: {}", run_id, e);
+            return;
+        }
+    };
+
+    let output_schema = match revision_id {
+        Some(revision_id) => match load_revision(&conn, revision_id) {
+            Ok(revision) => revision.output_schema,
+            Err(e) => {
+                error!("❌ Failed to load revision {} of run {}: {}", revision_id, run_id, e);
+                agent_schema
+            }
+        },
+        None => agent_schema,
+    };
+
+    let mut run_result = RunResult::from_stream(output, &project_path);
+    if let Some(schema) = output_schema.and_then(|s| serde_json::from_str::<Value>(&s).ok()) {
+        run_result.validate_output(&schema);
+    }
+
+    if let Err(e) = conn.execute(
+        "UPDATE agent_runs SET result_is_error = ?1, result_text = ?2, result_num_turns = ?3, result_cost_usd = ?4, result_duration_ms = ?5, result_files = ?6, result_output_valid = ?7, result_output_errors = ?8 WHERE id = ?9",
+        params![
+            run_result.is_error,
+            run_result.result,
+            run_result.num_turns,
+            run_result.total_cost_usd,
+            run_result.duration_ms,
+            serialize_json_list(&run_result.files_changed),
+            run_result.output_valid,
+            serialize_json_list(&run_result.output_errors),
+            run_id
+        ],
+    ) {
+        error!("❌ Failed to record result of run {}: {}", run_id, e);
+    } else {
+        info!(
+            "Recorded result of run {} (error: {}, {} files changed)",
+            run_id,
+            run_result.is_error,
+            run_result.files_changed.len()
+        );
+    }
+}
+
+/// Set or clear the JSON schema an agent's final answer is validated against
+#[tauri::command]
+pub async fn set_agent_output_schema(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    output_schema: Option<String>,
+) -> Result<(), String> {
+    let output_schema = output_schema.filter(|s| !s.trim().is_empty());
+    if let Some(schema) = &output_schema {
+        let schema: Value =
+            serde_json::from_str(schema).map_err(|e| format!("Invalid output schema: {}", e))?;
+        if !schema.is_object() {
+            return Err("Invalid output schema: expected a JSON object".to_string());
+        }
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agents SET output_schema = ?1 WHERE id = ?2",
+        params![output_schema, agent_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &str = r#"{"type":"system","subtype":"init","session_id":"abc"}
+{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Write","input":{"file_path":"/repo/src/new.rs","content":""}},{"type":"tool_use","name":"Read","input":{"file_path":"/repo/README.md"}}]}}
+{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","input":{"file_path":"/repo/src/lib.rs"}}]}}
+{"type":"result","subtype":"success","is_error":false,"duration_ms":5300,"num_turns":4,"result":"```json\n{\"summary\":\"done\"}\n```","total_cost_usd":0.042}"#;
+
+    #[test]
+    fn test_from_stream() {
+        let run_result = RunResult::from_stream(STREAM, "/repo");
+
+        assert!(!run_result.is_error);
+        assert_eq!(run_result.num_turns, Some(4));
+        assert_eq!(run_result.duration_ms, Some(5300));
+        assert_eq!(run_result.total_cost_usd, Some(0.042));
+        assert_eq!(run_result.files_changed, vec!["src/lib.rs", "src/new.rs"]);
+        assert!(RunResult::from_stream("", "/repo").is_error);
+    }
+
+    #[test]
+    fn test_validate_output() {
+        let mut run_result = RunResult::from_stream(STREAM, "/repo");
+        run_result.validate_output(&serde_json::json!({
+            "type": "object",
+            "required": ["summary"],
+            "properties": { "summary": { "type": "string" } }
+        }));
+        assert_eq!(run_result.output_valid, Some(true));
+
+        run_result.validate_output(&serde_json::json!({ "type": "object", "required": ["risk"] }));
+        assert_eq!(run_result.output_valid, Some(false));
+        assert_eq!(run_result.output_errors, vec!["document: missing required field 'risk'"]);
+    }
+
+    #[test]
+    fn test_record_run_result_uses_revision_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("agents.db");
+        let conn = Connection::open(&db_path).unwrap();
+        crate::commands::agents::create_schema(&conn).unwrap();
+
+        // The run was queued with a revision expecting a summary; the agent now expects a risk
+        conn.execute(
+            "INSERT INTO agents (id, name, icon, system_prompt, output_schema) VALUES (1, 'Reviewer', 'bot', '', ?1)",
+            params![r#"{"type":"object","required":["risk"]}"#],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO agent_revisions (id, agent_id, revision, name, icon, system_prompt, model, output_schema) VALUES (7, 1, 1, 'Reviewer', 'bot', '', 'sonnet', ?1)",
+            params![r#"{"type":"object","required":["summary"]}"#],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO agent_runs (id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, revision_id) VALUES (3, 1, 'Reviewer', 'bot', 'Review', 'sonnet', '/repo', '', 7)",
+            [],
+        )
+        .unwrap();
+
+        record_run_result(&db_path, 3, STREAM);
+
+        let (output_valid, cost): (Option<bool>, Option<f64>) = conn
+            .query_row(
+                "SELECT result_output_valid, result_cost_usd FROM agent_runs WHERE id = 3",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(output_valid, Some(true));
+        assert_eq!(cost, Some(0.042));
+    }
+}
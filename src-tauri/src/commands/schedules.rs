@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local};
+use log::info;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::commands::agents::AgentDb;
+use crate::scheduler::cron::CronSchedule;
+
+/// What to do with occurrences that were missed while the app was not running
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop missed occurrences and wait for the next one
+    Skip,
+    /// Run once for any number of missed occurrences
+    RunOnce,
+    /// Run once per missed occurrence (capped)
+    RunAll,
+}
+
+impl CatchUpPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatchUpPolicy::Skip => "skip",
+            CatchUpPolicy::RunOnce => "run_once",
+            CatchUpPolicy::RunAll => "run_all",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "skip" => CatchUpPolicy::Skip,
+            "run_all" => CatchUpPolicy::RunAll,
+            _ => CatchUpPolicy::RunOnce,
+        }
+    }
+}
+
+/// A cron schedule that triggers an agent run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentSchedule {
+    pub id: Option<i64>,
+    pub agent_id: i64,
+    pub name: String,
+    pub cron_expression: String,
+    pub project_path: String,
+    pub task: String,
+    pub model: Option<String>, // Falls back to the agent's model when unset
+    pub enabled: bool,
+    pub catch_up_policy: CatchUpPolicy,
+    pub last_run_at: Option<String>,
+    pub next_run_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Columns selected for every `AgentSchedule` query, in the order expected by `AgentSchedule::from_row`
+pub(crate) const SCHEDULE_COLUMNS: &str = "id, agent_id, name, cron_expression, project_path, task, model, enabled, catch_up_policy, last_run_at, next_run_at, created_at, updated_at";
+
+impl AgentSchedule {
+    pub(crate) fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(AgentSchedule {
+            id: Some(row.get(0)?),
+            agent_id: row.get(1)?,
+            name: row.get(2)?,
+            cron_expression: row.get(3)?,
+            project_path: row.get(4)?,
+            task: row.get(5)?,
+            model: row.get(6)?,
+            enabled: row.get::<_, bool>(7).unwrap_or(true),
+            catch_up_policy: CatchUpPolicy::parse(&row.get::<_, String>(8)?),
+            last_run_at: row.get(9)?,
+            next_run_at: row.get(10)?,
+            created_at: row.get(11)?,
+            updated_at: row.get(12)?,
+        })
+    }
+}
+
+/// Load a single schedule by ID
+pub(crate) fn load_schedule(conn: &Connection, id: i64) -> Result<AgentSchedule, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM agent_schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+        params![id],
+        AgentSchedule::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Validate a cron expression and compute its next run time from now
+fn next_run_from_now(cron_expression: &str) -> Result<Option<String>, String> {
+    let cron = CronSchedule::parse(cron_expression)?;
+    Ok(cron.next_after(Local::now()).map(|next| next.to_rfc3339()))
+}
+
+/// List schedules (optionally filtered by agent_id)
+#[tauri::command]
+pub async fn list_agent_schedules(
+    db: State<'_, AgentDb>,
+    agent_id: Option<i64>,
+) -> Result<Vec<AgentSchedule>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let query = if agent_id.is_some() {
+        format!(
+            "SELECT {} FROM agent_schedules WHERE agent_id = ?1 ORDER BY created_at DESC",
+            SCHEDULE_COLUMNS
+        )
+    } else {
+        format!(
+            "SELECT {} FROM agent_schedules ORDER BY created_at DESC",
+            SCHEDULE_COLUMNS
+        )
+    };
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+
+    let schedules = if let Some(aid) = agent_id {
+        stmt.query_map(params![aid], AgentSchedule::from_row)
+    } else {
+        stmt.query_map(params![], AgentSchedule::from_row)
+    }
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(schedules)
+}
+
+/// Create a new schedule for an agent
+#[tauri::command]
+pub async fn create_agent_schedule(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    name: String,
+    cron_expression: String,
+    project_path: String,
+    task: String,
+    model: Option<String>,
+    enabled: Option<bool>,
+    catch_up_policy: Option<CatchUpPolicy>,
+) -> Result<AgentSchedule, String> {
+    let next_run_at = next_run_from_now(&cron_expression)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO agent_schedules (agent_id, name, cron_expression, project_path, task, model, enabled, catch_up_policy, next_run_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            agent_id,
+            name,
+            cron_expression.trim(),
+            project_path,
+            task,
+            model,
+            enabled.unwrap_or(true),
+            catch_up_policy.unwrap_or(CatchUpPolicy::RunOnce).as_str(),
+            next_run_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    load_schedule(&conn, conn.last_insert_rowid())
+}
+
+/// Update an existing schedule; the next run time is recomputed from now
+#[tauri::command]
+pub async fn update_agent_schedule(
+    db: State<'_, AgentDb>,
+    id: i64,
+    name: String,
+    cron_expression: String,
+    project_path: String,
+    task: String,
+    model: Option<String>,
+    enabled: bool,
+    catch_up_policy: CatchUpPolicy,
+) -> Result<AgentSchedule, String> {
+    let next_run_at = next_run_from_now(&cron_expression)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE agent_schedules SET name = ?1, cron_expression = ?2, project_path = ?3, task = ?4, model = ?5, enabled = ?6, catch_up_policy = ?7, next_run_at = ?8, updated_at = CURRENT_TIMESTAMP WHERE id = ?9",
+        params![
+            name,
+            cron_expression.trim(),
+            project_path,
+            task,
+            model,
+            enabled,
+            catch_up_policy.as_str(),
+            next_run_at,
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    load_schedule(&conn, id)
+}
+
+/// Delete a schedule; runs it already triggered are kept
+#[tauri::command]
+pub async fn delete_agent_schedule(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM agent_schedules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Trigger a schedule immediately without changing its next run time
+#[tauri::command]
+pub async fn run_agent_schedule_now(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+) -> Result<i64, String> {
+    let run_id = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let schedule = load_schedule(&conn, id)?;
+        crate::scheduler::queue_schedule_run(&conn, &schedule)?
+    };
+
+    info!("Manually triggered schedule {} as run {}", id, run_id);
+    crate::commands::queue::schedule_dispatch(&app);
+
+    Ok(run_id)
+}
+
+/// Validate a cron expression and preview its next occurrences
+#[tauri::command]
+pub async fn preview_cron_schedule(
+    cron_expression: String,
+    count: Option<usize>,
+) -> Result<Vec<String>, String> {
+    let cron = CronSchedule::parse(&cron_expression)?;
+    let mut occurrences = Vec::new();
+    let mut cursor: DateTime<Local> = Local::now();
+
+    for _ in 0..count.unwrap_or(5).min(50) {
+        match cron.next_after(cursor) {
+            Some(next) => {
+                occurrences.push(next.to_rfc3339());
+                cursor = next;
+            }
+            None => break,
+        }
+    }
+
+    Ok(occurrences)
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use dirs;
 use log::{debug, error, info};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -43,7 +44,9 @@ struct CommandFrontmatter {
 }
 
 /// Parse a markdown file with optional YAML frontmatter
-fn parse_markdown_with_frontmatter(content: &str) -> Result<(Option<CommandFrontmatter>, String)> {
+pub(crate) fn parse_markdown_with_frontmatter<T: DeserializeOwned>(
+    content: &str,
+) -> Result<(Option<T>, String)> {
     let lines: Vec<&str> = content.lines().collect();
     
     // Check if the file starts with YAML frontmatter
@@ -67,7 +70,7 @@ fn parse_markdown_with_frontmatter(content: &str) -> Result<(Option<CommandFront
         let body_content = lines[(end + 1)..].join("\n");
         
         // Parse YAML
-        match serde_yaml::from_str::<CommandFrontmatter>(&frontmatter_content) {
+        match serde_yaml::from_str::<T>(&frontmatter_content) {
             Ok(frontmatter) => Ok((Some(frontmatter), body_content)),
             Err(e) => {
                 debug!("Failed to parse frontmatter: {}", e);
@@ -81,6 +84,36 @@ fn parse_markdown_with_frontmatter(content: &str) -> Result<(Option<CommandFront
     }
 }
 
+/// Build the markdown of a command file, with frontmatter if there is metadata
+pub(crate) fn command_file_content(
+    description: Option<&str>,
+    allowed_tools: &[String],
+    content: &str,
+) -> String {
+    let mut full_content = String::new();
+    
+    // Add frontmatter if we have metadata
+    if description.is_some() || !allowed_tools.is_empty() {
+        full_content.push_str("---\n");
+        
+        if let Some(desc) = description {
+            full_content.push_str(&format!("description: {}\n", desc));
+        }
+        
+        if !allowed_tools.is_empty() {
+            full_content.push_str("allowed-tools:\n");
+            for tool in allowed_tools {
+                full_content.push_str(&format!("  - {}\n", tool));
+            }
+        }
+        
+        full_content.push_str("---\n\n");
+    }
+    
+    full_content.push_str(content);
+    full_content
+}
+
 /// Extract command name and namespace from file path
 fn extract_command_info(file_path: &Path, base_path: &Path) -> Result<(String, Option<String>)> {
     let relative_path = file_path
@@ -124,7 +157,7 @@ fn load_command_from_file(
         .context("Failed to read command file")?;
     
     // Parse frontmatter
-    let (frontmatter, body) = parse_markdown_with_frontmatter(&content)?;
+    let (frontmatter, body) = parse_markdown_with_frontmatter::<CommandFrontmatter>(&content)?;
     
     // Extract command info
     let (name, namespace) = extract_command_info(file_path, base_path)?;
@@ -383,27 +416,7 @@ pub async fn slash_command_save(
     file_path = file_path.join(format!("{}.md", name));
     
     // Build content with frontmatter
-    let mut full_content = String::new();
-    
-    // Add frontmatter if we have metadata
-    if description.is_some() || !allowed_tools.is_empty() {
-        full_content.push_str("---\n");
-        
-        if let Some(desc) = &description {
-            full_content.push_str(&format!("description: {}\n", desc));
-        }
-        
-        if !allowed_tools.is_empty() {
-            full_content.push_str("allowed-tools:\n");
-            for tool in &allowed_tools {
-                full_content.push_str(&format!("  - {}\n", tool));
-            }
-        }
-        
-        full_content.push_str("---\n\n");
-    }
-    
-    full_content.push_str(&content);
+    let full_content = command_file_content(description.as_deref(), &allowed_tools, &content);
     
     // Write file
     fs::write(&file_path, &full_content)
@@ -445,6 +445,12 @@ pub async fn storage_reset_database(app: AppHandle) -> Result<(), String> {
         // Drop tables - order doesn't matter with foreign keys disabled
         conn.execute("DROP TABLE IF EXISTS agent_runs", [])
             .map_err(|e| format!("Failed to drop agent_runs table: {}", e))?;
+        conn.execute("DROP TABLE IF EXISTS agent_schedules", [])
+            .map_err(|e| format!("Failed to drop agent_schedules table: {}", e))?;
+        for table in ["agent_comparison_runs", "agent_comparisons", "eval_run_cases", "eval_runs", "eval_cases", "eval_suites", "pipeline_run_steps", "pipeline_runs", "pipeline_steps", "pipelines", "agent_revisions", "native_subagent_links", "agent_env_vars", "agent_run_batches", "agent_registry_cache", "agent_registries", "agent_library", "webhook_deliveries", "webhooks", "session_resumes"] {
+            conn.execute(&format!("DROP TABLE IF EXISTS {}", table), [])
+                .map_err(|e| format!("Failed to drop {} table: {}", table, e))?;
+        }
         conn.execute("DROP TABLE IF EXISTS agents", [])
             .map_err(|e| format!("Failed to drop agents table: {}", e))?;
         conn.execute("DROP TABLE IF EXISTS app_settings", [])
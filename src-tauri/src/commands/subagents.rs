@@ -0,0 +1,517 @@
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agent_revisions::ensure_current_revision;
+use crate::commands::agents::{load_agent, Agent, AgentDb};
+use crate::commands::registries::sha256_hex;
+use crate::commands::slash_commands::parse_markdown_with_frontmatter;
+
+/// Frontmatter of a Claude Code subagent file (`.claude/agents/<name>.md`)
+#[derive(Debug, Serialize, Deserialize)]
+struct SubagentFrontmatter {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    /// Comma-separated tool names; lists are accepted when reading
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_tools")]
+    tools: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+}
+
+fn deserialize_tools<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match Option::<serde_yaml::Value>::deserialize(deserializer)? {
+        Some(serde_yaml::Value::String(tools)) => Some(tools),
+        Some(serde_yaml::Value::Sequence(tools)) => Some(
+            tools
+                .iter()
+                .filter_map(|t| t.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => None,
+    })
+}
+
+/// A subagent markdown file and its sync state with the agents table
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NativeSubagent {
+    pub file_path: String,
+    /// "project" or "user"
+    pub scope: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub tools: Option<String>,
+    pub model: Option<String>,
+    pub modified_at: Option<String>,
+    /// Agent the file is linked to, if it was imported or exported before
+    pub agent_id: Option<i64>,
+    /// 'unlinked', 'in_sync', 'file_changed', 'agent_changed', 'conflict', 'missing'
+    pub sync_status: String,
+}
+
+/// Outcome of a sync between subagent files and agents
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SubagentSyncReport {
+    /// Files imported into new or linked agents
+    pub imported: Vec<String>,
+    /// Files rewritten from their linked agent
+    pub exported: Vec<String>,
+    /// Files and agents that both changed since the last sync; nothing was written
+    pub conflicts: Vec<NativeSubagent>,
+    /// Linked files that no longer exist
+    pub missing: Vec<NativeSubagent>,
+}
+
+/// A file linked to an agent, with the state of both at the last sync
+struct SubagentLink {
+    agent_id: i64,
+    description: Option<String>,
+    tools: Option<String>,
+    file_mtime: Option<i64>,
+    /// Hash of the agent's exported fields; unset for links last synced before it was
+    /// recorded, whose agent side counts as unchanged until the next sync
+    agent_hash: Option<String>,
+}
+
+/// Subagent names are lowercase letters, digits and hyphens
+fn subagent_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn agents_dir(scope: &str, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        "user" => Ok(dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(".claude")
+            .join("agents")),
+        "project" => Ok(Path::new(project_path.ok_or("Project path required for project scope")?)
+            .join(".claude")
+            .join("agents")),
+        _ => Err("Invalid scope. Must be 'project' or 'user'".to_string()),
+    }
+}
+
+fn scope_of(file_path: &Path) -> String {
+    match agents_dir("user", None) {
+        Ok(user_dir) if file_path.starts_with(&user_dir) => "user".to_string(),
+        _ => "project".to_string(),
+    }
+}
+
+/// Modification time in milliseconds since the epoch
+fn file_mtime(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let millis = modified.duration_since(std::time::UNIX_EPOCH).ok()?.as_millis();
+    Some(millis as i64)
+}
+
+fn format_mtime(millis: Option<i64>) -> Option<String> {
+    millis
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .map(|time| time.to_rfc3339())
+}
+
+fn read_subagent(path: &Path) -> Result<(SubagentFrontmatter, String), String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let (frontmatter, body) = parse_markdown_with_frontmatter::<SubagentFrontmatter>(&content)
+        .map_err(|e| e.to_string())?;
+    let frontmatter = frontmatter.ok_or_else(|| {
+        format!("{} has no valid frontmatter with a name", path.display())
+    })?;
+    Ok((frontmatter, body.trim().to_string()))
+}
+
+fn write_subagent(path: &Path, frontmatter: &SubagentFrontmatter, prompt: &str) -> Result<(), String> {
+    let yaml = serde_yaml::to_string(frontmatter).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create agents directory: {}", e))?;
+    }
+    std::fs::write(path, format!("---\n{}---\n\n{}\n", yaml, prompt.trim()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Hash of the fields an agent contributes to its subagent file, so that writes to
+/// other columns of the agent don't count as changes
+fn exported_hash(agent: &Agent, description: &Option<String>, tools: &Option<String>) -> String {
+    let fields = serde_json::json!([
+        subagent_name(&agent.name),
+        description,
+        tools,
+        agent.model,
+        agent.system_prompt.trim(),
+    ]);
+    sha256_hex(&fields.to_string())
+}
+
+fn load_link(conn: &Connection, file_path: &str) -> Result<Option<SubagentLink>, String> {
+    conn.query_row(
+        "SELECT agent_id, description, tools, file_mtime, agent_hash FROM native_subagent_links WHERE file_path = ?1",
+        params![file_path],
+        |row| {
+            Ok(SubagentLink {
+                agent_id: row.get(0)?,
+                description: row.get(1)?,
+                tools: row.get(2)?,
+                file_mtime: row.get(3)?,
+                agent_hash: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Record that a file and an agent are in sync as of now
+fn save_link(
+    conn: &Connection,
+    file_path: &Path,
+    agent_id: i64,
+    frontmatter: &SubagentFrontmatter,
+) -> Result<(), String> {
+    let agent = load_agent(conn, agent_id)?;
+    conn.execute(
+        "INSERT INTO native_subagent_links (file_path, agent_id, scope, description, tools, file_mtime, agent_hash, synced_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, CURRENT_TIMESTAMP)
+         ON CONFLICT(file_path) DO UPDATE SET agent_id = ?2, scope = ?3, description = ?4, tools = ?5, file_mtime = ?6, agent_hash = ?7, synced_at = CURRENT_TIMESTAMP",
+        params![
+            file_path.to_string_lossy(),
+            agent_id,
+            scope_of(file_path),
+            frontmatter.description,
+            frontmatter.tools,
+            file_mtime(file_path),
+            exported_hash(&agent, &frontmatter.description, &frontmatter.tools)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Sync state of a linked file
+fn link_status(conn: &Connection, path: &Path, link: &SubagentLink) -> String {
+    if !path.exists() {
+        return "missing".to_string();
+    }
+    let file_changed = file_mtime(path) != link.file_mtime;
+    let agent_changed = match (load_agent(conn, link.agent_id), &link.agent_hash) {
+        (Ok(agent), Some(hash)) => exported_hash(&agent, &link.description, &link.tools) != *hash,
+        _ => false,
+    };
+
+    match (file_changed, agent_changed) {
+        (true, true) => "conflict",
+        (true, false) => "file_changed",
+        (false, true) => "agent_changed",
+        (false, false) => "in_sync",
+    }
+    .to_string()
+}
+
+fn describe(conn: &Connection, path: &Path) -> Result<NativeSubagent, String> {
+    let file_path = path.to_string_lossy().to_string();
+    let link = load_link(conn, &file_path)?;
+    let parsed = if path.exists() { Some(read_subagent(path)?) } else { None };
+
+    let sync_status = match &link {
+        Some(link) => link_status(conn, path, link),
+        None => "unlinked".to_string(),
+    };
+    let name = parsed
+        .as_ref()
+        .map(|(fm, _)| fm.name.clone())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+    Ok(NativeSubagent {
+        scope: scope_of(path),
+        name,
+        description: parsed
+            .as_ref()
+            .and_then(|(fm, _)| fm.description.clone())
+            .or_else(|| link.as_ref().and_then(|l| l.description.clone())),
+        tools: parsed
+            .as_ref()
+            .and_then(|(fm, _)| fm.tools.clone())
+            .or_else(|| link.as_ref().and_then(|l| l.tools.clone())),
+        model: parsed.and_then(|(fm, _)| fm.model),
+        modified_at: format_mtime(file_mtime(path)),
+        agent_id: link.map(|l| l.agent_id),
+        file_path,
+        sync_status,
+    })
+}
+
+/// Create or update the agent linked to a subagent file from its contents
+fn import_file(conn: &Connection, path: &Path) -> Result<Agent, String> {
+    let (frontmatter, prompt) = read_subagent(path)?;
+    // "inherit" uses the main conversation's model; agents need a concrete one
+    let model = frontmatter.model.clone().filter(|m| m != "inherit");
+    let linked = load_link(conn, &path.to_string_lossy())?
+        .map(|link| link.agent_id)
+        .filter(|id| load_agent(conn, *id).is_ok());
+
+    let agent_id = match linked {
+        Some(agent_id) => {
+            let current = load_agent(conn, agent_id)?;
+            ensure_current_revision(conn, &current)?;
+            conn.execute(
+                "UPDATE agents SET system_prompt = ?1, model = COALESCE(?2, model) WHERE id = ?3",
+                params![prompt, model, agent_id],
+            )
+            .map_err(|e| e.to_string())?;
+            agent_id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO agents (name, icon, system_prompt, model) VALUES (?1, 'bot', ?2, COALESCE(?3, 'sonnet'))",
+                params![frontmatter.name, prompt, model],
+            )
+            .map_err(|e| format!("Failed to create agent: {}", e))?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    let agent = load_agent(conn, agent_id)?;
+    ensure_current_revision(conn, &agent)?;
+    save_link(conn, path, agent_id, &frontmatter)?;
+    info!("Imported subagent {:?} into agent {}", path, agent_id);
+    Ok(agent)
+}
+
+/// Write an agent to a subagent file and link them
+fn export_file(conn: &Connection, agent: &Agent, path: &Path) -> Result<(), String> {
+    let agent_id = agent.id.ok_or("Agent has no ID")?;
+    let link = load_link(conn, &path.to_string_lossy())?;
+
+    let frontmatter = SubagentFrontmatter {
+        name: subagent_name(&agent.name),
+        // Claude Code picks subagents by their description
+        description: link
+            .as_ref()
+            .and_then(|l| l.description.clone())
+            .or_else(|| agent.default_task.clone())
+            .or_else(|| Some(agent.name.clone())),
+        tools: link.and_then(|l| l.tools),
+        model: Some(agent.model.clone()),
+    };
+    write_subagent(path, &frontmatter, &agent.system_prompt)?;
+    save_link(conn, path, agent_id, &frontmatter)?;
+    info!("Exported agent {} to {:?}", agent_id, path);
+    Ok(())
+}
+
+/// Subagent files in the user directory and, if given, the project directory
+fn subagent_files(project_path: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    let mut dirs = vec![agents_dir("user", None)?];
+    if project_path.is_some() {
+        dirs.push(agents_dir("project", project_path)?);
+    }
+
+    let mut files = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        found.sort();
+        files.extend(found);
+    }
+    Ok(files)
+}
+
+/// Linked files under the scanned directories that were deleted
+fn missing_links(conn: &Connection, project_path: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    let mut dirs = vec![agents_dir("user", None)?];
+    if project_path.is_some() {
+        dirs.push(agents_dir("project", project_path)?);
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT file_path FROM native_subagent_links ORDER BY file_path")
+        .map_err(|e| e.to_string())?;
+    let paths = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(paths
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|p| !p.exists() && dirs.iter().any(|d| p.starts_with(d)))
+        .collect())
+}
+
+/// List user subagents, and project subagents if a project is given, with their sync state
+#[tauri::command]
+pub async fn list_native_subagents(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<Vec<NativeSubagent>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut subagents = Vec::new();
+
+    for path in subagent_files(project_path.as_deref())? {
+        match describe(&conn, &path) {
+            Ok(subagent) => subagents.push(subagent),
+            Err(e) => warn!("Skipping subagent file: {}", e),
+        }
+    }
+    for path in missing_links(&conn, project_path.as_deref())? {
+        subagents.push(describe(&conn, &path)?);
+    }
+
+    Ok(subagents)
+}
+
+/// Import a subagent file into the agents table, overwriting the linked agent if any
+#[tauri::command]
+pub async fn import_native_subagent(
+    db: State<'_, AgentDb>,
+    file_path: String,
+) -> Result<Agent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    import_file(&conn, Path::new(&file_path))
+}
+
+/// Write an agent to `.claude/agents/` in the project or user scope, overwriting the file
+#[tauri::command]
+pub async fn export_agent_to_native(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<NativeSubagent, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let agent = load_agent(&conn, agent_id)?;
+
+    let name = subagent_name(&agent.name);
+    if name.is_empty() {
+        return Err("Agent name has no characters usable in a subagent name".to_string());
+    }
+    let path = agents_dir(&scope, project_path.as_deref())?.join(format!("{}.md", name));
+
+    export_file(&conn, &agent, &path)?;
+    describe(&conn, &path)
+}
+
+/// Sync subagent files with their linked agents in the direction of whichever side
+/// changed since the last sync, and import unlinked files. Pairs where both sides
+/// changed are reported as conflicts and left untouched.
+#[tauri::command]
+pub async fn sync_native_subagents(
+    db: State<'_, AgentDb>,
+    project_path: Option<String>,
+) -> Result<SubagentSyncReport, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut report = SubagentSyncReport::default();
+
+    for path in subagent_files(project_path.as_deref())? {
+        let subagent = match describe(&conn, &path) {
+            Ok(subagent) => subagent,
+            Err(e) => {
+                warn!("Skipping subagent file: {}", e);
+                continue;
+            }
+        };
+
+        match subagent.sync_status.as_str() {
+            "unlinked" | "file_changed" => {
+                import_file(&conn, &path)?;
+                report.imported.push(subagent.file_path);
+            }
+            "agent_changed" => {
+                let agent = load_agent(&conn, subagent.agent_id.ok_or("Link has no agent")?)?;
+                export_file(&conn, &agent, &path)?;
+                report.exported.push(subagent.file_path);
+            }
+            "conflict" => report.conflicts.push(subagent),
+            _ => {}
+        }
+    }
+    for path in missing_links(&conn, project_path.as_deref())? {
+        report.missing.push(describe(&conn, &path)?);
+    }
+
+    info!(
+        "Subagent sync: {} imported, {} exported, {} conflicts",
+        report.imported.len(),
+        report.exported.len(),
+        report.conflicts.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subagent_name() {
+        assert_eq!(subagent_name("Code Reviewer"), "code-reviewer");
+        assert_eq!(subagent_name("Git Commit Bot!"), "git-commit-bot");
+    }
+
+    #[test]
+    fn test_parse_subagent_frontmatter() {
+        let content = "---\nname: test-runner\ndescription: Runs the tests\ntools:\n  - Bash\n  - Read\n---\n\nYou run tests.\n";
+        let (frontmatter, body) =
+            parse_markdown_with_frontmatter::<SubagentFrontmatter>(content).unwrap();
+        let frontmatter = frontmatter.unwrap();
+
+        assert_eq!(frontmatter.name, "test-runner");
+        assert_eq!(frontmatter.tools.as_deref(), Some("Bash, Read"));
+        assert_eq!(frontmatter.model, None);
+        assert_eq!(body.trim(), "You run tests.");
+    }
+
+    #[test]
+    fn test_exported_hash_ignores_other_fields() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::commands::agents::create_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO agents (name, icon, system_prompt, model) VALUES ('Test Runner', 'bot', 'You run tests.', 'sonnet')",
+            [],
+        )
+        .unwrap();
+        let agent = load_agent(&conn, 1).unwrap();
+        let tools = Some("Bash, Read".to_string());
+        let hash = exported_hash(&agent, &None, &tools);
+
+        // Writes that don't reach the subagent file leave the hash alone
+        conn.execute(
+            "UPDATE agents SET output_schema = '{}', latest_source_sha = 'abc' WHERE id = 1",
+            [],
+        )
+        .unwrap();
+        assert_eq!(exported_hash(&load_agent(&conn, 1).unwrap(), &None, &tools), hash);
+
+        conn.execute("UPDATE agents SET system_prompt = 'You fix tests.' WHERE id = 1", [])
+            .unwrap();
+        assert_ne!(exported_hash(&load_agent(&conn, 1).unwrap(), &None, &tools), hash);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agents::{load_agent, AgentDb};
+
+/// A named `{{variable}}` that can be used in an agent's task template
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TaskVariable {
+    pub name: String,
+    pub description: Option<String>,
+    /// Value used when none is provided; variables without a default are required
+    pub default_value: Option<String>,
+}
+
+/// Names of all `{{placeholder}}`s in a template, in order of appearance
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    render(template, |name| {
+        names.push(name.to_string());
+        None
+    });
+    names
+}
+
+/// Replace every `{{placeholder}}` for which `lookup` returns a value.
+/// Placeholders without a value are left untouched.
+pub fn render(template: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rendered.push_str(&rest[start..]);
+            return rendered;
+        };
+
+        match lookup(after[..end].trim()) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Render a task template with the given values, falling back to the variables'
+/// defaults. Fails if a declared variable used in the template has no value.
+pub fn render_task(
+    template: &str,
+    variables: &[TaskVariable],
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    let missing: Vec<String> = placeholders(template)
+        .into_iter()
+        .filter(|name| {
+            !values.contains_key(name)
+                && variables
+                    .iter()
+                    .any(|v| &v.name == name && v.default_value.is_none())
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing value for task variable(s): {}",
+            missing.join(", ")
+        ));
+    }
+
+    Ok(render(template, |name| {
+        values.get(name).cloned().or_else(|| {
+            variables
+                .iter()
+                .find(|v| v.name == name)
+                .and_then(|v| v.default_value.clone())
+        })
+    }))
+}
+
+/// Validate variable definitions before they are stored on an agent
+pub fn validate_variables(variables: &[TaskVariable]) -> Result<(), String> {
+    for (i, variable) in variables.iter().enumerate() {
+        if variable.name.is_empty()
+            || !variable
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "Invalid task variable name '{}': use letters, digits, '-' and '_'",
+                variable.name
+            ));
+        }
+        if variables[..i].iter().any(|v| v.name == variable.name) {
+            return Err(format!("Duplicate task variable '{}'", variable.name));
+        }
+    }
+    Ok(())
+}
+
+/// Render a task for an agent without running it, e.g. to preview the final task
+#[tauri::command]
+pub async fn render_agent_task(
+    db: State<'_, AgentDb>,
+    agent_id: i64,
+    template: String,
+    variables: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let agent = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_agent(&conn, agent_id)?
+    };
+    render_task(&template, &agent.task_variables, &variables.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(name: &str, default_value: Option<&str>) -> TaskVariable {
+        TaskVariable {
+            name: name.to_string(),
+            description: None,
+            default_value: default_value.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_render_task() {
+        let variables = vec![variable("branch", Some("main")), variable("ticket", None)];
+        let template = "Review {{branch}} for {{ ticket }}, see {{unknown}}";
+
+        let values = HashMap::from([("ticket".to_string(), "ENG-42".to_string())]);
+        assert_eq!(
+            render_task(template, &variables, &values).unwrap(),
+            "Review main for ENG-42, see {{unknown}}"
+        );
+
+        // Required variable without a value
+        assert!(render_task(template, &variables, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_validate_variables() {
+        assert!(validate_variables(&[variable("files", None), variable("branch", None)]).is_ok());
+        assert!(validate_variables(&[variable("files", None), variable("files", None)]).is_err());
+        assert!(validate_variables(&[variable("two words", None)]).is_err());
+    }
+}
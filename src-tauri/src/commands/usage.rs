@@ -94,14 +94,14 @@ struct MessageData {
 }
 
 #[derive(Debug, Deserialize)]
-struct UsageData {
-    input_tokens: Option<u64>,
-    output_tokens: Option<u64>,
-    cache_creation_input_tokens: Option<u64>,
-    cache_read_input_tokens: Option<u64>,
+pub(crate) struct UsageData {
+    pub(crate) input_tokens: Option<u64>,
+    pub(crate) output_tokens: Option<u64>,
+    pub(crate) cache_creation_input_tokens: Option<u64>,
+    pub(crate) cache_read_input_tokens: Option<u64>,
 }
 
-fn calculate_cost(model: &str, usage: &UsageData) -> f64 {
+pub(crate) fn calculate_cost(model: &str, usage: &UsageData) -> f64 {
     let input_tokens = usage.input_tokens.unwrap_or(0) as f64;
     let output_tokens = usage.output_tokens.unwrap_or(0) as f64;
     let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0) as f64;
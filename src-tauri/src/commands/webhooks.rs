@@ -0,0 +1,834 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::agent_env::{self, decrypt, encrypt, secret_key};
+use crate::commands::agents::{AgentDb, AgentRun, AGENT_RUN_COLUMNS};
+use crate::commands::task_templates;
+
+/// Seconds to wait before each retry of a failed delivery
+const RETRY_DELAYS_SECS: [u64; 3] = [5, 30, 120];
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries kept in the log per webhook
+const DELIVERY_LOG_LIMIT: i64 = 200;
+
+/// Longest response body kept in the delivery log
+const RESPONSE_BODY_LIMIT: usize = 2000;
+
+/// Characters of a task or prompt included in a payload
+const PAYLOAD_TEXT_LIMIT: usize = 280;
+
+pub const SIGNATURE_HEADER: &str = "X-Claudia-Signature";
+pub const EVENT_HEADER: &str = "X-Claudia-Event";
+pub const DELIVERY_HEADER: &str = "X-Claudia-Delivery";
+
+/// Event sent by `test_webhook`; webhooks can't subscribe to it
+const TEST_EVENT: &str = "webhook.test";
+
+/// Lifecycle events of agent runs and interactive Claude sessions
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    #[serde(rename = "run.started")]
+    RunStarted,
+    #[serde(rename = "run.completed")]
+    RunCompleted,
+    /// Failed, or stopped by a timeout
+    #[serde(rename = "run.failed")]
+    RunFailed,
+    #[serde(rename = "run.budget_exceeded")]
+    RunBudgetExceeded,
+    #[serde(rename = "session.started")]
+    SessionStarted,
+    #[serde(rename = "session.completed")]
+    SessionCompleted,
+    #[serde(rename = "session.failed")]
+    SessionFailed,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::RunStarted => "run.started",
+            WebhookEvent::RunCompleted => "run.completed",
+            WebhookEvent::RunFailed => "run.failed",
+            WebhookEvent::RunBudgetExceeded => "run.budget_exceeded",
+            WebhookEvent::SessionStarted => "session.started",
+            WebhookEvent::SessionCompleted => "session.completed",
+            WebhookEvent::SessionFailed => "session.failed",
+        }
+    }
+
+    /// Event of a run that ended with `status`. Cancelled runs and runs waiting for a
+    /// usage limit to reset have none.
+    pub fn for_run_status(status: &str) -> Option<Self> {
+        match status {
+            "completed" => Some(WebhookEvent::RunCompleted),
+            "failed" | "timed_out" => Some(WebhookEvent::RunFailed),
+            "budget_exceeded" => Some(WebhookEvent::RunBudgetExceeded),
+            _ => None,
+        }
+    }
+}
+
+/// A user-configured HTTP endpoint notified of lifecycle events. The secret never leaves the backend.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    pub id: i64,
+    pub name: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// Request body with `{{placeholders}}`; the default JSON payload is sent when unset
+    pub payload_template: Option<String>,
+    /// Whether requests are signed with an HMAC secret
+    pub has_secret: bool,
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// One event sent to a webhook, with the outcome of its latest attempt
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    pub payload: String,
+    pub status: String, // 'pending', 'delivered', 'failed'
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub last_attempt_at: Option<String>,
+}
+
+/// A delivery ready to be sent
+#[derive(Debug, Clone)]
+struct DeliveryRequest {
+    id: i64,
+    url: String,
+    event: String,
+    body: String,
+    secret: Option<String>,
+}
+
+#[derive(Debug)]
+struct DeliveryResponse {
+    status: u16,
+    body: String,
+}
+
+impl DeliveryResponse {
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Server errors and throttling are retried, other client errors won't go away
+    fn is_retryable(&self) -> bool {
+        self.status >= 500 || self.status == 408 || self.status == 429
+    }
+}
+
+const WEBHOOK_COLUMNS: &str =
+    "id, name, url, events, payload_template, secret IS NOT NULL, enabled, created_at, updated_at";
+
+fn row_to_webhook(row: &rusqlite::Row) -> rusqlite::Result<Webhook> {
+    let events: String = row.get(3)?;
+    Ok(Webhook {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        url: row.get(2)?,
+        events: serde_json::from_str(&events).unwrap_or_default(),
+        payload_template: row.get(4)?,
+        has_secret: row.get(5)?,
+        enabled: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn load_webhook(conn: &Connection, id: i64) -> Result<Webhook, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM webhooks WHERE id = ?1", WEBHOOK_COLUMNS),
+        params![id],
+        row_to_webhook,
+    )
+    .map_err(|e| format!("Webhook {} not found: {}", id, e))
+}
+
+fn row_to_delivery(row: &rusqlite::Row) -> rusqlite::Result<WebhookDelivery> {
+    Ok(WebhookDelivery {
+        id: row.get(0)?,
+        webhook_id: row.get(1)?,
+        event: row.get(2)?,
+        payload: row.get(3)?,
+        status: row.get(4)?,
+        attempts: row.get(5)?,
+        response_status: row.get(6)?,
+        response_body: row.get(7)?,
+        error: row.get(8)?,
+        created_at: row.get(9)?,
+        last_attempt_at: row.get(10)?,
+    })
+}
+
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event, payload, status, attempts, response_status, response_body, error, created_at, last_attempt_at";
+
+fn load_delivery(conn: &Connection, id: i64) -> Result<WebhookDelivery, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM webhook_deliveries WHERE id = ?1",
+            DELIVERY_COLUMNS
+        ),
+        params![id],
+        row_to_delivery,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn validate_webhook(url: &str, events: &[WebhookEvent]) -> Result<(), String> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Webhook URL '{}' must use http or https", url));
+    }
+    if events.is_empty() {
+        return Err("Select at least one event for the webhook".to_string());
+    }
+    Ok(())
+}
+
+/// Default JSON body of a delivery
+fn default_payload(event: &str, timestamp: &str, data: &Value) -> Value {
+    json!({
+        "event": event,
+        "timestamp": timestamp,
+        "data": data,
+    })
+}
+
+/// Body of a delivery: the default payload, or the webhook's template with `{{event}}`,
+/// `{{timestamp}}`, `{{payload}}` (the default payload) and the fields of the event data,
+/// such as `{{run_id}}`, filled in. Values are JSON-escaped in templates that look like JSON.
+fn render_payload(template: Option<&str>, event: &str, data: &Value) -> String {
+    let timestamp = Utc::now().to_rfc3339();
+    let payload = default_payload(event, &timestamp, data);
+    let Some(template) = template.filter(|t| !t.trim().is_empty()) else {
+        return payload.to_string();
+    };
+
+    let trimmed = template.trim_start();
+    let escape = trimmed.starts_with('{') || trimmed.starts_with('[');
+    task_templates::render(template, |name| {
+        let value = match name {
+            "event" => Value::from(event),
+            "timestamp" => Value::from(timestamp.as_str()),
+            "payload" => return Some(payload.to_string()),
+            _ => data.get(name)?.clone(),
+        };
+        Some(match value {
+            Value::Null => String::new(),
+            Value::String(text) if escape => {
+                let quoted = Value::String(text).to_string();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            Value::String(text) => text,
+            other => other.to_string(),
+        })
+    })
+}
+
+/// Hex HMAC-SHA256 of the body, sent as `sha256=<hex>` in the signature header
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+async fn send(
+    client: &reqwest::Client,
+    delivery: &DeliveryRequest,
+) -> Result<DeliveryResponse, String> {
+    let content_type = if serde_json::from_str::<Value>(&delivery.body).is_ok() {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+    let mut request = client
+        .post(&delivery.url)
+        .header("Content-Type", content_type)
+        .header("User-Agent", "Claudia-App")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .body(delivery.body.clone());
+    if let Some(secret) = &delivery.secret {
+        request = request.header(
+            SIGNATURE_HEADER,
+            format!("sha256={}", sign(secret, &delivery.body)),
+        );
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", delivery.url, e))?;
+    let status = response.status().as_u16();
+    let mut body = response.text().await.unwrap_or_default();
+    if body.len() > RESPONSE_BODY_LIMIT {
+        let mut end = RESPONSE_BODY_LIMIT;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+    }
+    Ok(DeliveryResponse { status, body })
+}
+
+/// The start of a task or prompt, as sent in payloads; the full text stays in the app
+pub fn excerpt(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(PAYLOAD_TEXT_LIMIT) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+fn record_attempt(
+    app: &AppHandle,
+    delivery_id: i64,
+    attempts: usize,
+    status: &str,
+    result: &Result<DeliveryResponse, String>,
+) {
+    let (response_status, response_body, error) = match result {
+        Ok(response) if response.is_success() => {
+            (Some(response.status), Some(response.body.as_str()), None)
+        }
+        Ok(response) => (
+            Some(response.status),
+            Some(response.body.as_str()),
+            Some(format!("Endpoint responded with HTTP {}", response.status)),
+        ),
+        Err(e) => (None, None, Some(e.clone())),
+    };
+
+    let db = app.state::<AgentDb>();
+    let Ok(conn) = db.0.lock() else {
+        return;
+    };
+    if let Err(e) = conn.execute(
+        "UPDATE webhook_deliveries SET status = ?1, attempts = ?2, response_status = ?3, response_body = ?4, error = ?5, last_attempt_at = CURRENT_TIMESTAMP WHERE id = ?6",
+        params![status, attempts as i64, response_status, response_body, error, delivery_id],
+    ) {
+        warn!("Failed to log webhook delivery {}: {}", delivery_id, e);
+    }
+}
+
+/// Send a delivery starting at attempt `first_attempt`, retrying network failures, server errors
+/// and throttling until `max_attempts` attempts have been made
+async fn deliver(app: &AppHandle, delivery: DeliveryRequest, first_attempt: usize, max_attempts: usize) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            record_attempt(app, delivery.id, first_attempt - 1, "failed", &Err(e.to_string()));
+            return;
+        }
+    };
+
+    for attempt in first_attempt..=max_attempts {
+        let result = send(&client, &delivery).await;
+        let finished = match &result {
+            Ok(response) => response.is_success() || !response.is_retryable(),
+            Err(_) => false,
+        };
+        let status = match &result {
+            Ok(response) if response.is_success() => "delivered",
+            _ if finished || attempt == max_attempts => "failed",
+            _ => "pending",
+        };
+        record_attempt(app, delivery.id, attempt, status, &result);
+        if status != "pending" {
+            if status == "failed" {
+                warn!(
+                    "Webhook delivery {} of {} failed after {} attempt(s)",
+                    delivery.id, delivery.event, attempt
+                );
+            }
+            return;
+        }
+
+        let delay = RETRY_DELAYS_SECS[(attempt - 1).min(RETRY_DELAYS_SECS.len() - 1)];
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+    }
+}
+
+/// Log a delivery of `event` to a webhook and return it ready to be sent
+fn queue_delivery(
+    app: &AppHandle,
+    conn: &Connection,
+    webhook: &Webhook,
+    secret: Option<String>,
+    event: &str,
+    data: &Value,
+) -> Result<DeliveryRequest, String> {
+    let body = render_payload(webhook.payload_template.as_deref(), event, data);
+    conn.execute(
+        "INSERT INTO webhook_deliveries (webhook_id, event, payload) VALUES (?1, ?2, ?3)",
+        params![webhook.id, event, body],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM webhook_deliveries WHERE webhook_id = ?1 AND id NOT IN (SELECT id FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY id DESC LIMIT ?2)",
+        params![webhook.id, DELIVERY_LOG_LIMIT],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let secret = match secret {
+        Some(sealed) => Some(decrypt(&secret_key(app)?, &sealed)?),
+        None => None,
+    };
+    Ok(DeliveryRequest {
+        id,
+        url: webhook.url.clone(),
+        event: event.to_string(),
+        body,
+        secret,
+    })
+}
+
+fn queue_deliveries(
+    app: &AppHandle,
+    event: WebhookEvent,
+    data: &Value,
+) -> Result<Vec<DeliveryRequest>, String> {
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}, secret FROM webhooks WHERE enabled = 1 AND EXISTS (SELECT 1 FROM json_each(webhooks.events) WHERE value = ?1)",
+            WEBHOOK_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let webhooks = stmt
+        .query_map(params![event.as_str()], |row| {
+            Ok((row_to_webhook(row)?, row.get::<_, Option<String>>(9)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut deliveries = Vec::new();
+    for (webhook, secret) in webhooks {
+        match queue_delivery(app, &conn, &webhook, secret, event.as_str(), data) {
+            Ok(delivery) => deliveries.push(delivery),
+            Err(e) => warn!(
+                "Failed to queue {} for webhook {}: {}",
+                event.as_str(),
+                webhook.id,
+                e
+            ),
+        }
+    }
+    Ok(deliveries)
+}
+
+/// Send `event` to every enabled webhook subscribed to it, in the background
+pub fn notify(app: &AppHandle, event: WebhookEvent, data: Value) {
+    let deliveries = match queue_deliveries(app, event, &data) {
+        Ok(deliveries) => deliveries,
+        Err(e) => {
+            warn!("Failed to load webhooks for {}: {}", event.as_str(), e);
+            return;
+        }
+    };
+
+    for delivery in deliveries {
+        info!("Delivering {} to webhook {}", delivery.event, delivery.url);
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            deliver(&app, delivery, 1, RETRY_DELAYS_SECS.len() + 1).await;
+        });
+    }
+}
+
+/// Send the deliveries that were still pending when the app last quit, continuing their retries.
+/// Deliveries whose webhook was deleted or disabled since are marked failed.
+pub fn resume_pending_deliveries(app: &AppHandle) -> Result<usize, String> {
+    let deliveries = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE webhook_deliveries SET status = 'failed', error = 'Webhook was deleted or disabled before the delivery was sent' WHERE status = 'pending' AND webhook_id NOT IN (SELECT id FROM webhooks WHERE enabled = 1)",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut stmt = conn
+            .prepare("SELECT d.id, d.event, d.payload, d.attempts, w.url, w.secret FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id WHERE d.status = 'pending' ORDER BY d.id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let max_attempts = RETRY_DELAYS_SECS.len() + 1;
+    let count = deliveries.len();
+    for (id, event, body, attempts, url, secret) in deliveries {
+        let secret = match secret {
+            Some(sealed) => match secret_key(app).and_then(|key| decrypt(&key, &sealed)) {
+                Ok(secret) => Some(secret),
+                Err(e) => {
+                    record_attempt(app, id, attempts as usize, "failed", &Err(e));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        info!("Resuming delivery {} of {} to webhook {}", id, event, url);
+        let delivery = DeliveryRequest {
+            id,
+            url,
+            event,
+            body,
+            secret,
+        };
+        let first_attempt = (attempts as usize + 1).min(max_attempts);
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            deliver(&app, delivery, first_attempt, max_attempts).await;
+        });
+    }
+    Ok(count)
+}
+
+fn run_data(run: &AgentRun) -> Value {
+    json!({
+        "run_id": run.id,
+        "agent_id": run.agent_id,
+        "agent_name": run.agent_name,
+        "task": excerpt(&run.task),
+        "model": run.model,
+        "project_path": run.project_path,
+        "session_id": run.session_id,
+        "status": run.status,
+        "status_reason": run.status_reason,
+        "created_at": run.created_at,
+        "completed_at": run.completed_at,
+    })
+}
+
+/// Load a run with the agent's secrets scrubbed from its task
+fn load_run(app: &AppHandle, run_id: i64) -> Option<AgentRun> {
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().ok()?;
+    let mut run = conn
+        .query_row(
+            &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
+            params![run_id],
+            AgentRun::from_row,
+        )
+        .ok()?;
+    run.task = agent_env::load_redaction(app, &conn, run.agent_id).redact(&run.task);
+    Some(run)
+}
+
+/// Notify webhooks that a run has started
+pub fn notify_run_started(app: &AppHandle, run_id: i64) {
+    if let Some(run) = load_run(app, run_id) {
+        notify(app, WebhookEvent::RunStarted, run_data(&run));
+    }
+}
+
+/// Notify webhooks that a run has ended, with the event matching its final status
+pub fn notify_run_finished(app: &AppHandle, run_id: i64) {
+    let Some(run) = load_run(app, run_id) else {
+        return;
+    };
+    if let Some(event) = WebhookEvent::for_run_status(&run.status) {
+        notify(app, event, run_data(&run));
+    }
+}
+
+/// List all webhooks
+#[tauri::command]
+pub async fn list_webhooks(db: State<'_, AgentDb>) -> Result<Vec<Webhook>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM webhooks ORDER BY name COLLATE NOCASE",
+            WEBHOOK_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let webhooks = stmt
+        .query_map([], row_to_webhook)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(webhooks)
+}
+
+/// Create a webhook; the optional secret is encrypted and used to sign its requests
+#[tauri::command]
+pub async fn create_webhook(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    name: String,
+    url: String,
+    events: Vec<WebhookEvent>,
+    payload_template: Option<String>,
+    secret: Option<String>,
+) -> Result<Webhook, String> {
+    let url = url.trim().to_string();
+    validate_webhook(&url, &events)?;
+    let secret = match secret.filter(|s| !s.is_empty()) {
+        Some(secret) => Some(encrypt(&secret_key(&app)?, &secret)?),
+        None => None,
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO webhooks (name, url, events, payload_template, secret) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            name,
+            url,
+            serde_json::to_string(&events).map_err(|e| e.to_string())?,
+            payload_template.filter(|t| !t.trim().is_empty()),
+            secret
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    load_webhook(&conn, conn.last_insert_rowid())
+}
+
+/// Update a webhook. The secret is kept when `secret` is None and removed when it is empty.
+#[tauri::command]
+pub async fn update_webhook(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+    name: String,
+    url: String,
+    events: Vec<WebhookEvent>,
+    payload_template: Option<String>,
+    enabled: bool,
+    secret: Option<String>,
+) -> Result<Webhook, String> {
+    let url = url.trim().to_string();
+    validate_webhook(&url, &events)?;
+    let secret = match secret {
+        Some(secret) if secret.is_empty() => Some(None),
+        Some(secret) => Some(Some(encrypt(&secret_key(&app)?, &secret)?)),
+        None => None,
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_webhook(&conn, id)?;
+    conn.execute(
+        "UPDATE webhooks SET name = ?1, url = ?2, events = ?3, payload_template = ?4, enabled = ?5, updated_at = CURRENT_TIMESTAMP WHERE id = ?6",
+        params![
+            name,
+            url,
+            serde_json::to_string(&events).map_err(|e| e.to_string())?,
+            payload_template.filter(|t| !t.trim().is_empty()),
+            enabled,
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(secret) = secret {
+        conn.execute(
+            "UPDATE webhooks SET secret = ?1 WHERE id = ?2",
+            params![secret, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    load_webhook(&conn, id)
+}
+
+/// Delete a webhook and its delivery log
+#[tauri::command]
+pub async fn delete_webhook(db: State<'_, AgentDb>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM webhook_deliveries WHERE webhook_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Recent deliveries of one webhook, or of all webhooks, newest first
+#[tauri::command]
+pub async fn list_webhook_deliveries(
+    db: State<'_, AgentDb>,
+    webhook_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<WebhookDelivery>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE ?1 IS NULL OR webhook_id = ?1 ORDER BY id DESC LIMIT ?2",
+            DELIVERY_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let deliveries = stmt
+        .query_map(params![webhook_id, limit.unwrap_or(50)], row_to_delivery)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(deliveries)
+}
+
+/// Send a test event to a webhook once, without retries, and return the logged delivery
+#[tauri::command]
+pub async fn test_webhook(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    id: i64,
+) -> Result<WebhookDelivery, String> {
+    let delivery = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let webhook = load_webhook(&conn, id)?;
+        let secret: Option<String> = conn
+            .query_row(
+                "SELECT secret FROM webhooks WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        let data = json!({
+            "webhook_id": webhook.id,
+            "webhook_name": webhook.name,
+            "message": "Test delivery from Claudia",
+        });
+        queue_delivery(&app, &conn, &webhook, secret, TEST_EVENT, &data)?
+    };
+
+    let delivery_id = delivery.id;
+    deliver(&app, delivery, 1, 1).await;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_delivery(&conn, delivery_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_render_payload() {
+        let data = json!({"run_id": 7, "agent_name": "Say \"hi\"", "status_reason": null});
+
+        let default: Value =
+            serde_json::from_str(&render_payload(None, "run.completed", &data)).unwrap();
+        assert_eq!(default["event"], "run.completed");
+        assert_eq!(default["data"]["run_id"], 7);
+
+        let rendered = render_payload(
+            Some(
+                r#"{"text": "{{agent_name}} finished run {{run_id}} ({{event}}){{status_reason}}"}"#,
+            ),
+            "run.completed",
+            &data,
+        );
+        let rendered: Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            rendered["text"],
+            "Say \"hi\" finished run 7 (run.completed)"
+        );
+
+        // Plain text templates are not escaped
+        assert_eq!(
+            render_payload(Some("{{agent_name}}: {{unknown}}"), "run.completed", &data),
+            "Say \"hi\": {{unknown}}"
+        );
+    }
+
+    #[test]
+    fn test_excerpt() {
+        assert_eq!(excerpt("  Review the diff\n"), "Review the diff");
+        let long = "é".repeat(PAYLOAD_TEXT_LIMIT + 10);
+        let short = excerpt(&long);
+        assert_eq!(short.chars().count(), PAYLOAD_TEXT_LIMIT + 1);
+        assert!(short.ends_with('…'));
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_to_local_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read until the whole body has arrived
+            while !String::from_utf8_lossy(&request).ends_with("{\"ok\":true}") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbusy")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let delivery = DeliveryRequest {
+            id: 3,
+            url,
+            event: "run.completed".to_string(),
+            body: "{\"ok\":true}".to_string(),
+            secret: Some("s3cret".to_string()),
+        };
+        let response = send(&reqwest::Client::new(), &delivery).await.unwrap();
+        assert_eq!(response.status, 503);
+        assert_eq!(response.body, "busy");
+        assert!(response.is_retryable());
+
+        let request = server.await.unwrap().to_lowercase();
+        assert!(request.starts_with("post /hook "));
+        assert!(request.contains("content-type: application/json"));
+        assert!(request.contains("x-claudia-event: run.completed"));
+        assert!(request.contains(&format!(
+            "x-claudia-signature: sha256={}",
+            sign("s3cret", "{\"ok\":true}")
+        )));
+    }
+}
@@ -0,0 +1,411 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agents::{AgentDb, AgentRun, AGENT_RUN_COLUMNS};
+
+/// Git worktree an isolated run executes in.
+/// The worktree is removed when the run finishes; its changes are committed to `branch`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RunWorktree {
+    /// 'requested', 'active', 'ready', 'merged', 'kept', 'discarded'
+    pub status: String,
+    /// Directory the agent runs in (inside the worktree) while it is active
+    pub path: Option<String>,
+    pub branch: Option<String>,
+    /// Commit of the project the worktree was created from
+    pub base_commit: Option<String>,
+}
+
+impl RunWorktree {
+    /// Read the worktree of a run from four consecutive columns starting at `index`
+    pub fn from_row(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Option<Self>> {
+        let Some(status) = row.get::<_, Option<String>>(index)? else {
+            return Ok(None);
+        };
+        Ok(Some(RunWorktree {
+            status,
+            path: row.get(index + 1)?,
+            branch: row.get(index + 2)?,
+            base_commit: row.get(index + 3)?,
+        }))
+    }
+}
+
+/// A file changed on a run's branch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorktreeFileChange {
+    pub path: String,
+    /// None for binary files
+    pub additions: Option<i64>,
+    pub deletions: Option<i64>,
+}
+
+/// Changes a run made relative to the commit its worktree was created from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorktreeDiff {
+    pub run_id: i64,
+    pub worktree: RunWorktree,
+    pub files: Vec<WorktreeFileChange>,
+    pub patch: String,
+}
+
+/// Run git in `dir` and return its trimmed stdout
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Branch name for a run, e.g. `agent/code-reviewer-42`
+fn branch_name(agent_name: &str, run_id: i64) -> String {
+    let slug: String = agent_name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        format!("agent/run-{}", run_id)
+    } else {
+        format!("agent/{}-{}", slug, run_id)
+    }
+}
+
+/// Parse `git diff --numstat` output
+pub(crate) fn parse_numstat(numstat: &str) -> Vec<WorktreeFileChange> {
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let additions = parts.next()?;
+            let deletions = parts.next()?;
+            let path = parts.next()?;
+            Some(WorktreeFileChange {
+                path: path.to_string(),
+                additions: additions.parse().ok(),
+                deletions: deletions.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+fn load_run(conn: &Connection, run_id: i64) -> Result<AgentRun, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM agent_runs WHERE id = ?1", AGENT_RUN_COLUMNS),
+        params![run_id],
+        AgentRun::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn set_worktree_status(conn: &Connection, run_id: i64, status: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE agent_runs SET worktree_status = ?1 WHERE id = ?2",
+        params![status, run_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Create the worktree for a run that requested isolation and return the directory
+/// the agent should run in
+pub(crate) fn create_run_worktree(app: &AppHandle, run: &AgentRun) -> Result<String, String> {
+    let run_id = run.id.ok_or("Run has no ID")?;
+    let project_path = Path::new(&run.project_path);
+
+    let toplevel = git(project_path, &["rev-parse", "--show-toplevel"])
+        .map_err(|_| "Worktree isolation requires the project to be a git repository".to_string())?;
+    let base_commit = git(project_path, &["rev-parse", "HEAD"])
+        .map_err(|_| "Worktree isolation requires at least one commit in the repository".to_string())?;
+
+    let worktrees_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("worktrees");
+    std::fs::create_dir_all(&worktrees_dir)
+        .map_err(|e| format!("Failed to create worktrees directory: {}", e))?;
+    let worktree_root = worktrees_dir.join(format!("run-{}", run_id));
+    let branch = branch_name(&run.agent_name, run_id);
+
+    git(
+        project_path,
+        &[
+            "worktree",
+            "add",
+            "-b",
+            &branch,
+            &worktree_root.to_string_lossy(),
+            &base_commit,
+        ],
+    )?;
+
+    // Run in the same subdirectory of the repository as the project path
+    let relative = Path::new(&run.project_path)
+        .canonicalize()
+        .ok()
+        .zip(Path::new(&toplevel).canonicalize().ok())
+        .and_then(|(project, root)| project.strip_prefix(root).ok().map(PathBuf::from))
+        .unwrap_or_default();
+    let execution_path = worktree_root.join(relative).to_string_lossy().to_string();
+
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE agent_runs SET worktree_status = 'active', worktree_path = ?1, worktree_branch = ?2, worktree_base_commit = ?3 WHERE id = ?4",
+        params![execution_path, branch, base_commit, run_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    info!("Created worktree {:?} on branch {} for run {}", worktree_root, branch, run_id);
+    Ok(execution_path)
+}
+
+/// Commit everything the agent changed in the worktree to its branch and remove the
+/// worktree. Returns whether the branch has any changes.
+fn close_worktree(run: &AgentRun, worktree: &RunWorktree) -> Result<bool, String> {
+    let (Some(path), Some(branch), Some(base_commit)) =
+        (&worktree.path, &worktree.branch, &worktree.base_commit)
+    else {
+        return Err("Worktree details are missing".to_string());
+    };
+    let path = Path::new(path);
+    let project_path = Path::new(&run.project_path);
+
+    if path.exists() {
+        git(path, &["add", "-A"])?;
+        if !git(path, &["status", "--porcelain"])?.is_empty() {
+            let message = format!("{} (agent run {})", run.agent_name, run.id.unwrap_or_default());
+            let mut args = vec!["commit", "--no-verify", "-m", message.as_str()];
+            // Make the commit work on machines without a configured identity
+            if git(path, &["config", "user.email"]).is_err() {
+                args.splice(0..0, ["-c", "user.name=Claudia", "-c", "user.email=claudia@localhost"]);
+            }
+            git(path, &args)?;
+        }
+
+        let root = git(path, &["rev-parse", "--show-toplevel"])?;
+        git(project_path, &["worktree", "remove", "--force", &root])?;
+    } else {
+        // Removed by hand; forget about it
+        let _ = git(project_path, &["worktree", "prune"]);
+    }
+
+    let head = git(project_path, &["rev-parse", branch])?;
+    Ok(&head != base_commit)
+}
+
+/// Close the worktrees of runs that have finished. Called by the run queue, which is
+/// dispatched whenever a run completes, fails or is cancelled.
+pub(crate) fn finalize_run_worktrees(app: &AppHandle) {
+    let db = app.state::<AgentDb>();
+    let finished: Vec<AgentRun> = {
+        let conn = match db.0.lock() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to lock database for worktree cleanup: {}", e);
+                return;
+            }
+        };
+        match conn
+            .prepare(&format!(
+                "SELECT {} FROM agent_runs WHERE worktree_status IN ('requested', 'active') AND status NOT IN ('queued', 'pending', 'running', 'rate_limited')",
+                AGENT_RUN_COLUMNS
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([], AgentRun::from_row)?
+                    .collect::<Result<Vec<_>, _>>()
+            }) {
+            Ok(runs) => runs,
+            Err(e) => {
+                error!("Failed to load finished worktree runs: {}", e);
+                return;
+            }
+        }
+    };
+
+    for run in finished {
+        let (Some(run_id), Some(worktree)) = (run.id, run.worktree.clone()) else {
+            continue;
+        };
+
+        // Git calls run without holding the database lock
+        let status = if worktree.status == "requested" {
+            // The run never started
+            "discarded"
+        } else {
+            match close_worktree(&run, &worktree) {
+                Ok(true) => "ready",
+                Ok(false) => {
+                    if let Some(branch) = &worktree.branch {
+                        let _ = git(Path::new(&run.project_path), &["branch", "-D", branch]);
+                    }
+                    "discarded"
+                }
+                Err(e) => {
+                    warn!("Failed to close worktree of run {}: {}", run_id, e);
+                    continue;
+                }
+            }
+        };
+
+        if let Ok(conn) = db.0.lock() {
+            if let Err(e) = set_worktree_status(&conn, run_id, status) {
+                error!("Failed to update worktree status of run {}: {}", run_id, e);
+                continue;
+            }
+        }
+        info!("Worktree of run {} is {}", run_id, status);
+        let _ = app.emit(&format!("agent-worktree-updated:{}", run_id), status);
+    }
+}
+
+/// Load a run together with its worktree, which must be in one of `allowed` states
+fn load_run_worktree(
+    db: &State<'_, AgentDb>,
+    run_id: i64,
+    allowed: &[&str],
+) -> Result<(AgentRun, RunWorktree), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let run = load_run(&conn, run_id)?;
+    let worktree = run
+        .worktree
+        .clone()
+        .ok_or("Run was not executed in a worktree")?;
+    if !allowed.contains(&worktree.status.as_str()) {
+        return Err(format!("Worktree of run {} is {}", run_id, worktree.status));
+    }
+    Ok((run, worktree))
+}
+
+/// Diff of a run's worktree against its base commit; see `get_run_worktree_diff`
+pub(crate) fn run_worktree_diff(run: &AgentRun, worktree: RunWorktree) -> Result<WorktreeDiff, String> {
+    let base_commit = worktree.base_commit.clone().ok_or("Worktree has no base commit")?;
+
+    let (dir, target) = match (&worktree.path, worktree.status.as_str()) {
+        (Some(path), "active") if Path::new(path).exists() => {
+            // Record new files as intent-to-add so they show up in the diff
+            git(Path::new(path), &["add", "-A", "--intent-to-add"])?;
+            (PathBuf::from(path), None)
+        }
+        _ => (
+            PathBuf::from(&run.project_path),
+            Some(worktree.branch.clone().ok_or("Worktree has no branch")?),
+        ),
+    };
+
+    let range: Vec<&str> = match &target {
+        Some(branch) => vec![base_commit.as_str(), branch.as_str()],
+        None => vec![base_commit.as_str()],
+    };
+    let mut numstat_args = vec!["diff", "--numstat"];
+    numstat_args.extend(&range);
+    let mut patch_args = vec!["diff"];
+    patch_args.extend(&range);
+
+    Ok(WorktreeDiff {
+        run_id: run.id.unwrap_or_default(),
+        files: parse_numstat(&git(&dir, &numstat_args)?),
+        patch: git(&dir, &patch_args)?,
+        worktree,
+    })
+}
+
+/// Files changed by an isolated run and the full patch. While the run is active
+/// this includes uncommitted changes in the worktree.
+#[tauri::command]
+pub async fn get_run_worktree_diff(
+    db: State<'_, AgentDb>,
+    run_id: i64,
+) -> Result<WorktreeDiff, String> {
+    let (run, worktree) = load_run_worktree(&db, run_id, &["active", "ready", "kept", "merged"])?;
+    run_worktree_diff(&run, worktree)
+}
+
+/// Merge a finished run's branch into the project's current branch.
+/// Conflicting merges are aborted and leave the branch untouched.
+#[tauri::command]
+pub async fn merge_run_worktree(db: State<'_, AgentDb>, run_id: i64) -> Result<String, String> {
+    let (run, worktree) = load_run_worktree(&db, run_id, &["ready", "kept"])?;
+    let branch = worktree.branch.ok_or("Worktree has no branch")?;
+    let project_path = Path::new(&run.project_path);
+
+    let output = match git(project_path, &["merge", "--no-ff", "--no-edit", &branch]) {
+        Ok(output) => output,
+        Err(e) => {
+            let _ = git(project_path, &["merge", "--abort"]);
+            return Err(format!("Failed to merge {}: {}", branch, e));
+        }
+    };
+    let _ = git(project_path, &["branch", "-d", &branch]);
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    set_worktree_status(&conn, run_id, "merged")?;
+    info!("Merged branch {} of run {}", branch, run_id);
+    Ok(output)
+}
+
+/// Keep a finished run's branch without merging it
+#[tauri::command]
+pub async fn keep_run_worktree(db: State<'_, AgentDb>, run_id: i64) -> Result<(), String> {
+    load_run_worktree(&db, run_id, &["ready"])?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    set_worktree_status(&conn, run_id, "kept")
+}
+
+/// Delete a finished run's branch and its changes
+#[tauri::command]
+pub async fn discard_run_worktree(db: State<'_, AgentDb>, run_id: i64) -> Result<(), String> {
+    let (run, worktree) = load_run_worktree(&db, run_id, &["ready", "kept"])?;
+    if let Some(branch) = &worktree.branch {
+        git(Path::new(&run.project_path), &["branch", "-D", branch])?;
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    set_worktree_status(&conn, run_id, "discarded")?;
+    info!("Discarded worktree branch of run {}", run_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(branch_name("Code Reviewer", 42), "agent/code-reviewer-42");
+        assert_eq!(branch_name("  Test--Writer! ", 7), "agent/test-writer-7");
+        assert_eq!(branch_name("🚀", 3), "agent/run-3");
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let files = parse_numstat("3\t1\tsrc/main.rs\n-\t-\tassets/logo.png\n");
+        assert_eq!(files.len(), 2);
+        assert_eq!((files[0].additions, files[0].deletions), (Some(3), Some(1)));
+        assert_eq!(files[1].path, "assets/logo.png");
+        assert_eq!(files[1].additions, None);
+    }
+}
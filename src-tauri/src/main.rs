@@ -6,20 +6,21 @@ mod claude_binary;
 mod commands;
 mod process;
 mod error_messages;
+mod scheduler;
 
 use checkpoint::state::CheckpointState;
 use commands::agents::{
     cleanup_finished_processes, create_agent, delete_agent, execute_agent, export_agent,
-    export_agent_to_file, fetch_github_agent_content, fetch_github_agents, get_agent,
+    export_agent_to_file, get_agent,
     get_agent_run, get_agent_run_with_real_time_metrics, get_claude_binary_path,
     get_live_session_output, get_session_output, get_session_status, import_agent,
-    import_agent_from_file, import_agent_from_github, init_database, kill_agent_session,
+    import_agent_from_file, init_database, kill_agent_session,
     list_agent_runs, list_agent_runs_with_metrics, list_agents, list_claude_installations,
-    list_running_sessions, load_agent_session_history, set_claude_binary_path, stream_session_output, update_agent, AgentDb,
+    list_running_sessions, load_agent_session_history, set_claude_binary_path, stream_session_output, update_agent, update_agent_budget, AgentDb,
 };
 use commands::claude::{
-    cancel_claude_execution, check_auto_checkpoint, check_claude_version, cleanup_old_checkpoints,
-    clear_checkpoint_manager, continue_claude_code, create_checkpoint, execute_claude_code,
+    cancel_claude_execution, cancel_session_resume, check_auto_checkpoint, check_claude_version,
+    cleanup_old_checkpoints, clear_checkpoint_manager, continue_claude_code, create_checkpoint, execute_claude_code,
     find_claude_md_files, fork_from_checkpoint, get_checkpoint_diff, get_checkpoint_settings,
     get_checkpoint_state_stats, get_claude_session_output, get_claude_settings, get_project_sessions,
     get_recently_modified_files, get_session_timeline, get_system_prompt, list_checkpoints,
@@ -44,6 +45,62 @@ use commands::storage::{
     storage_insert_row, storage_execute_sql, storage_reset_database,
 };
 use commands::proxy::{get_proxy_settings, save_proxy_settings, apply_proxy_settings};
+use commands::schedules::{
+    create_agent_schedule, delete_agent_schedule, list_agent_schedules, preview_cron_schedule,
+    run_agent_schedule_now, update_agent_schedule,
+};
+use commands::task_templates::render_agent_task;
+use commands::agent_revisions::{
+    diff_agent_revisions, get_agent_revision, list_agent_revisions, restore_agent_revision,
+};
+use commands::pipelines::{
+    cancel_pipeline_run, create_pipeline, delete_pipeline, get_pipeline, get_pipeline_run,
+    list_pipeline_runs, list_pipelines, run_pipeline, update_pipeline,
+};
+use commands::registries::{
+    create_agent_registry, delete_agent_registry, fetch_registry_agent_content,
+    fetch_registry_agents, import_agent_from_registry, list_agent_registries,
+    update_agent_registry,
+};
+use commands::agent_export::{get_agent_export_schema, update_agent_requirements};
+use commands::run_results::set_agent_output_schema;
+use commands::run_changes::restore_agent_run_changes;
+use commands::run_annotations::{
+    get_agent_run_stats, list_agent_run_tags, set_agent_run_annotation,
+};
+use commands::agent_library::{
+    list_agent_folders, list_agent_tags, set_agent_favorite, set_agent_folder, set_agent_tags,
+};
+use commands::agent_env::{delete_agent_env_var, list_agent_env_vars, set_agent_env_var};
+use commands::notifications::{
+    get_notification_preferences, save_notification_preferences, send_test_notification,
+};
+use commands::webhooks::{
+    create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks, test_webhook,
+    update_webhook,
+};
+use commands::agent_updates::{apply_agent_update, check_agent_updates, get_agent_update_diff};
+use commands::subagents::{
+    export_agent_to_native, import_native_subagent, list_native_subagents, sync_native_subagents,
+};
+use commands::reruns::{rerun_agent_run, rerun_agent_runs};
+use commands::batches::{
+    cancel_agent_batch, execute_agent_batch, get_agent_batch, list_agent_batches,
+};
+use commands::comparisons::{
+    compare_agent_models, delete_agent_comparison, get_agent_comparison, list_agent_comparisons,
+};
+use commands::evals::{
+    cancel_eval_run, create_eval_suite, delete_eval_suite, get_eval_run, get_eval_suite,
+    list_eval_runs, list_eval_suites, run_eval_suite, update_eval_suite,
+};
+use commands::worktrees::{
+    discard_run_worktree, get_run_worktree_diff, keep_run_worktree, merge_run_worktree,
+};
+use commands::queue::{
+    cancel_queued_run, get_process_timeouts, get_queue_settings, list_queued_runs,
+    save_process_timeouts, save_queue_settings, AgentQueueState,
+};
 use process::ProcessRegistryState;
 use std::sync::Mutex;
 use tauri::Manager;
@@ -56,6 +113,7 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // Initialize agents database
             let conn = init_database(&app.handle()).expect("Failed to initialize agents database");
@@ -134,9 +192,31 @@ fn main() {
             // Initialize process registry
             app.manage(ProcessRegistryState::default());
 
-            // Initialize Claude process state
+            // Initialize Claude process state and reschedule sessions waiting for a usage limit
             app.manage(ClaudeProcessState::default());
 
+            // Initialize the agent run queue and resume runs queued before the last shutdown
+            app.manage(AgentQueueState::default());
+            if let Err(e) = commands::queue::requeue_orphaned_runs(app.handle()) {
+                log::warn!("Failed to requeue runs left pending: {}", e);
+            }
+            commands::queue::schedule_dispatch(app.handle());
+
+            if let Err(e) = commands::claude::restore_session_resumes(app.handle()) {
+                log::warn!("Failed to restore scheduled session resumes: {}", e);
+            }
+
+            // Finish sending webhook deliveries interrupted by the last shutdown
+            if let Err(e) = commands::webhooks::resume_pending_deliveries(app.handle()) {
+                log::warn!("Failed to resume pending webhook deliveries: {}", e);
+            }
+
+            // Start the cron scheduler for agent schedules
+            scheduler::start_scheduler(app.handle().clone());
+
+            // Periodically check imported agents for updates at their source
+            commands::agent_updates::start_update_checker(app.handle().clone());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -157,6 +237,7 @@ fn main() {
             continue_claude_code,
             resume_claude_code,
             cancel_claude_execution,
+            cancel_session_resume,
             list_running_claude_sessions,
             get_claude_session_output,
             list_directory_contents,
@@ -186,6 +267,7 @@ fn main() {
             list_agents,
             create_agent,
             update_agent,
+            update_agent_budget,
             delete_agent,
             get_agent,
             execute_agent,
@@ -208,9 +290,121 @@ fn main() {
             export_agent_to_file,
             import_agent,
             import_agent_from_file,
-            fetch_github_agents,
-            fetch_github_agent_content,
-            import_agent_from_github,
+            get_agent_export_schema,
+            update_agent_requirements,
+            list_agent_env_vars,
+            set_agent_env_var,
+            delete_agent_env_var,
+            set_agent_output_schema,
+            set_agent_run_annotation,
+            list_agent_run_tags,
+            get_agent_run_stats,
+            restore_agent_run_changes,
+            set_agent_folder,
+            set_agent_tags,
+            set_agent_favorite,
+            list_agent_folders,
+            list_agent_tags,
+
+            // Webhooks
+            list_webhooks,
+            create_webhook,
+            update_webhook,
+            delete_webhook,
+            list_webhook_deliveries,
+            test_webhook,
+
+            // Desktop notifications
+            get_notification_preferences,
+            save_notification_preferences,
+            send_test_notification,
+            
+            // Agent Registries
+            list_agent_registries,
+            create_agent_registry,
+            update_agent_registry,
+            delete_agent_registry,
+            fetch_registry_agents,
+            fetch_registry_agent_content,
+            import_agent_from_registry,
+            
+            // Agent Updates
+            check_agent_updates,
+            get_agent_update_diff,
+            apply_agent_update,
+            
+            // Claude Code Subagents
+            list_native_subagents,
+            import_native_subagent,
+            export_agent_to_native,
+            sync_native_subagents,
+            
+            // Agent Run Queue
+            get_queue_settings,
+            save_queue_settings,
+            get_process_timeouts,
+            save_process_timeouts,
+            list_queued_runs,
+            cancel_queued_run,
+            rerun_agent_run,
+            rerun_agent_runs,
+            
+            // Agent Batches
+            execute_agent_batch,
+            get_agent_batch,
+            list_agent_batches,
+            cancel_agent_batch,
+            
+            // Model Comparisons
+            compare_agent_models,
+            get_agent_comparison,
+            list_agent_comparisons,
+            delete_agent_comparison,
+            
+            // Agent Evals
+            list_eval_suites,
+            get_eval_suite,
+            create_eval_suite,
+            update_eval_suite,
+            delete_eval_suite,
+            run_eval_suite,
+            get_eval_run,
+            list_eval_runs,
+            cancel_eval_run,
+            
+            // Agent Schedules
+            list_agent_schedules,
+            create_agent_schedule,
+            update_agent_schedule,
+            delete_agent_schedule,
+            run_agent_schedule_now,
+            preview_cron_schedule,
+            
+            // Agent Task Templates
+            render_agent_task,
+            
+            // Agent Revisions
+            list_agent_revisions,
+            get_agent_revision,
+            diff_agent_revisions,
+            restore_agent_revision,
+            
+            // Agent Pipelines
+            list_pipelines,
+            get_pipeline,
+            create_pipeline,
+            update_pipeline,
+            delete_pipeline,
+            run_pipeline,
+            list_pipeline_runs,
+            get_pipeline_run,
+            cancel_pipeline_run,
+            
+            // Agent Worktrees
+            get_run_worktree_diff,
+            merge_run_worktree,
+            keep_run_worktree,
+            discard_run_worktree,
             
             // Usage & Analytics
             get_usage_stats,
@@ -0,0 +1,323 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::registry::ProcessRegistry;
+use super::watchdog::TERMINATION_GRACE;
+use crate::commands::usage::{calculate_cost, UsageData};
+
+/// Caps for a single agent run; unset limits are unlimited
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct RunBudget {
+    pub max_duration_secs: Option<i64>,
+    /// Input plus output tokens, as shown in the run metrics
+    pub max_tokens: Option<i64>,
+    pub max_cost_usd: Option<f64>,
+}
+
+impl RunBudget {
+    /// Limits set on `self` take precedence over the ones in `fallback`
+    pub fn or(self, fallback: RunBudget) -> RunBudget {
+        RunBudget {
+            max_duration_secs: self.max_duration_secs.or(fallback.max_duration_secs),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            max_cost_usd: self.max_cost_usd.or(fallback.max_cost_usd),
+        }
+    }
+
+    /// Read a budget from three consecutive columns starting at `index`
+    pub fn from_row(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Self> {
+        Ok(RunBudget {
+            max_duration_secs: row.get(index)?,
+            max_tokens: row.get(index + 1)?,
+            max_cost_usd: row.get(index + 2)?,
+        })
+    }
+}
+
+/// Tracks token usage and cost of a running agent from its stream-json output
+#[derive(Debug)]
+pub struct BudgetTracker {
+    budget: RunBudget,
+    tokens: i64,
+    /// Cost of the current attempt
+    cost_usd: f64,
+    /// Cost of the finished attempts of a run resumed after a usage limit
+    prior_cost_usd: f64,
+    /// Assistant messages are streamed once per content block with the same usage
+    seen_messages: HashSet<String>,
+    exceeded: bool,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: RunBudget) -> Self {
+        Self {
+            budget,
+            tokens: 0,
+            cost_usd: 0.0,
+            prior_cost_usd: 0.0,
+            seen_messages: HashSet::new(),
+            exceeded: false,
+        }
+    }
+
+    /// Tracker for a run resumed after a usage limit, so that the usage of its earlier
+    /// attempts counts towards the budget too
+    pub fn resumed(budget: RunBudget, previous_output: &str) -> Self {
+        let mut tracker = Self::new(budget);
+        for line in previous_output.lines() {
+            tracker.record(line);
+        }
+        // The last attempt may have stopped before reporting its result
+        tracker.prior_cost_usd += tracker.cost_usd;
+        tracker.cost_usd = 0.0;
+        tracker
+    }
+
+    /// Account for one line of stream-json output. Returns the reason the first
+    /// time the token or cost cap is exceeded.
+    pub fn observe(&mut self, line: &str) -> Option<String> {
+        if self.exceeded || !self.record(line) {
+            return None;
+        }
+
+        let reason = self.exceeded_reason()?;
+        self.exceeded = true;
+        Some(reason)
+    }
+
+    /// Add the usage reported by a line; returns whether it reported any
+    fn record(&mut self, line: &str) -> bool {
+        self.record_json(line).is_some()
+    }
+
+    fn record_json(&mut self, line: &str) -> Option<()> {
+        let json: serde_json::Value = serde_json::from_str(line).ok()?;
+
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("assistant") => {
+                let message = json.get("message")?;
+                if let Some(id) = message.get("id").and_then(|id| id.as_str()) {
+                    if !self.seen_messages.insert(id.to_string()) {
+                        return None;
+                    }
+                }
+                let usage: UsageData = serde_json::from_value(message.get("usage")?.clone()).ok()?;
+                self.tokens += (usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0)) as i64;
+                if let Some(model) = message.get("model").and_then(|m| m.as_str()) {
+                    self.cost_usd += calculate_cost(model, &usage);
+                }
+            }
+            Some("result") => {
+                // The final result carries the authoritative cost of the attempt
+                if let Some(total) = json.get("total_cost_usd").and_then(|c| c.as_f64()) {
+                    self.cost_usd = self.cost_usd.max(total);
+                }
+                self.prior_cost_usd += self.cost_usd;
+                self.cost_usd = 0.0;
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn total_cost_usd(&self) -> f64 {
+        self.prior_cost_usd + self.cost_usd
+    }
+
+    fn exceeded_reason(&self) -> Option<String> {
+        if let Some(max_tokens) = self.budget.max_tokens {
+            if self.tokens > max_tokens {
+                return Some(format!(
+                    "Token budget exceeded: {} of {} tokens used",
+                    self.tokens, max_tokens
+                ));
+            }
+        }
+        if let Some(max_cost) = self.budget.max_cost_usd {
+            if self.total_cost_usd() > max_cost {
+                return Some(format!(
+                    "Cost budget exceeded: ${:.2} of ${:.2} spent",
+                    self.total_cost_usd(),
+                    max_cost
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Mark a run as over budget and terminate its process. Does nothing if the run already finished.
+pub async fn enforce_budget(
+    app: &AppHandle,
+    registry: &ProcessRegistry,
+    db_path: &Path,
+    run_id: i64,
+    reason: &str,
+) {
+    // Update the status first so the completion handler doesn't overwrite it
+    let updated = Connection::open(db_path)
+        .and_then(|conn| {
+            conn.execute(
+                "UPDATE agent_runs SET status = 'budget_exceeded', status_reason = ?1, completed_at = CURRENT_TIMESTAMP WHERE id = ?2 AND status IN ('pending', 'running')",
+                params![reason, run_id],
+            )
+        })
+        .unwrap_or(0);
+    if updated == 0 {
+        return;
+    }
+
+    warn!("💸 Agent run {} stopped: {}", run_id, reason);
+    let _ = app.emit(&format!("agent-budget-exceeded:{}", run_id), reason);
+
+    match registry.terminate_process(run_id, TERMINATION_GRACE).await {
+        Ok(true) => info!("Stopped agent run {} after exceeding its budget", run_id),
+        Ok(false) => warn!("Agent run {} was not found in the registry", run_id),
+        Err(e) => warn!("Failed to kill agent run {}: {}", run_id, e),
+    }
+}
+
+/// Time a run resumed after a usage limit spent running in its earlier attempts
+pub fn prior_duration(conn: &Connection, run_id: i64) -> Duration {
+    conn.query_row(
+        "SELECT prior_duration_secs FROM agent_runs WHERE id = ?1",
+        params![run_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|secs| Duration::from_secs(secs.max(0) as u64))
+    .unwrap_or_default()
+}
+
+/// Stop the run once its wall-clock budget has elapsed, counting the `prior` time of
+/// earlier attempts. The returned task should be aborted when the attempt ends.
+pub fn spawn_duration_watchdog(
+    app: AppHandle,
+    registry: Arc<ProcessRegistry>,
+    db_path: std::path::PathBuf,
+    run_id: i64,
+    budget: RunBudget,
+    prior: Duration,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let max_secs = budget.max_duration_secs.filter(|secs| *secs > 0)?;
+    let remaining = Duration::from_secs(max_secs as u64).saturating_sub(prior);
+
+    Some(tokio::spawn(async move {
+        tokio::time::sleep(remaining).await;
+        let reason = format!("Time budget exceeded: ran for more than {} seconds", max_secs);
+        enforce_budget(&app, &registry, &db_path, run_id, &reason).await;
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assistant(id: &str, input: u64, output: u64) -> String {
+        serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "id": id,
+                "model": "claude-sonnet-4-20250514",
+                "usage": { "input_tokens": input, "output_tokens": output }
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_token_budget() {
+        let mut tracker = BudgetTracker::new(RunBudget {
+            max_tokens: Some(1_000),
+            ..Default::default()
+        });
+
+        assert_eq!(tracker.observe(&assistant("msg_1", 400, 100)), None);
+        // The same message streamed again for another content block
+        assert_eq!(tracker.observe(&assistant("msg_1", 400, 100)), None);
+        assert_eq!(tracker.tokens, 500);
+
+        assert!(tracker.observe(&assistant("msg_2", 400, 200)).is_some());
+        // Reported only once
+        assert_eq!(tracker.observe(&assistant("msg_3", 400, 200)), None);
+    }
+
+    #[test]
+    fn test_cost_budget() {
+        let mut tracker = BudgetTracker::new(RunBudget {
+            max_cost_usd: Some(1.0),
+            ..Default::default()
+        });
+
+        // 100k input tokens at $3/M and 10k output tokens at $15/M
+        assert_eq!(tracker.observe(&assistant("msg_1", 100_000, 10_000)), None);
+        assert!((tracker.cost_usd - 0.45).abs() < 1e-9);
+
+        let result = serde_json::json!({ "type": "result", "total_cost_usd": 1.25 }).to_string();
+        assert!(tracker.observe(&result).is_some());
+    }
+
+    #[test]
+    fn test_resumed_budget() {
+        let budget = RunBudget {
+            max_tokens: Some(1_000),
+            max_cost_usd: Some(1.0),
+            ..Default::default()
+        };
+        // The first attempt stopped at a usage limit after 600 tokens and $0.60
+        let previous_output = [
+            assistant("msg_1", 500, 100),
+            serde_json::json!({ "type": "result", "total_cost_usd": 0.6 }).to_string(),
+        ]
+        .join("\n");
+
+        let mut tracker = BudgetTracker::resumed(budget, &previous_output);
+        assert_eq!(tracker.tokens, 600);
+        assert!((tracker.total_cost_usd() - 0.6).abs() < 1e-9);
+
+        // The resumed attempt only has 400 tokens left
+        let reason = tracker.observe(&assistant("msg_2", 300, 200));
+        assert_eq!(
+            reason.as_deref(),
+            Some("Token budget exceeded: 1100 of 1000 tokens used")
+        );
+
+        // Cost reported by the resumed attempt adds to the earlier attempts
+        let mut tracker = BudgetTracker::resumed(
+            RunBudget {
+                max_cost_usd: Some(1.0),
+                ..Default::default()
+            },
+            &previous_output,
+        );
+        let result = serde_json::json!({ "type": "result", "total_cost_usd": 0.5 }).to_string();
+        assert!(tracker.observe(&result).is_some());
+    }
+
+    #[test]
+    fn test_budget_precedence() {
+        let run = RunBudget {
+            max_tokens: Some(10),
+            ..Default::default()
+        };
+        let agent = RunBudget {
+            max_tokens: Some(20),
+            max_cost_usd: Some(2.0),
+            max_duration_secs: None,
+        };
+        assert_eq!(
+            run.or(agent),
+            RunBudget {
+                max_tokens: Some(10),
+                max_cost_usd: Some(2.0),
+                max_duration_secs: None,
+            }
+        );
+    }
+}
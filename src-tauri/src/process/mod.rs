@@ -1,3 +1,7 @@
+pub mod budget;
 pub mod registry;
+pub mod run_log;
+pub mod usage_limit;
+pub mod watchdog;
 
 pub use registry::*;
@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::process::Child;
 
 /// Type of process being tracked
@@ -237,6 +238,42 @@ impl ProcessRegistry {
         Ok(processes.get(&run_id).map(|handle| handle.info.clone()))
     }
 
+    /// Ask a process to exit with SIGTERM and kill it if it is still running after `grace`
+    pub async fn terminate_process(&self, run_id: i64, grace: Duration) -> Result<bool, String> {
+        let (pid, child_arc) = {
+            let processes = self.processes.lock().map_err(|e| e.to_string())?;
+            match processes.get(&run_id) {
+                Some(handle) => (handle.info.pid, handle.child.clone()),
+                None => return Ok(false),
+            }
+        };
+
+        if terminate_gracefully(pid, Some(&child_arc), grace).await {
+            log::info!("Process {} exited after SIGTERM", run_id);
+            self.unregister_process(run_id)?;
+            return Ok(true);
+        }
+        self.kill_process(run_id).await
+    }
+
+    /// Terminate a process that is not registered (yet), such as a session that
+    /// never sent its init message. Returns false if it had to be killed.
+    pub async fn terminate_pid(pid: u32, grace: Duration) -> bool {
+        if terminate_gracefully(pid, None, grace).await {
+            return true;
+        }
+        log::warn!("Killing PID {}", pid);
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGKILL);
+        }
+        #[cfg(not(unix))]
+        let _ = std::process::Command::new("taskkill")
+            .args(["/F", "/PID", &pid.to_string()])
+            .output();
+        false
+    }
+
     /// Kill a running process with proper cleanup
     pub async fn kill_process(&self, run_id: i64) -> Result<bool, String> {
         use log::{error, info, warn};
@@ -514,6 +551,66 @@ impl ProcessRegistry {
     }
 }
 
+/// Send SIGTERM and wait up to `grace` for the process to exit. Returns false if it
+/// is still running or graceful termination isn't available on this platform.
+async fn terminate_gracefully(
+    pid: u32,
+    child: Option<&Arc<Mutex<Option<Child>>>>,
+    grace: Duration,
+) -> bool {
+    // Signalling PID 0 would hit our whole process group
+    if pid == 0 || !send_terminate(pid) {
+        return false;
+    }
+    log::info!("Sent SIGTERM to PID {}, waiting up to {:?}", pid, grace);
+
+    let deadline = tokio::time::Instant::now() + grace;
+    while tokio::time::Instant::now() < deadline {
+        if !is_alive(pid, child) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    log::warn!("PID {} still running {:?} after SIGTERM", pid, grace);
+    false
+}
+
+#[cfg(unix)]
+fn send_terminate(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) == 0 }
+}
+
+#[cfg(not(unix))]
+fn send_terminate(_pid: u32) -> bool {
+    false
+}
+
+/// Whether the process is still running, reaping it through its child handle if we own one
+fn is_alive(pid: u32, child: Option<&Arc<Mutex<Option<Child>>>>) -> bool {
+    if let Some(child_arc) = child {
+        if let Ok(mut child_guard) = child_arc.lock() {
+            if let Some(child) = child_guard.as_mut() {
+                return match child.try_wait() {
+                    Ok(None) => true,
+                    _ => {
+                        *child_guard = None;
+                        false
+                    }
+                };
+            }
+        }
+    }
+    #[cfg(unix)]
+    {
+        unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
 impl Default for ProcessRegistry {
     fn default() -> Self {
         Self::new()
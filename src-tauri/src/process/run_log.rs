@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+/// zstd compression level used for persisted run output
+const RUN_LOG_COMPRESSION_LEVEL: i32 = 3;
+
+/// Directory under the app data dir that holds persisted agent run output
+pub fn run_logs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_dir.join("run_logs"))
+}
+
+/// Path of the compressed output log for a given agent run
+pub fn run_log_path(logs_dir: &Path, run_id: i64) -> PathBuf {
+    logs_dir.join(format!("{}.jsonl.zst", run_id))
+}
+
+/// Compress and write the raw stream-json output of a run to disk.
+/// Returns the path the log was written to.
+pub fn write_run_log(logs_dir: &Path, run_id: i64, output: &str) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(logs_dir)
+        .map_err(|e| format!("Failed to create run log directory: {}", e))?;
+
+    let compressed = zstd::encode_all(output.as_bytes(), RUN_LOG_COMPRESSION_LEVEL)
+        .map_err(|e| format!("Failed to compress run output: {}", e))?;
+
+    let path = run_log_path(logs_dir, run_id);
+    std::fs::write(&path, compressed).map_err(|e| format!("Failed to write run log: {}", e))?;
+
+    Ok(path)
+}
+
+/// Read and decompress a persisted run log
+pub fn read_run_log(path: &Path) -> Result<String, String> {
+    let compressed =
+        std::fs::read(path).map_err(|e| format!("Failed to read run log {}: {}", path.display(), e))?;
+    let raw = zstd::decode_all(compressed.as_slice())
+        .map_err(|e| format!("Failed to decompress run log: {}", e))?;
+
+    Ok(String::from_utf8_lossy(&raw).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_log_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = "{\"type\":\"system\",\"subtype\":\"init\"}\n{\"type\":\"result\"}\n";
+
+        let path = write_run_log(temp_dir.path(), 42, output).unwrap();
+        assert_eq!(path, run_log_path(temp_dir.path(), 42));
+        assert_eq!(read_run_log(&path).unwrap(), output);
+    }
+}
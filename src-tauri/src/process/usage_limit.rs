@@ -0,0 +1,348 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use log::{error, info, warn};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands::agents::AgentDb;
+
+/// Prompt sent to a session resumed after its usage limit reset
+pub const RESUME_PROMPT: &str = "The usage limit has reset. Continue where you left off.";
+
+/// Wait before resuming when a usage limit message carries no reset time
+const USAGE_LIMIT_FALLBACK_MINUTES: i64 = 60;
+
+/// Wait before resuming after an API rate limit, which clears much sooner
+const RATE_LIMIT_FALLBACK_MINUTES: i64 = 5;
+
+/// A usage or rate limit reported by Claude
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageLimit {
+    pub message: String,
+    /// When the limit resets; estimated if the message carries no reset time
+    pub reset_at: DateTime<Utc>,
+}
+
+impl UsageLimit {
+    /// Status reason recorded on a rate limited run
+    pub fn reason(&self) -> String {
+        format!(
+            "Usage limit reached, resuming at {}",
+            self.reset_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        )
+    }
+}
+
+/// The text of a line that may report a limit: error results and the synthetic
+/// messages the CLI emits for API errors. Regular assistant messages, tool output
+/// and other unstructured output are ignored so that code or answers mentioning
+/// rate limits aren't mistaken for one.
+fn limit_text(line: &str) -> Option<String> {
+    let json: Value = serde_json::from_str(line).ok()?;
+
+    match json.get("type").and_then(|t| t.as_str()) {
+        Some("result") if json.get("is_error").and_then(|e| e.as_bool()) == Some(true) => json
+            .get("result")
+            .and_then(|r| r.as_str())
+            .map(String::from),
+        Some("assistant") => {
+            let message = json.get("message")?;
+            if message.get("model").and_then(|m| m.as_str()) != Some("<synthetic>") {
+                return None;
+            }
+            let text: Vec<&str> = message
+                .get("content")?
+                .as_array()?
+                .iter()
+                .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+                .collect();
+            Some(text.join("\n"))
+        }
+        _ => None,
+    }
+}
+
+/// The text of a stderr line that reports a limit. Only the CLI's own messages match,
+/// `Claude AI usage limit reached|<unix seconds>` and `API Error: 429 ... rate_limit_error`,
+/// so tools that log their own rate limits to stderr aren't mistaken for one.
+fn stderr_limit_text(line: &str) -> Option<String> {
+    let pattern = Regex::new(
+        r"^(?:Error:\s*)?(?:Claude AI usage limit reached\|\d{9,}|API Error: 429\b.*\brate_limit_error\b.*)$",
+    )
+    .ok()?;
+    let line = line.trim();
+    pattern.is_match(line).then(|| line.to_string())
+}
+
+/// Recognise a usage or rate limit message in a line of Claude's stream-json output
+pub fn detect_usage_limit(line: &str, now: DateTime<Local>) -> Option<UsageLimit> {
+    usage_limit_from_text(limit_text(line)?, now)
+}
+
+/// Recognise a usage or rate limit message in a line Claude wrote to stderr
+pub fn detect_stderr_usage_limit(line: &str, now: DateTime<Local>) -> Option<UsageLimit> {
+    usage_limit_from_text(stderr_limit_text(line)?, now)
+}
+
+fn usage_limit_from_text(text: String, now: DateTime<Local>) -> Option<UsageLimit> {
+    let lower = text.to_lowercase();
+
+    let fallback_minutes = if lower.contains("usage limit") || lower.contains("limit reached") {
+        USAGE_LIMIT_FALLBACK_MINUTES
+    } else if lower.contains("rate_limit_error") || lower.contains("rate limit") {
+        RATE_LIMIT_FALLBACK_MINUTES
+    } else {
+        return None;
+    };
+
+    let reset_at = parse_reset_time(&text, now)
+        .unwrap_or_else(|| now.with_timezone(&Utc) + Duration::minutes(fallback_minutes));
+    Some(UsageLimit {
+        message: text.trim().to_string(),
+        reset_at,
+    })
+}
+
+/// Reset time of a limit message: either `limit reached|<unix seconds>` or
+/// "resets at 3pm" / "reset at 15:30", taken as the next such local time
+fn parse_reset_time(text: &str, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+    let timestamp = Regex::new(r"(?i)limit reached\|(\d{9,})").ok()?;
+    if let Some(captures) = timestamp.captures(text) {
+        let secs = captures[1].parse::<i64>().ok()?;
+        return Utc.timestamp_opt(secs, 0).single();
+    }
+
+    let clock = Regex::new(r"(?i)resets?\s+(?:at\s+)?(\d{1,2})(?::(\d{2}))?\s*(am|pm)?").ok()?;
+    let captures = clock.captures(text)?;
+    let minute = match captures.get(2) {
+        Some(minute) => minute.as_str().parse::<u32>().ok()?,
+        None => 0,
+    };
+    let mut hour = captures[1].parse::<u32>().ok()?;
+    match captures.get(3).map(|m| m.as_str().to_lowercase()) {
+        Some(meridiem) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour %= 12;
+            if meridiem == "pm" {
+                hour += 12;
+            }
+        }
+        // A bare number such as "resets 5" is too ambiguous to schedule on
+        None if captures.get(2).is_none() => return None,
+        None => {}
+    }
+
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+    let mut reset = now.date_naive().and_time(time);
+    if reset <= now.naive_local() {
+        reset += Duration::days(1);
+    }
+    Local
+        .from_local_datetime(&reset)
+        .earliest()
+        .map(|reset| reset.with_timezone(&Utc))
+}
+
+/// Remembers the first limit reported in the output of a process
+#[derive(Debug, Clone, Default)]
+pub struct UsageLimitTracker(Arc<Mutex<Option<UsageLimit>>>);
+
+impl UsageLimitTracker {
+    /// Check one line of stream-json output. Returns the limit the first time one is seen.
+    pub fn observe(&self, line: &str) -> Option<UsageLimit> {
+        self.record(|now| detect_usage_limit(line, now))
+    }
+
+    /// Check one line of stderr. Returns the limit the first time one is seen.
+    pub fn observe_stderr(&self, line: &str) -> Option<UsageLimit> {
+        self.record(|now| detect_stderr_usage_limit(line, now))
+    }
+
+    fn record(&self, detect: impl FnOnce(DateTime<Local>) -> Option<UsageLimit>) -> Option<UsageLimit> {
+        let mut detected = self.0.lock().ok()?;
+        if detected.is_some() {
+            return None;
+        }
+        let limit = detect(Local::now())?;
+        warn!("⏳ Claude reported a usage limit: {}", limit.message);
+        *detected = Some(limit.clone());
+        Some(limit)
+    }
+
+    pub fn detected(&self) -> Option<UsageLimit> {
+        self.0.lock().ok().and_then(|detected| detected.clone())
+    }
+}
+
+/// Mark a run that stopped at a usage limit as rate limited until the reset time, adding
+/// the time the attempt ran to the run's time budget usage.
+/// Returns false if the run had already finished or been cancelled.
+pub fn mark_rate_limited(
+    db_path: &Path,
+    run_id: i64,
+    limit: &UsageLimit,
+    attempt_duration: std::time::Duration,
+) -> bool {
+    let updated = Connection::open(db_path).and_then(|conn| {
+        conn.execute(
+            "UPDATE agent_runs SET status = 'rate_limited', status_reason = ?1, resume_at = ?2, completed_at = NULL, prior_duration_secs = prior_duration_secs + ?3 WHERE id = ?4 AND status IN ('pending', 'running')",
+            params![
+                limit.reason(),
+                limit.reset_at.to_rfc3339(),
+                attempt_duration.as_secs() as i64,
+                run_id
+            ],
+        )
+    });
+    match updated {
+        Ok(updated) => {
+            if updated > 0 {
+                info!(
+                    "⏳ Agent run {} is rate limited until {}",
+                    run_id, limit.reset_at
+                );
+            }
+            updated > 0
+        }
+        Err(e) => {
+            error!("❌ Failed to mark run {} as rate limited: {}", run_id, e);
+            false
+        }
+    }
+}
+
+/// Put rate limited runs whose limit has reset back on the queue. They resume their
+/// session when started.
+pub fn requeue_due_runs(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM agent_runs WHERE status = 'rate_limited' AND resume_at <= ?1")
+        .map_err(|e| e.to_string())?;
+    let run_ids = stmt
+        .query_map(params![now.to_rfc3339()], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for run_id in &run_ids {
+        conn.execute(
+            "UPDATE agent_runs SET status = 'queued', status_reason = NULL WHERE id = ?1 AND status = 'rate_limited'",
+            params![run_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(run_ids)
+}
+
+/// Requeue the runs whose usage limit has reset and dispatch them
+pub fn resume_rate_limited_runs(app: &AppHandle) {
+    let requeued = {
+        let db = app.state::<AgentDb>();
+        let conn = match db.0.lock() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to lock database to resume rate limited runs: {}", e);
+                return;
+            }
+        };
+        requeue_due_runs(&conn, Utc::now())
+    };
+
+    match requeued {
+        Ok(run_ids) if !run_ids.is_empty() => {
+            for run_id in &run_ids {
+                info!(
+                    "⏳ Usage limit of run {} has reset, queueing it to resume",
+                    run_id
+                );
+                let _ = app.emit(&format!("agent-resumed:{}", run_id), run_id);
+            }
+            crate::commands::queue::schedule_dispatch(app);
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to requeue rate limited runs: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2025, 6, 10, hour, minute, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_detect_unix_timestamp() {
+        let line = r#"{"type":"result","subtype":"success","is_error":true,"result":"Claude AI usage limit reached|1750000000"}"#;
+        let limit = detect_usage_limit(line, at(9, 0)).unwrap();
+        assert_eq!(limit.reset_at.timestamp(), 1_750_000_000);
+    }
+
+    #[test]
+    fn test_detect_clock_time() {
+        let line = r#"{"type":"assistant","message":{"model":"<synthetic>","content":[{"type":"text","text":"5-hour limit reached ∙ resets 3pm"}]}}"#;
+        let limit = detect_usage_limit(line, at(9, 0)).unwrap();
+        assert_eq!(limit.reset_at, at(15, 0).with_timezone(&Utc));
+
+        // A time earlier in the day means tomorrow
+        let line = r#"{"type":"result","subtype":"success","is_error":true,"result":"Usage limit reached, resets at 8:30am"}"#;
+        let limit = detect_usage_limit(line, at(9, 0)).unwrap();
+        assert_eq!(
+            limit.reset_at,
+            (at(8, 30) + Duration::days(1)).with_timezone(&Utc)
+        );
+    }
+
+    #[test]
+    fn test_detect_without_reset_time() {
+        let now = at(9, 0);
+        let line = r#"{"type":"assistant","message":{"model":"<synthetic>","content":[{"type":"text","text":"API Error: 429 {\"type\":\"error\",\"error\":{\"type\":\"rate_limit_error\"}}"}]}}"#;
+        let limit = detect_usage_limit(line, now).unwrap();
+        assert_eq!(
+            limit.reset_at,
+            now.with_timezone(&Utc) + Duration::minutes(RATE_LIMIT_FALLBACK_MINUTES)
+        );
+    }
+
+    #[test]
+    fn test_ignores_regular_output() {
+        let now = at(9, 0);
+        let answer = r#"{"type":"result","subtype":"success","is_error":false,"result":"Added a rate limit to the API"}"#;
+        assert_eq!(detect_usage_limit(answer, now), None);
+        let message = r#"{"type":"assistant","message":{"model":"claude-sonnet-4-20250514","content":[{"type":"text","text":"The usage limit reached its maximum"}]}}"#;
+        assert_eq!(detect_usage_limit(message, now), None);
+        assert_eq!(detect_usage_limit("Compiling crate", now), None);
+        // Unstructured output, such as stderr of a tool that hit a rate limit
+        assert_eq!(detect_usage_limit("Error: GitHub API rate limit exceeded", now), None);
+    }
+
+    #[test]
+    fn test_detect_stderr() {
+        let now = at(9, 0);
+        let limit = detect_stderr_usage_limit("Claude AI usage limit reached|1750000000\n", now).unwrap();
+        assert_eq!(limit.message, "Claude AI usage limit reached|1750000000");
+        assert_eq!(limit.reset_at.timestamp(), 1_750_000_000);
+
+        let line = r#"API Error: 429 {"type":"error","error":{"type":"rate_limit_error","message":"Rate limited"}}"#;
+        let limit = detect_stderr_usage_limit(line, now).unwrap();
+        assert_eq!(
+            limit.reset_at,
+            now.with_timezone(&Utc) + Duration::minutes(RATE_LIMIT_FALLBACK_MINUTES)
+        );
+
+        // Other programs mentioning limits on stderr are not Claude's limit
+        assert_eq!(detect_stderr_usage_limit("Error: GitHub API rate limit exceeded", now), None);
+        assert_eq!(detect_stderr_usage_limit("warning: usage limit reached for cache", now), None);
+        assert_eq!(detect_stderr_usage_limit("npm ERR! API Error: 429 rate_limit_error", now), None);
+    }
+}
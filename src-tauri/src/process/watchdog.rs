@@ -0,0 +1,293 @@
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::registry::ProcessRegistry;
+
+/// How long a stopped process gets to exit after SIGTERM before it is killed
+pub const TERMINATION_GRACE: Duration = Duration::from_secs(5);
+
+/// How often the watchdog checks a process
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Limits for Claude processes, both interactive sessions and agent runs; unset limits are disabled
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ProcessTimeouts {
+    /// Seconds to wait for the first line of output
+    pub startup_secs: Option<u64>,
+    /// Seconds without any output after which the process is considered stalled
+    pub idle_secs: Option<u64>,
+    /// Hard wall-clock limit in seconds
+    pub max_duration_secs: Option<u64>,
+}
+
+impl Default for ProcessTimeouts {
+    fn default() -> Self {
+        Self {
+            startup_secs: Some(30),
+            idle_secs: None,
+            max_duration_secs: None,
+        }
+    }
+}
+
+impl ProcessTimeouts {
+    fn is_disabled(&self) -> bool {
+        self.startup_secs.is_none() && self.idle_secs.is_none() && self.max_duration_secs.is_none()
+    }
+
+    /// The limit a process has hit after running for `elapsed`, whose last output was
+    /// at `last_output` (measured from the start), if any
+    pub fn check(&self, elapsed: Duration, last_output: Option<Duration>) -> Option<StallReason> {
+        if let Some(max) = self.max_duration_secs {
+            if elapsed >= Duration::from_secs(max) {
+                return Some(StallReason::MaxDuration(max));
+            }
+        }
+        match last_output {
+            None => self
+                .startup_secs
+                .filter(|secs| elapsed >= Duration::from_secs(*secs))
+                .map(StallReason::Startup),
+            Some(last_output) => self
+                .idle_secs
+                .filter(|secs| elapsed.saturating_sub(last_output) >= Duration::from_secs(*secs))
+                .map(StallReason::Idle),
+        }
+    }
+}
+
+/// Why the watchdog stopped a process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StallReason {
+    /// No output within the startup timeout
+    Startup(u64),
+    /// No output for the idle timeout
+    Idle(u64),
+    /// Ran longer than the wall-clock limit
+    MaxDuration(u64),
+}
+
+impl fmt::Display for StallReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StallReason::Startup(secs) => {
+                write!(f, "No output within {} of starting", format_secs(*secs))
+            }
+            StallReason::Idle(secs) => write!(f, "Stalled: no output for {}", format_secs(*secs)),
+            StallReason::MaxDuration(secs) => {
+                write!(f, "Exceeded the wall-clock limit of {}", format_secs(*secs))
+            }
+        }
+    }
+}
+
+fn format_secs(secs: u64) -> String {
+    let plural = |n: u64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    if secs >= 3600 && secs.is_multiple_of(3600) {
+        plural(secs / 3600, "hour")
+    } else if secs >= 60 && secs.is_multiple_of(60) {
+        plural(secs / 60, "minute")
+    } else {
+        plural(secs, "second")
+    }
+}
+
+/// Output activity of a process, shared between its readers and its watchdog
+#[derive(Debug, Clone)]
+pub struct ActivityMonitor {
+    started: Instant,
+    /// Milliseconds from the start to the last output, plus one; 0 until the first output
+    last_output_ms: Arc<AtomicU64>,
+    finished: Arc<AtomicBool>,
+}
+
+impl Default for ActivityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActivityMonitor {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_output_ms: Arc::new(AtomicU64::new(0)),
+            finished: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Record a line of output. Returns true for the first one.
+    pub fn touch(&self) -> bool {
+        let now = self.started.elapsed().as_millis() as u64 + 1;
+        self.last_output_ms.swap(now, Ordering::Relaxed) == 0
+    }
+
+    /// Mark the process as exited, which stops its watchdog
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+    }
+
+    fn last_output(&self) -> Option<Duration> {
+        match self.last_output_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms - 1)),
+        }
+    }
+}
+
+/// Watch a process and call `on_stall` once it hits one of the timeouts. The watchdog
+/// ends when the activity monitor is finished.
+pub fn spawn_watchdog<F, Fut>(timeouts: ProcessTimeouts, activity: ActivityMonitor, on_stall: F)
+where
+    F: FnOnce(StallReason) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    if timeouts.is_disabled() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if activity.finished.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(reason) = timeouts.check(activity.started.elapsed(), activity.last_output())
+            {
+                on_stall(reason).await;
+                return;
+            }
+        }
+    });
+}
+
+/// Mark a stalled run as timed out and terminate its process. Does nothing if the run already finished.
+pub async fn stop_stalled_run(
+    app: &AppHandle,
+    registry: &ProcessRegistry,
+    db_path: &Path,
+    run_id: i64,
+    reason: StallReason,
+) {
+    let reason_text = reason.to_string();
+    // Update the status first so the completion handler doesn't overwrite it
+    let updated = Connection::open(db_path)
+        .and_then(|conn| {
+            conn.execute(
+                "UPDATE agent_runs SET status = 'timed_out', status_reason = ?1, completed_at = CURRENT_TIMESTAMP WHERE id = ?2 AND status IN ('pending', 'running')",
+                params![reason_text, run_id],
+            )
+        })
+        .unwrap_or(0);
+    if updated == 0 {
+        return;
+    }
+
+    warn!("⏰ Agent run {} stopped: {}", run_id, reason_text);
+    if let StallReason::Startup(_) = reason {
+        warn!("💡 Claude may be waiting for input, failing to authenticate or unable to reach the API");
+    }
+    let _ = app.emit(&format!("agent-timeout:{}", run_id), &reason_text);
+
+    match registry.terminate_process(run_id, TERMINATION_GRACE).await {
+        Ok(true) => info!("Terminated timed out agent run {}", run_id),
+        Ok(false) => warn!("Agent run {} was not found in the registry", run_id),
+        Err(e) => warn!("Failed to terminate agent run {}: {}", run_id, e),
+    }
+}
+
+pub(crate) const TIMEOUT_KEYS: [&str; 3] = [
+    "process_startup_timeout_secs",
+    "process_idle_timeout_secs",
+    "process_max_duration_secs",
+];
+
+/// Load process timeouts from app_settings, falling back to defaults. An empty value disables a limit.
+pub fn load_process_timeouts(conn: &Connection) -> ProcessTimeouts {
+    let mut timeouts = ProcessTimeouts::default();
+    for key in TIMEOUT_KEYS {
+        let Ok(value) = conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        ) else {
+            continue;
+        };
+        let limit = value.parse::<u64>().ok().filter(|secs| *secs > 0);
+        match key {
+            "process_startup_timeout_secs" => timeouts.startup_secs = limit,
+            "process_idle_timeout_secs" => timeouts.idle_secs = limit,
+            _ => timeouts.max_duration_secs = limit,
+        }
+    }
+    timeouts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_timeouts() {
+        let timeouts = ProcessTimeouts {
+            startup_secs: Some(30),
+            idle_secs: Some(600),
+            max_duration_secs: Some(3600),
+        };
+        let secs = Duration::from_secs;
+
+        assert_eq!(timeouts.check(secs(29), None), None);
+        assert_eq!(
+            timeouts.check(secs(30), None),
+            Some(StallReason::Startup(30))
+        );
+        // Idle time is measured from the last output
+        assert_eq!(timeouts.check(secs(700), Some(secs(200))), None);
+        assert_eq!(
+            timeouts.check(secs(800), Some(secs(200))),
+            Some(StallReason::Idle(600))
+        );
+        assert_eq!(
+            timeouts.check(secs(3600), Some(secs(3599))),
+            Some(StallReason::MaxDuration(3600))
+        );
+        assert_eq!(
+            ProcessTimeouts::default().check(secs(7200), Some(secs(1))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_stall_reason_message() {
+        assert_eq!(
+            StallReason::Startup(30).to_string(),
+            "No output within 30 seconds of starting"
+        );
+        assert_eq!(
+            StallReason::Idle(600).to_string(),
+            "Stalled: no output for 10 minutes"
+        );
+        assert_eq!(
+            StallReason::MaxDuration(3600).to_string(),
+            "Exceeded the wall-clock limit of 1 hour"
+        );
+    }
+
+    #[test]
+    fn test_activity_monitor() {
+        let activity = ActivityMonitor::new();
+        assert_eq!(activity.last_output(), None);
+        assert!(activity.touch());
+        assert!(!activity.touch());
+        assert!(activity.last_output().is_some());
+    }
+}
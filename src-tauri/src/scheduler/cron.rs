@@ -0,0 +1,272 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+/// Upper bound for the search of the next matching time (a little over 4 years, to cover Feb 29)
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// A parsed standard 5-field cron expression: minute hour day-of-month month day-of-week.
+/// Supports `*`, lists (`1,15`), ranges (`1-5`), steps (`*/10`, `0-30/5`), `7` as Sunday
+/// and the `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` macros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    /// Whether day-of-month was restricted (doesn't start with `*`)
+    dom_restricted: bool,
+    /// Whether day-of-week was restricted (doesn't start with `*`)
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday), got {}",
+                expression,
+                fields.len()
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, "day of week")?;
+        // Both 0 and 7 mean Sunday
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, "minute")?,
+            hours: parse_field(fields[1], 0, 23, "hour")?,
+            days_of_month: parse_field(fields[2], 1, 31, "day of month")?,
+            months: parse_field(fields[3], 1, 12, "month")?,
+            days_of_week,
+            dom_restricted: !fields[2].starts_with('*'),
+            dow_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let dom = self.days_of_month[date.day() as usize];
+        let dow = self.days_of_week[date.weekday().num_days_from_sunday() as usize];
+
+        // Classic cron semantics: when both fields are restricted either may match,
+        // otherwise both must (a `*/2` field still only allows every other day)
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// The first matching local time strictly after `after`
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(MAX_SEARCH_DAYS);
+        let mut current = start;
+
+        while current < limit {
+            if !self.months[current.month() as usize] {
+                current = first_of_next_month(current)?;
+                continue;
+            }
+            if !self.matches_day(current.date()) {
+                current = current.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.hours[current.hour() as usize] {
+                current = current.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !self.minutes[current.minute() as usize] {
+                current += Duration::minutes(1);
+                continue;
+            }
+
+            // Skip local times that don't exist because of a DST transition
+            match Local.from_local_datetime(&current).earliest() {
+                Some(time) if time > after => return Some(time),
+                _ => current += Duration::minutes(1),
+            }
+        }
+
+        None
+    }
+
+    /// All matching times in `(after, until]`, capped at `limit` entries
+    pub fn occurrences_between(
+        &self,
+        after: DateTime<Local>,
+        until: DateTime<Local>,
+        limit: usize,
+    ) -> Vec<DateTime<Local>> {
+        let mut occurrences = Vec::new();
+        let mut cursor = after;
+
+        while occurrences.len() < limit {
+            match self.next_after(cursor) {
+                Some(next) if next <= until => {
+                    occurrences.push(next);
+                    cursor = next;
+                }
+                _ => break,
+            }
+        }
+
+        occurrences
+    }
+}
+
+fn first_of_next_month(current: NaiveDateTime) -> Option<NaiveDateTime> {
+    let (year, month) = if current.month() == 12 {
+        (current.year() + 1, 1)
+    } else {
+        (current.year(), current.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)
+}
+
+/// Parse one cron field into a lookup table indexed by value
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step '{}' in {} field", step, name))?;
+                if step == 0 {
+                    return Err(format!("Step must be greater than zero in {} field", name));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, name)?, parse_value(end, name)?)
+        } else {
+            let value = parse_value(range, name)?;
+            // "5/15" means "starting at 5, every 15"
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Value '{}' out of range {}-{} in {} field",
+                range, min, max, name
+            ));
+        }
+
+        let mut value = start;
+        while value <= end {
+            allowed[value as usize] = true;
+            value += step;
+        }
+    }
+
+    Ok(allowed)
+}
+
+fn parse_value(value: &str, name: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' in {} field", value, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(y, m, d)
+                    .unwrap()
+                    .and_hms_opt(h, min, 0)
+                    .unwrap(),
+            )
+            .earliest()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+        assert!(CronSchedule::parse("0 2 * * 1-5").is_ok());
+        assert!(CronSchedule::parse("@daily").is_ok());
+    }
+
+    #[test]
+    fn test_next_after() {
+        // Nightly at 02:30
+        let nightly = CronSchedule::parse("30 2 * * *").unwrap();
+        assert_eq!(
+            nightly.next_after(local(2025, 3, 10, 1, 0)),
+            Some(local(2025, 3, 10, 2, 30))
+        );
+        assert_eq!(
+            nightly.next_after(local(2025, 3, 10, 2, 30)),
+            Some(local(2025, 3, 11, 2, 30))
+        );
+
+        // Weekdays at 09:00 (2025-03-15 is a Saturday)
+        let weekdays = CronSchedule::parse("0 9 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(local(2025, 3, 14, 10, 0)),
+            Some(local(2025, 3, 17, 9, 0))
+        );
+
+        // Every 15 minutes
+        let quarter = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(
+            quarter.next_after(local(2025, 3, 10, 1, 7)),
+            Some(local(2025, 3, 10, 1, 15))
+        );
+
+        // Mondays that fall on an odd day of the month (2025-03-10 is an even Monday)
+        let odd_mondays = CronSchedule::parse("0 9 */2 * 1").unwrap();
+        assert_eq!(
+            odd_mondays.next_after(local(2025, 3, 9, 0, 0)),
+            Some(local(2025, 3, 17, 9, 0))
+        );
+        assert_eq!(
+            odd_mondays.next_after(local(2025, 3, 17, 9, 0)),
+            Some(local(2025, 3, 31, 9, 0))
+        );
+    }
+
+    #[test]
+    fn test_occurrences_between() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        let missed = hourly.occurrences_between(local(2025, 3, 10, 0, 30), local(2025, 3, 10, 4, 0), 10);
+        assert_eq!(missed.len(), 4);
+        assert_eq!(missed[0], local(2025, 3, 10, 1, 0));
+
+        let capped = hourly.occurrences_between(local(2025, 3, 10, 0, 30), local(2025, 3, 10, 4, 0), 2);
+        assert_eq!(capped.len(), 2);
+    }
+}
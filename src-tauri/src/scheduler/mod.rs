@@ -0,0 +1,198 @@
+pub mod cron;
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local};
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands::agents::{insert_queued_run, load_agent, AgentDb, QueuedRunParams};
+use crate::commands::schedules::{AgentSchedule, CatchUpPolicy, SCHEDULE_COLUMNS};
+use crate::commands::task_templates::render_task;
+use cron::CronSchedule;
+
+/// How often the scheduler checks for due schedules
+const SCHEDULER_TICK_SECS: u64 = 30;
+
+/// Occurrences older than this are treated as missed and handled by the catch-up policy
+const CATCH_UP_GRACE_MINUTES: i64 = 2;
+
+/// Upper bound of runs queued for a single schedule by the `run_all` catch-up policy
+const MAX_CATCH_UP_RUNS: usize = 10;
+
+/// Start the background scheduler. The first tick runs immediately so that
+/// occurrences missed while the app was closed are caught up on startup.
+/// Each tick also requeues rate limited runs whose usage limit has reset.
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!("⏰ Agent scheduler started");
+        loop {
+            fire_due_schedules(&app);
+            crate::process::usage_limit::resume_rate_limited_runs(&app);
+            tokio::time::sleep(tokio::time::Duration::from_secs(SCHEDULER_TICK_SECS)).await;
+        }
+    });
+}
+
+/// Queue a run for a schedule and return the new run ID
+pub fn queue_schedule_run(conn: &Connection, schedule: &AgentSchedule) -> Result<i64, String> {
+    let agent = load_agent(conn, schedule.agent_id)?;
+    let model = schedule.model.clone().unwrap_or_else(|| agent.model.clone());
+    let task = render_task(&schedule.task, &agent.task_variables, &HashMap::new())?;
+
+    insert_queued_run(
+        conn,
+        &agent,
+        &QueuedRunParams {
+            project_path: schedule.project_path.clone(),
+            task,
+            task_template: Some(schedule.task.clone()),
+            model,
+            schedule_id: schedule.id,
+            ..Default::default()
+        },
+    )
+}
+
+/// Number of runs to queue for a schedule whose next run time has passed
+fn runs_to_queue(
+    cron: &CronSchedule,
+    policy: CatchUpPolicy,
+    due_at: DateTime<Local>,
+    now: DateTime<Local>,
+) -> usize {
+    if now - due_at <= Duration::minutes(CATCH_UP_GRACE_MINUTES) {
+        // On time
+        return 1;
+    }
+
+    match policy {
+        CatchUpPolicy::Skip => 0,
+        CatchUpPolicy::RunOnce => 1,
+        CatchUpPolicy::RunAll => {
+            1 + cron
+                .occurrences_between(due_at, now, MAX_CATCH_UP_RUNS - 1)
+                .len()
+        }
+    }
+}
+
+/// Queue runs for every enabled schedule that is due and advance its next run time
+fn fire_due_schedules(app: &AppHandle) {
+    let now = Local::now();
+    let mut queued_any = false;
+
+    {
+        let db = app.state::<AgentDb>();
+        let conn = match db.0.lock() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to lock database for scheduler: {}", e);
+                return;
+            }
+        };
+
+        let schedules = match conn
+            .prepare(&format!(
+                "SELECT {} FROM agent_schedules WHERE enabled = 1",
+                SCHEDULE_COLUMNS
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([], AgentSchedule::from_row)?
+                    .collect::<Result<Vec<_>, _>>()
+            }) {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                error!("Failed to load agent schedules: {}", e);
+                return;
+            }
+        };
+
+        for schedule in schedules {
+            let Some(schedule_id) = schedule.id else {
+                continue;
+            };
+            let cron = match CronSchedule::parse(&schedule.cron_expression) {
+                Ok(cron) => cron,
+                Err(e) => {
+                    warn!("Skipping schedule {} with invalid cron expression: {}", schedule_id, e);
+                    continue;
+                }
+            };
+
+            let due_at = schedule
+                .next_run_at
+                .as_deref()
+                .and_then(|next| DateTime::parse_from_rfc3339(next).ok())
+                .map(|next| next.with_timezone(&Local));
+
+            let Some(due_at) = due_at else {
+                // Never computed (e.g. inserted through the storage browser)
+                let next_run_at = cron.next_after(now).map(|next| next.to_rfc3339());
+                let _ = conn.execute(
+                    "UPDATE agent_schedules SET next_run_at = ?1 WHERE id = ?2",
+                    params![next_run_at, schedule_id],
+                );
+                continue;
+            };
+
+            if due_at > now {
+                continue;
+            }
+
+            let count = runs_to_queue(&cron, schedule.catch_up_policy, due_at, now);
+            if count == 0 {
+                info!("Skipping missed occurrences of schedule {} ('{}')", schedule_id, schedule.name);
+            }
+
+            for _ in 0..count {
+                match queue_schedule_run(&conn, &schedule) {
+                    Ok(run_id) => {
+                        info!("⏰ Schedule {} ('{}') queued run {}", schedule_id, schedule.name, run_id);
+                        let _ = app.emit(
+                            "agent-schedule-fired",
+                            serde_json::json!({ "schedule_id": schedule_id, "run_id": run_id }),
+                        );
+                        queued_any = true;
+                    }
+                    Err(e) => {
+                        error!("Failed to queue run for schedule {}: {}", schedule_id, e);
+                        break;
+                    }
+                }
+            }
+
+            let next_run_at = cron.next_after(now).map(|next| next.to_rfc3339());
+            let last_run_at = if count > 0 { Some(now.to_rfc3339()) } else { schedule.last_run_at.clone() };
+            if let Err(e) = conn.execute(
+                "UPDATE agent_schedules SET last_run_at = ?1, next_run_at = ?2 WHERE id = ?3",
+                params![last_run_at, next_run_at, schedule_id],
+            ) {
+                error!("Failed to advance schedule {}: {}", schedule_id, e);
+            }
+        }
+    }
+
+    if queued_any {
+        crate::commands::queue::schedule_dispatch(app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_runs_to_queue_catch_up_policies() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        let due_at = Local.with_ymd_and_hms(2025, 3, 10, 1, 0, 0).unwrap();
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 6, 30, 0).unwrap();
+
+        assert_eq!(runs_to_queue(&hourly, CatchUpPolicy::Skip, now - Duration::seconds(20), now), 1);
+        assert_eq!(runs_to_queue(&hourly, CatchUpPolicy::Skip, due_at, now), 0);
+        assert_eq!(runs_to_queue(&hourly, CatchUpPolicy::RunOnce, due_at, now), 1);
+        assert_eq!(runs_to_queue(&hourly, CatchUpPolicy::RunAll, due_at, now), 6);
+    }
+}
@@ -137,7 +137,7 @@ export const CCAgents: React.FC<CCAgentsProps> = ({ onBack, className }) => {
       await loadRuns(); // Reload runs as they might be affected
     } catch (err) {
       console.error("Failed to delete agent:", err);
-      setToast({ message: "删除智能体失败", type: "error" });
+      setToast({ message: `删除智能体失败：${err}`, type: "error" });
     } finally {
       setIsDeleting(false);
       setShowDeleteDialog(false);
@@ -15,7 +15,7 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Card, CardContent, CardFooter } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
-import { api, type GitHubAgentFile, type AgentExport, type Agent } from "@/lib/api";
+import { api, type RegistryAgentFile, type AgentExport, type Agent } from "@/lib/api";
 import { type AgentIconName } from "./CCAgents";
 import { ICON_MAP } from "./IconPicker";
 import { open } from "@tauri-apps/plugin-shell";
@@ -27,7 +27,7 @@ interface GitHubAgentBrowserProps {
 }
 
 interface AgentPreview {
-  file: GitHubAgentFile;
+  file: RegistryAgentFile;
   data: AgentExport | null;
   loading: boolean;
   error: string | null;
@@ -38,7 +38,7 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
   onClose,
   onImportSuccess,
 }) => {
-  const [agents, setAgents] = useState<GitHubAgentFile[]>([]);
+  const [agents, setAgents] = useState<RegistryAgentFile[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [searchQuery, setSearchQuery] = useState("");
@@ -66,17 +66,21 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
     try {
       setLoading(true);
       setError(null);
-      const agentFiles = await api.fetchGitHubAgents();
-      setAgents(agentFiles);
+      const listings = await api.fetchRegistryAgents();
+      setAgents(listings.flatMap(listing => listing.agents));
+      const failed = listings.filter(listing => listing.error && listing.agents.length === 0);
+      if (failed.length > 0 && failed.length === listings.length) {
+        setError("Failed to fetch agents from the configured registries. Please check your internet connection.");
+      }
     } catch (err) {
-      console.error("Failed to fetch GitHub agents:", err);
-      setError("Failed to fetch agents from GitHub. Please check your internet connection.");
+      console.error("Failed to fetch registry agents:", err);
+      setError("Failed to fetch agents from the configured registries. Please check your internet connection.");
     } finally {
       setLoading(false);
     }
   };
 
-  const handlePreviewAgent = async (file: GitHubAgentFile) => {
+  const handlePreviewAgent = async (file: RegistryAgentFile) => {
     setSelectedAgent({
       file,
       data: null,
@@ -85,7 +89,7 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
     });
 
     try {
-      const agentData = await api.fetchGitHubAgentContent(file.download_url);
+      const agentData = await api.fetchRegistryAgentContent(file.registry_id, file.path);
       setSelectedAgent({
         file,
         data: agentData,
@@ -115,7 +119,7 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
 
     try {
       setImporting(true);
-      await api.importAgentFromGitHub(selectedAgent.file.download_url);
+      await api.importAgentFromRegistry(selectedAgent.file.registry_id, selectedAgent.file.path);
       
       // Refresh existing agents list
       await fetchExistingAgents();
@@ -225,7 +229,7 @@ export const GitHubAgentBrowser: React.FC<GitHubAgentBrowserProps> = ({
                 <AnimatePresence mode="popLayout">
                   {filteredAgents.map((agent, index) => (
                     <motion.div
-                      key={agent.sha}
+                      key={`${agent.registry_id}:${agent.path}`}
                       initial={{ opacity: 0, scale: 0.9 }}
                       animate={{ opacity: 1, scale: 1 }}
                       exit={{ opacity: 0, scale: 0.9 }}
@@ -118,10 +118,58 @@ export interface Agent {
   default_task?: string;
   model: string;
   hooks?: string; // JSON string of HooksConfiguration
+  task_variables: TaskVariable[]; // Variables available as {{name}} in the task template
+  budget: RunBudget; // Default caps for runs of this agent
+  source?: AgentSource; // Set for agents imported from a registry
+  required_mcp_servers: RequiredMcpServer[]; // MCP servers the agent expects to be configured
+  slash_commands: BundledSlashCommand[]; // Installed into the project before each run
+  min_claude_version?: string; // Oldest Claude Code version the agent works with
+  output_schema?: string; // JSON schema the final answer is validated against
+  folder?: string; // Library folder, such as "Reviews/Security"
+  tags: string[];
+  favorite: boolean;
+  last_used_at?: string; // When a run of the agent was last queued
+  run_count: number;
   created_at: string;
   updated_at: string;
 }
 
+/** Order of the agent list */
+export type AgentSort = 'created' | 'name' | 'last_used' | 'run_count';
+
+/** Filters of the agent list; an empty folder selects agents outside any folder */
+export interface AgentListFilter {
+  folder?: string;
+  tag?: string;
+  favoritesOnly?: boolean;
+  search?: string; // Matched against name and system prompt
+  sort?: AgentSort;
+}
+
+/** Where an imported agent came from, pinned to the content it was imported from */
+export interface AgentSource {
+  registry_id?: number;
+  path?: string;
+  url?: string;
+  sha?: string;
+  imported_at: string;
+  latest_sha?: string; // SHA seen at the source by the last update check
+  checked_at?: string;
+}
+
+/** Caps for an agent run; unset limits are unlimited */
+export interface RunBudget {
+  max_duration_secs?: number;
+  max_tokens?: number; // Input plus output tokens
+  max_cost_usd?: number;
+}
+
+export interface TaskVariable {
+  name: string;
+  description?: string;
+  default_value?: string; // Variables without a default are required
+}
+
 export interface AgentExport {
   version: number;
   exported_at: string;
@@ -132,15 +180,74 @@ export interface AgentExport {
     default_task?: string;
     model: string;
     hooks?: string;
+    task_variables?: TaskVariable[];
+    // Version 2 only
+    permissions?: AgentPermissions;
+    required_mcp_servers?: RequiredMcpServer[];
+    slash_commands?: BundledSlashCommand[];
+    min_claude_version?: string;
+    env?: ExportedEnvVar[];
+    output_schema?: string;
+    tags?: string[];
   };
 }
 
-export interface GitHubAgentFile {
+export interface AgentPermissions {
+  file_read: boolean;
+  file_write: boolean;
+  network: boolean;
+}
+
+/** An MCP server an agent expects to be configured; only the names of its env variables are exported */
+export interface RequiredMcpServer {
   name: string;
-  path: string;
-  download_url: string;
-  size: number;
-  sha: string;
+  transport: 'stdio' | 'sse';
+  command?: string;
+  args: string[];
+  url?: string;
+  env_keys: string[];
+}
+
+/** A slash command shipped with an agent and installed into the project it runs in */
+export interface BundledSlashCommand {
+  name: string;
+  namespace?: string;
+  description?: string;
+  allowed_tools: string[];
+  content: string;
+}
+
+/** Where a registry's agents are listed and downloaded from */
+export type RegistrySource =
+  | { type: 'github'; owner: string; repo: string; branch?: string; path: string }
+  // A host with a GitHub-compatible contents API, e.g. https://gitee.com/api/v5
+  | { type: 'mirror'; api_base_url: string; owner: string; repo: string; branch?: string; path: string }
+  | { type: 'http_index'; url: string }
+  | { type: 'local'; path: string };
+
+export interface AgentRegistry {
+  id?: number;
+  name: string;
+  source: RegistrySource;
+  enabled: boolean;
+  created_at: string;
+}
+
+export interface RegistryAgentFile {
+  registry_id: number;
+  name: string;
+  path: string; // Identifies the agent within its registry
+  download_url: string; // HTTP URL, or a file path for local registries
+  size?: number;
+  sha?: string; // Content hash reported by the source
+}
+
+export interface RegistryListing {
+  registry: AgentRegistry;
+  agents: RegistryAgentFile[];
+  from_cache: boolean; // The source could not be reached and the listing comes from the offline cache
+  fetched_at?: string;
+  error?: string;
 }
 
 export interface AgentRun {
@@ -152,11 +259,28 @@ export interface AgentRun {
   model: string;
   project_path: string;
   session_id: string;
-  status: string; // 'pending', 'running', 'completed', 'failed', 'cancelled'
+  status: string; // 'queued', 'pending', 'running', 'completed', 'failed', 'cancelled', 'budget_exceeded', 'timed_out', 'rate_limited'
   pid?: number;
   process_started_at?: string;
   created_at: string;
   completed_at?: string;
+  output_log_path?: string; // zstd-compressed copy of the raw stream-json output
+  priority: number;
+  schedule_id?: number; // Schedule that triggered this run, if any
+  pipeline_run_id?: number; // Pipeline run this run is a step of, if any
+  revision_id?: number; // Agent revision the run was executed with
+  task_template?: string; // Template the task was rendered from, if any
+  budget: RunBudget; // Caps in effect for this run
+  status_reason?: string; // Why the run was stopped, e.g. which budget was exceeded
+  worktree?: RunWorktree; // Set when the run executes in an isolated git worktree
+  rerun_of?: number; // Run this one repeats, if it was started as a rerun
+  batch_id?: number; // Multi-project batch the run belongs to, if any
+  result?: RunResult; // Final outcome, recorded when the run finishes
+  annotation?: RunAnnotation; // Rating, notes and tags added by the user
+  checkpoint_id?: string; // Snapshot of the project taken before the run started
+  file_changes?: RunFileChange[]; // Files changed relative to the snapshot, once finished
+  changes_restored_at?: string; // When the run's changes were reverted, if they were
+  resume_at?: string; // Set once a usage limit stopped the run; it then resumes its session
 }
 
 export interface AgentRunMetrics {
@@ -175,15 +299,507 @@ export interface AgentRunWithMetrics {
   model: string;
   project_path: string;
   session_id: string;
-  status: string; // 'pending', 'running', 'completed', 'failed', 'cancelled'
+  status: string; // 'queued', 'pending', 'running', 'completed', 'failed', 'cancelled', 'budget_exceeded', 'timed_out', 'rate_limited'
   pid?: number;
   process_started_at?: string;
   created_at: string;
   completed_at?: string;
+  output_log_path?: string;
+  priority: number;
+  schedule_id?: number;
+  pipeline_run_id?: number;
+  revision_id?: number;
+  task_template?: string;
+  budget: RunBudget;
+  status_reason?: string;
+  worktree?: RunWorktree;
+  rerun_of?: number;
+  batch_id?: number;
+  result?: RunResult;
   metrics?: AgentRunMetrics;
   output?: string; // Real-time JSONL content
 }
 
+export type QueueOrder = 'fifo' | 'priority';
+
+export interface QueueSettings {
+  max_concurrent_runs: number;
+  max_runs_per_project?: number;
+  max_runs_per_agent?: number;
+  order: QueueOrder;
+}
+
+/**
+ * Startup, idle and wall-clock limits of Claude sessions and agent runs, in seconds;
+ * unset limits are disabled
+ */
+export interface ProcessTimeouts {
+  startup_secs?: number;
+  idle_secs?: number;
+  max_duration_secs?: number;
+}
+
+export type CatchUpPolicy = 'skip' | 'run_once' | 'run_all';
+
+export interface AgentSchedule {
+  id?: number;
+  agent_id: number;
+  name: string;
+  cron_expression: string;
+  project_path: string;
+  task: string;
+  model?: string; // Falls back to the agent's model when unset
+  enabled: boolean;
+  catch_up_policy: CatchUpPolicy;
+  last_run_at?: string;
+  next_run_at?: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export type StepFailurePolicy = 'stop' | 'continue' | 'retry';
+
+export interface PipelineStep {
+  key: string; // Referenced by depends_on and {{steps.<key>.output}} placeholders
+  agent_id: number;
+  task_template: string;
+  model?: string;
+  depends_on?: string[]; // Defaults to the previous step when omitted
+  on_failure: StepFailurePolicy;
+  max_retries: number;
+}
+
+export interface Pipeline {
+  id?: number;
+  name: string;
+  description?: string;
+  steps: PipelineStep[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface PipelineRunStep {
+  id: number;
+  step_key: string;
+  position: number;
+  agent_id: number;
+  status: string; // 'waiting', 'running', 'completed', 'failed', 'skipped', 'cancelled'
+  attempts: number;
+  agent_run_id?: number;
+  output?: string;
+  error?: string;
+  completed_at?: string;
+}
+
+export interface PipelineRun {
+  id: number;
+  pipeline_id: number;
+  pipeline_name: string;
+  project_path: string;
+  status: string; // 'running', 'completed', 'failed', 'cancelled'
+  created_at: string;
+  completed_at?: string;
+  steps: PipelineRunStep[];
+}
+
+export interface AgentRevision {
+  id: number;
+  agent_id: number;
+  revision: number;
+  name: string;
+  icon: string;
+  system_prompt: string;
+  default_task?: string;
+  model: string;
+  enable_file_read: boolean;
+  enable_file_write: boolean;
+  enable_network: boolean;
+  hooks?: string;
+  task_variables: TaskVariable[];
+  budget: RunBudget;
+  required_mcp_servers: RequiredMcpServer[];
+  slash_commands: BundledSlashCommand[];
+  min_claude_version?: string;
+  output_schema?: string;
+  restored_from?: number; // Revision ID this one was restored from
+  created_at: string;
+}
+
+export interface DiffLine {
+  kind: 'equal' | 'added' | 'removed';
+  text: string;
+}
+
+export interface AgentRevisionDiff {
+  from: AgentRevision;
+  to: AgentRevision;
+  prompt_diff: DiffLine[];
+  changed_fields: string[];
+}
+
+export interface RunWorktree {
+  status: string; // 'requested', 'active', 'ready', 'merged', 'kept', 'discarded'
+  path?: string; // Directory the agent runs in while the worktree is active
+  branch?: string;
+  base_commit?: string; // Commit the worktree was created from
+}
+
+export interface WorktreeFileChange {
+  path: string;
+  additions?: number; // Unset for binary files
+  deletions?: number;
+}
+
+export interface WorktreeDiff {
+  run_id: number;
+  worktree: RunWorktree;
+  files: WorktreeFileChange[];
+  patch: string;
+}
+
+export interface AgentUpdateStatus {
+  agent_id: number;
+  agent_name: string;
+  current_sha?: string;
+  latest_sha?: string;
+  update_available: boolean;
+  locally_modified: boolean; // Edited since it was imported or last updated
+  error?: string;
+}
+
+export interface AgentFieldUpdate {
+  field: string; // 'system_prompt', 'model', 'hooks'
+  base?: string; // Value as last imported from the source
+  local?: string;
+  remote?: string;
+  locally_modified: boolean;
+  remote_changed: boolean;
+  conflict: boolean; // Both sides changed the field differently
+}
+
+export interface AgentUpdateDiff {
+  agent_id: number;
+  current_sha?: string;
+  latest_sha: string;
+  fields: AgentFieldUpdate[];
+  prompt_diff: DiffLine[]; // Local system prompt against the remote one
+}
+
+export type UpdateConflictResolution = 'keep_local' | 'take_remote';
+
+/**
+ * A Claude Code native subagent file and its link to a Claudia agent
+ */
+export interface NativeSubagent {
+  file_path: string;
+  scope: 'user' | 'project';
+  name: string;
+  description?: string;
+  tools?: string;
+  model?: string;
+  modified_at?: string;
+  agent_id?: number;
+  sync_status: 'unlinked' | 'in_sync' | 'file_changed' | 'agent_changed' | 'conflict' | 'missing';
+}
+
+export interface SubagentSyncReport {
+  imported: string[];
+  exported: string[];
+  conflicts: NativeSubagent[];
+  missing: NativeSubagent[];
+}
+
+/**
+ * Environment variable passed to an agent's process; secret values are never returned
+ */
+export interface AgentEnvVar {
+  id?: number;
+  agent_id: number;
+  key: string;
+  value?: string;
+  is_secret: boolean;
+  has_value: boolean; // false for imported secrets that still need a value
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ExportedEnvVar {
+  key: string;
+  value?: string; // always empty for secrets
+  secret?: boolean;
+}
+
+/**
+ * Changes applied to a repeated run; unset fields keep the original value
+ */
+export interface RerunOverrides {
+  task?: string;
+  model?: string;
+  project_path?: string;
+  priority?: number;
+  budget?: RunBudget;
+  use_worktree?: boolean;
+  use_latest_revision?: boolean; // Use the agent's current configuration instead of the original revision
+}
+
+/**
+ * Selects finished runs for a bulk rerun
+ */
+export interface AgentRunFilter {
+  agent_id?: number;
+  statuses?: string[]; // Defaults to all finished statuses
+  within_hours?: number;
+  project_path?: string;
+  model?: string;
+  include_rerun?: boolean; // Include runs that were already rerun
+  limit?: number;
+}
+
+/**
+ * One project's run within a batch
+ */
+export interface AgentBatchRun {
+  run_id: number;
+  project_path: string;
+  status: string;
+  status_reason?: string;
+  session_id: string;
+  cost_usd?: number;
+  completed_at?: string;
+}
+
+/**
+ * An agent fanned out across several projects, with the aggregate state of its runs
+ */
+export interface AgentBatch {
+  id: number;
+  agent_id: number;
+  agent_name: string;
+  task: string;
+  model: string;
+  created_at: string;
+  status: 'queued' | 'running' | 'completed' | 'completed_with_failures' | 'failed' | 'cancelled';
+  total: number;
+  queued: number;
+  running: number;
+  succeeded: number;
+  failed: number;
+  cancelled: number;
+  total_cost_usd: number;
+  runs: AgentBatchRun[];
+}
+
+/**
+ * Final outcome of an agent run, taken from its `result` message
+ */
+export interface RunResult {
+  result?: string; // Final answer; empty if the run ended without a result message
+  is_error: boolean;
+  num_turns?: number;
+  total_cost_usd?: number;
+  duration_ms?: number;
+  files_changed: string[]; // Relative to the project where possible
+  output_valid?: boolean; // Set when the agent has an output schema
+  output_errors: string[];
+}
+
+/**
+ * A check applied to an eval case once its agent run has finished
+ */
+export type EvalAssertion =
+  | { type: 'file_exists'; path: string }
+  | { type: 'output_matches'; pattern: string }
+  | { type: 'command_succeeds'; command: string; timeout_secs?: number }
+  | { type: 'max_cost_usd'; max_cost_usd: number };
+
+/**
+ * One test case of an eval suite; runs in a temporary copy of its fixture directory
+ */
+export interface EvalCase {
+  name: string;
+  fixture_path: string;
+  task: string;
+  model?: string;
+  assertions: EvalAssertion[];
+}
+
+export interface EvalSuite {
+  id?: number;
+  agent_id: number;
+  name: string;
+  description?: string;
+  cases: EvalCase[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface AssertionOutcome {
+  assertion: EvalAssertion;
+  passed: boolean;
+  message: string;
+}
+
+export interface EvalCaseResult {
+  id: number;
+  position: number;
+  case_name: string;
+  status: 'running' | 'evaluating' | 'passed' | 'failed' | 'cancelled';
+  agent_run_id?: number;
+  outcomes: AssertionOutcome[];
+  error?: string;
+  cost_usd?: number;
+  completed_at?: string;
+}
+
+/**
+ * Pass/fail report of an eval suite run against one agent revision
+ */
+export interface EvalRun {
+  id: number;
+  suite_id: number;
+  suite_name: string;
+  agent_id: number;
+  revision_id: number;
+  model?: string;
+  status: 'running' | 'passed' | 'failed' | 'cancelled';
+  passed: number;
+  failed: number;
+  total_cost_usd: number;
+  created_at: string;
+  completed_at?: string;
+  cases: EvalCaseResult[];
+}
+
+/**
+ * One model's run within a comparison
+ */
+export interface ComparisonEntry {
+  model: string;
+  run_id: number;
+  status: string;
+  status_reason?: string;
+  isolation: 'worktree' | 'copy'; // Worktree for git projects, temporary copy otherwise
+  metrics?: AgentRunMetrics;
+  answer?: string; // Final answer of the run
+  files: WorktreeFileChange[];
+  patch?: string;
+  diff_error?: string; // Why the changes could not be read
+}
+
+/**
+ * The same agent and task run side by side under several models
+ */
+export interface AgentComparison {
+  id: number;
+  agent_id: number;
+  agent_name: string;
+  task: string;
+  project_path: string;
+  created_at: string;
+  status: 'running' | 'completed';
+  entries: ComparisonEntry[];
+}
+
+/**
+ * A user's rating, notes and tags on an agent run
+ */
+export interface RunAnnotation {
+  rating?: number; // 1 (bad) to 5 (good)
+  notes?: string;
+  tags: string[]; // Lowercase, e.g. "hallucinated" or "perfect"
+  annotated_at: string;
+}
+
+export interface RunStats {
+  revision_id?: number; // Unset for runs from before revisions were recorded
+  revision?: number;
+  total_runs: number;
+  finished_runs: number;
+  completed_runs: number;
+  success_rate?: number; // Completed share of finished runs
+  rated_runs: number;
+  average_rating?: number;
+}
+
+/**
+ * Success rate and average rating of an agent's runs, overall and per revision
+ */
+export interface AgentRunStats {
+  agent_id: number;
+  overall: RunStats;
+  revisions: RunStats[]; // Newest revision first
+}
+
+/**
+ * A file an agent run added, modified or deleted
+ */
+export interface RunFileChange {
+  path: string; // Relative to the project
+  change: 'added' | 'modified' | 'deleted';
+  additions: number;
+  deletions: number;
+}
+
+export interface RunRestoreResult {
+  run_id: number;
+  files_restored: number;
+  warnings: string[];
+}
+
+/**
+ * Run and session lifecycle events a webhook can subscribe to
+ */
+export type WebhookEvent =
+  | 'run.started'
+  | 'run.completed'
+  | 'run.failed'
+  | 'run.budget_exceeded'
+  | 'session.started'
+  | 'session.completed'
+  | 'session.failed';
+
+/**
+ * A user-configured HTTP endpoint notified of lifecycle events
+ */
+export interface Webhook {
+  id: number;
+  name: string;
+  url: string;
+  events: WebhookEvent[];
+  payload_template?: string; // Body with {{placeholders}}; the default JSON payload when unset
+  has_secret: boolean; // Whether requests are signed with an HMAC secret
+  enabled: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+/**
+ * A logged webhook delivery with the outcome of its latest attempt
+ */
+export interface WebhookDelivery {
+  id: number;
+  webhook_id: number;
+  event: string;
+  payload: string;
+  status: 'pending' | 'delivered' | 'failed';
+  attempts: number;
+  response_status?: number;
+  response_body?: string;
+  error?: string;
+  created_at: string;
+  last_attempt_at?: string;
+}
+
+/**
+ * Which events raise a desktop notification
+ */
+export interface NotificationPreferences {
+  completed: boolean;
+  failed: boolean;
+  budget_exceeded: boolean;
+  timed_out: boolean;
+}
+
 // Usage Dashboard types
 export interface UsageEntry {
   project: string;
@@ -472,56 +1088,113 @@ export const api = {
   },
 
   /**
-   * Fetch list of agents from GitHub repository
-   * @returns Promise resolving to list of available agents on GitHub
+   * Lists the configured agent registries
    */
-  async fetchGitHubAgents(): Promise<GitHubAgentFile[]> {
+  async listAgentRegistries(): Promise<AgentRegistry[]> {
     try {
-      return await invoke<GitHubAgentFile[]>('fetch_github_agents');
+      return await invoke<AgentRegistry[]>('list_agent_registries');
     } catch (error) {
-      console.error("Failed to fetch GitHub agents:", error);
+      console.error("Failed to list agent registries:", error);
       throw error;
     }
   },
 
   /**
-   * Fetch and preview a specific agent from GitHub
-   * @param downloadUrl - The download URL for the agent file
-   * @returns Promise resolving to the agent export data
+   * Adds an agent registry
+   * @param name - Display name
+   * @param source - Where the registry's agents are listed from
    */
-  async fetchGitHubAgentContent(downloadUrl: string): Promise<AgentExport> {
+  async createAgentRegistry(name: string, source: RegistrySource): Promise<AgentRegistry> {
     try {
-      return await invoke<AgentExport>('fetch_github_agent_content', { downloadUrl });
+      return await invoke<AgentRegistry>('create_agent_registry', { name, source });
     } catch (error) {
-      console.error("Failed to fetch GitHub agent content:", error);
+      console.error("Failed to create agent registry:", error);
       throw error;
     }
   },
 
   /**
-   * Import an agent directly from GitHub
-   * @param downloadUrl - The download URL for the agent file
-   * @returns Promise resolving to the imported agent
+   * Updates an agent registry; changing the source drops its cached listing
    */
-  async importAgentFromGitHub(downloadUrl: string): Promise<Agent> {
+  async updateAgentRegistry(
+    id: number,
+    name: string,
+    source: RegistrySource,
+    enabled: boolean
+  ): Promise<AgentRegistry> {
     try {
-      return await invoke<Agent>('import_agent_from_github', { downloadUrl });
+      return await invoke<AgentRegistry>('update_agent_registry', { id, name, source, enabled });
     } catch (error) {
-      console.error("Failed to import agent from GitHub:", error);
+      console.error("Failed to update agent registry:", error);
       throw error;
     }
   },
 
   /**
-   * Reads the Claude settings file
-   * @returns Promise resolving to the settings object
+   * Deletes an agent registry; agents imported from it are kept
+   * @param id - The registry ID
    */
-  async getClaudeSettings(): Promise<ClaudeSettings> {
+  async deleteAgentRegistry(id: number): Promise<void> {
     try {
-      const result = await invoke<{ data: ClaudeSettings }>("get_claude_settings");
-      console.log("Raw result from get_claude_settings:", result);
-      
-      // The Rust backend returns ClaudeSettings { data: ... }
+      return await invoke<void>('delete_agent_registry', { id });
+    } catch (error) {
+      console.error("Failed to delete agent registry:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists the agents of one registry, or of all enabled registries.
+   * Unreachable registries fall back to their cached listing.
+   * @param registryId - Optional registry ID
+   */
+  async fetchRegistryAgents(registryId?: number): Promise<RegistryListing[]> {
+    try {
+      return await invoke<RegistryListing[]>('fetch_registry_agents', { registryId });
+    } catch (error) {
+      console.error("Failed to fetch registry agents:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Fetches and previews an agent from a registry
+   * @param registryId - The registry ID
+   * @param path - The agent's path within the registry
+   */
+  async fetchRegistryAgentContent(registryId: number, path: string): Promise<AgentExport> {
+    try {
+      return await invoke<AgentExport>('fetch_registry_agent_content', { registryId, path });
+    } catch (error) {
+      console.error("Failed to fetch registry agent content:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Imports an agent from a registry, pinned to the content it was imported from
+   * @param registryId - The registry ID
+   * @param path - The agent's path within the registry
+   */
+  async importAgentFromRegistry(registryId: number, path: string): Promise<Agent> {
+    try {
+      return await invoke<Agent>('import_agent_from_registry', { registryId, path });
+    } catch (error) {
+      console.error("Failed to import agent from registry:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reads the Claude settings file
+   * @returns Promise resolving to the settings object
+   */
+  async getClaudeSettings(): Promise<ClaudeSettings> {
+    try {
+      const result = await invoke<{ data: ClaudeSettings }>("get_claude_settings");
+      console.log("Raw result from get_claude_settings:", result);
+      
+      // The Rust backend returns ClaudeSettings { data: ... }
       // We need to extract the data field
       if (result && typeof result === 'object' && 'data' in result) {
         return result.data;
@@ -649,12 +1322,13 @@ export const api = {
   // Agent API methods
   
   /**
-   * Lists all CC agents
+   * Lists CC agents
+   * @param filter - Optional folder, tag, favourites and text filters and the sort order
    * @returns Promise resolving to an array of agents
    */
-  async listAgents(): Promise<Agent[]> {
+  async listAgents(filter?: AgentListFilter): Promise<Agent[]> {
     try {
-      return await invoke<Agent[]>('list_agents');
+      return await invoke<Agent[]>('list_agents', { ...filter });
     } catch (error) {
       console.error("Failed to list agents:", error);
       throw error;
@@ -669,6 +1343,7 @@ export const api = {
    * @param default_task - Optional default task
    * @param model - Optional model (defaults to 'sonnet')
    * @param hooks - Optional hooks configuration as JSON string
+   * @param task_variables - Optional variables for the task template
    * @returns Promise resolving to the created agent
    */
   async createAgent(
@@ -677,7 +1352,8 @@ export const api = {
     system_prompt: string, 
     default_task?: string, 
     model?: string,
-    hooks?: string
+    hooks?: string,
+    task_variables?: TaskVariable[]
   ): Promise<Agent> {
     try {
       return await invoke<Agent>('create_agent', { 
@@ -686,7 +1362,8 @@ export const api = {
         systemPrompt: system_prompt,
         defaultTask: default_task,
         model,
-        hooks
+        hooks,
+        taskVariables: task_variables
       });
     } catch (error) {
       console.error("Failed to create agent:", error);
@@ -703,6 +1380,7 @@ export const api = {
    * @param default_task - Optional default task
    * @param model - Optional model
    * @param hooks - Optional hooks configuration as JSON string
+   * @param task_variables - Optional variables for the task template (left unchanged when omitted)
    * @returns Promise resolving to the updated agent
    */
   async updateAgent(
@@ -712,7 +1390,8 @@ export const api = {
     system_prompt: string, 
     default_task?: string, 
     model?: string,
-    hooks?: string
+    hooks?: string,
+    task_variables?: TaskVariable[]
   ): Promise<Agent> {
     try {
       return await invoke<Agent>('update_agent', { 
@@ -722,7 +1401,8 @@ export const api = {
         systemPrompt: system_prompt,
         defaultTask: default_task,
         model,
-        hooks
+        hooks,
+        taskVariables: task_variables
       });
     } catch (error) {
       console.error("Failed to update agent:", error);
@@ -730,6 +1410,21 @@ export const api = {
     }
   },
 
+  /**
+   * Sets the default run budget of an agent
+   * @param id - The agent ID
+   * @param budget - Caps for time, tokens and cost; unset limits are unlimited
+   * @returns Promise resolving to the updated agent
+   */
+  async updateAgentBudget(id: number, budget: RunBudget): Promise<Agent> {
+    try {
+      return await invoke<Agent>('update_agent_budget', { id, budget });
+    } catch (error) {
+      console.error("Failed to update agent budget:", error);
+      throw error;
+    }
+  },
+
   /**
    * Deletes an agent
    * @param id - The agent ID to delete
@@ -801,16 +1496,29 @@ export const api = {
   },
 
   /**
-   * Executes an agent
+   * Queues an agent run; it starts as soon as the run queue's concurrency limits allow
    * @param agentId - The agent ID to execute
    * @param projectPath - The project path to run the agent in
-   * @param task - The task description
+   * @param task - The task description; may be a template using the agent's {{variables}}
    * @param model - Optional model override
-   * @returns Promise resolving to the run ID when execution starts
-   */
-  async executeAgent(agentId: number, projectPath: string, task: string, model?: string): Promise<number> {
+   * @param priority - Optional priority, higher runs first when the queue uses priority order
+   * @param variables - Optional values for the task template variables
+   * @param budget - Optional caps for this run; unset limits fall back to the agent's budget
+   * @param useWorktree - Run in an isolated git worktree instead of the project itself
+   * @returns Promise resolving to the run ID once the run is queued
+   */
+  async executeAgent(
+    agentId: number,
+    projectPath: string,
+    task: string,
+    model?: string,
+    priority?: number,
+    variables?: Record<string, string>,
+    budget?: RunBudget,
+    useWorktree?: boolean
+  ): Promise<number> {
     try {
-      return await invoke<number>('execute_agent', { agentId, projectPath, task, model });
+      return await invoke<number>('execute_agent', { agentId, projectPath, task, model, priority, variables, budget, useWorktree });
     } catch (error) {
       console.error("Failed to execute agent:", error);
       // Return a sentinel value to indicate error
@@ -821,11 +1529,15 @@ export const api = {
   /**
    * Lists agent runs with metrics
    * @param agentId - Optional agent ID to filter runs
+   * @param filter - Optional rating range and tag to filter runs by
    * @returns Promise resolving to an array of agent runs with metrics
    */
-  async listAgentRuns(agentId?: number): Promise<AgentRunWithMetrics[]> {
+  async listAgentRuns(
+    agentId?: number,
+    filter?: { minRating?: number; maxRating?: number; tag?: string }
+  ): Promise<AgentRunWithMetrics[]> {
     try {
-      return await invoke<AgentRunWithMetrics[]>('list_agent_runs', { agentId });
+      return await invoke<AgentRunWithMetrics[]>('list_agent_runs', { agentId, ...filter });
     } catch (error) {
       console.error("Failed to list agent runs:", error);
       // Return empty array instead of throwing to prevent UI crashes
@@ -980,32 +1692,1241 @@ export const api = {
   },
 
   /**
-   * Executes a new interactive Claude Code session with streaming output
+   * Gets the agent run queue settings
+   * @returns Promise resolving to the queue settings
    */
-  async executeClaudeCode(projectPath: string, prompt: string, model: string): Promise<void> {
-    return invoke("execute_claude_code", { projectPath, prompt, model });
+  async getQueueSettings(): Promise<QueueSettings> {
+    try {
+      return await invoke<QueueSettings>('get_queue_settings');
+    } catch (error) {
+      console.error("Failed to get queue settings:", error);
+      throw error;
+    }
   },
 
   /**
-   * Continues an existing Claude Code conversation with streaming output
+   * Saves the agent run queue settings
+   * @param settings - The new concurrency limits and dispatch order
    */
-  async continueClaudeCode(projectPath: string, prompt: string, model: string): Promise<void> {
-    return invoke("continue_claude_code", { projectPath, prompt, model });
+  async saveQueueSettings(settings: QueueSettings): Promise<void> {
+    try {
+      return await invoke<void>('save_queue_settings', { settings });
+    } catch (error) {
+      console.error("Failed to save queue settings:", error);
+      throw error;
+    }
   },
 
   /**
-   * Resumes an existing Claude Code session by ID with streaming output
+   * Gets the timeouts after which stalled Claude processes are stopped
+   * @returns Promise resolving to the process timeouts
    */
-  async resumeClaudeCode(projectPath: string, sessionId: string, prompt: string, model: string): Promise<void> {
-    return invoke("resume_claude_code", { projectPath, sessionId, prompt, model });
+  async getProcessTimeouts(): Promise<ProcessTimeouts> {
+    try {
+      return await invoke<ProcessTimeouts>('get_process_timeouts');
+    } catch (error) {
+      console.error("Failed to get process timeouts:", error);
+      throw error;
+    }
   },
 
   /**
-   * Cancels the currently running Claude Code execution
-   * @param sessionId - Optional session ID to cancel a specific session
+   * Saves the process timeouts; they apply to processes started afterwards
+   * @param timeouts - The startup, idle and wall-clock limits
    */
-  async cancelClaudeExecution(sessionId?: string): Promise<void> {
-    return invoke("cancel_claude_execution", { sessionId });
+  async saveProcessTimeouts(timeouts: ProcessTimeouts): Promise<void> {
+    try {
+      return await invoke<void>('save_process_timeouts', { timeouts });
+    } catch (error) {
+      console.error("Failed to save process timeouts:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists runs waiting in the queue, in dispatch order
+   * @returns Promise resolving to the queued runs
+   */
+  async listQueuedRuns(): Promise<AgentRun[]> {
+    try {
+      return await invoke<AgentRun[]>('list_queued_runs');
+    } catch (error) {
+      console.error("Failed to list queued runs:", error);
+      return [];
+    }
+  },
+
+  /**
+   * Cancels a run that has not started yet
+   * @param runId - The queued run ID
+   * @returns Promise resolving to whether the run was removed from the queue
+   */
+  async cancelQueuedRun(runId: number): Promise<boolean> {
+    try {
+      return await invoke<boolean>('cancel_queued_run', { runId });
+    } catch (error) {
+      console.error("Failed to cancel queued run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists agent schedules
+   * @param agentId - Optional agent ID to filter schedules
+   * @returns Promise resolving to the schedules
+   */
+  async listAgentSchedules(agentId?: number): Promise<AgentSchedule[]> {
+    try {
+      return await invoke<AgentSchedule[]>('list_agent_schedules', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent schedules:", error);
+      return [];
+    }
+  },
+
+  /**
+   * Creates a cron schedule for an agent
+   * @param agentId - The agent to run
+   * @param name - Display name of the schedule
+   * @param cronExpression - Standard 5-field cron expression or macro such as @daily
+   * @param projectPath - The project path to run the agent in
+   * @param task - The task description
+   * @param model - Optional model override
+   * @param enabled - Whether the schedule is active (defaults to true)
+   * @param catchUpPolicy - How to handle runs missed while the app was closed
+   * @returns Promise resolving to the created schedule
+   */
+  async createAgentSchedule(
+    agentId: number,
+    name: string,
+    cronExpression: string,
+    projectPath: string,
+    task: string,
+    model?: string,
+    enabled?: boolean,
+    catchUpPolicy?: CatchUpPolicy
+  ): Promise<AgentSchedule> {
+    try {
+      return await invoke<AgentSchedule>('create_agent_schedule', {
+        agentId,
+        name,
+        cronExpression,
+        projectPath,
+        task,
+        model,
+        enabled,
+        catchUpPolicy
+      });
+    } catch (error) {
+      console.error("Failed to create agent schedule:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Updates an agent schedule; the next run time is recomputed
+   * @returns Promise resolving to the updated schedule
+   */
+  async updateAgentSchedule(
+    id: number,
+    name: string,
+    cronExpression: string,
+    projectPath: string,
+    task: string,
+    enabled: boolean,
+    catchUpPolicy: CatchUpPolicy,
+    model?: string
+  ): Promise<AgentSchedule> {
+    try {
+      return await invoke<AgentSchedule>('update_agent_schedule', {
+        id,
+        name,
+        cronExpression,
+        projectPath,
+        task,
+        model,
+        enabled,
+        catchUpPolicy
+      });
+    } catch (error) {
+      console.error("Failed to update agent schedule:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes an agent schedule
+   * @param id - The schedule ID
+   */
+  async deleteAgentSchedule(id: number): Promise<void> {
+    try {
+      return await invoke<void>('delete_agent_schedule', { id });
+    } catch (error) {
+      console.error("Failed to delete agent schedule:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Triggers a schedule immediately
+   * @param id - The schedule ID
+   * @returns Promise resolving to the queued run ID
+   */
+  async runAgentScheduleNow(id: number): Promise<number> {
+    try {
+      return await invoke<number>('run_agent_schedule_now', { id });
+    } catch (error) {
+      console.error("Failed to run agent schedule:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Validates a cron expression and returns its next occurrences
+   * @param cronExpression - The cron expression to preview
+   * @param count - Number of occurrences to return (defaults to 5)
+   * @returns Promise resolving to RFC 3339 timestamps
+   */
+  async previewCronSchedule(cronExpression: string, count?: number): Promise<string[]> {
+    return invoke<string[]>('preview_cron_schedule', { cronExpression, count });
+  },
+
+  /**
+   * Lists all agent pipelines
+   * @returns Promise resolving to the pipelines
+   */
+  async listPipelines(): Promise<Pipeline[]> {
+    try {
+      return await invoke<Pipeline[]>('list_pipelines');
+    } catch (error) {
+      console.error("Failed to list pipelines:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets a single pipeline with its steps
+   * @param id - The pipeline ID
+   */
+  async getPipeline(id: number): Promise<Pipeline> {
+    try {
+      return await invoke<Pipeline>('get_pipeline', { id });
+    } catch (error) {
+      console.error("Failed to get pipeline:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates a pipeline
+   * @param name - The pipeline name
+   * @param description - Optional description
+   * @param steps - Ordered steps; depends_on turns them into a DAG
+   */
+  async createPipeline(name: string, description: string | undefined, steps: PipelineStep[]): Promise<Pipeline> {
+    try {
+      return await invoke<Pipeline>('create_pipeline', { name, description, steps });
+    } catch (error) {
+      console.error("Failed to create pipeline:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Updates a pipeline; runs in progress keep their original definition
+   */
+  async updatePipeline(id: number, name: string, description: string | undefined, steps: PipelineStep[]): Promise<Pipeline> {
+    try {
+      return await invoke<Pipeline>('update_pipeline', { id, name, description, steps });
+    } catch (error) {
+      console.error("Failed to update pipeline:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a pipeline
+   * @param id - The pipeline ID
+   */
+  async deletePipeline(id: number): Promise<void> {
+    try {
+      return await invoke<void>('delete_pipeline', { id });
+    } catch (error) {
+      console.error("Failed to delete pipeline:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Starts a pipeline run in a project
+   * @param id - The pipeline ID
+   * @param projectPath - The project to run every step in
+   */
+  async runPipeline(id: number, projectPath: string): Promise<PipelineRun> {
+    try {
+      return await invoke<PipelineRun>('run_pipeline', { id, projectPath });
+    } catch (error) {
+      console.error("Failed to run pipeline:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists pipeline runs
+   * @param pipelineId - Optional pipeline ID to filter runs
+   */
+  async listPipelineRuns(pipelineId?: number): Promise<PipelineRun[]> {
+    try {
+      return await invoke<PipelineRun[]>('list_pipeline_runs', { pipelineId });
+    } catch (error) {
+      console.error("Failed to list pipeline runs:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets a pipeline run with the state of each step
+   * @param id - The pipeline run ID
+   */
+  async getPipelineRun(id: number): Promise<PipelineRun> {
+    try {
+      return await invoke<PipelineRun>('get_pipeline_run', { id });
+    } catch (error) {
+      console.error("Failed to get pipeline run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Cancels a pipeline run and its active agent runs
+   * @param id - The pipeline run ID
+   */
+  async cancelPipelineRun(id: number): Promise<void> {
+    try {
+      return await invoke<void>('cancel_pipeline_run', { id });
+    } catch (error) {
+      console.error("Failed to cancel pipeline run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists the revisions of an agent, newest first
+   * @param agentId - The agent ID
+   */
+  async listAgentRevisions(agentId: number): Promise<AgentRevision[]> {
+    try {
+      return await invoke<AgentRevision[]>('list_agent_revisions', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent revisions:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets a single agent revision
+   * @param id - The revision ID
+   */
+  async getAgentRevision(id: number): Promise<AgentRevision> {
+    try {
+      return await invoke<AgentRevision>('get_agent_revision', { id });
+    } catch (error) {
+      console.error("Failed to get agent revision:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Diffs the system prompts of two revisions of the same agent
+   * @param fromRevisionId - The older revision ID
+   * @param toRevisionId - The newer revision ID
+   */
+  async diffAgentRevisions(fromRevisionId: number, toRevisionId: number): Promise<AgentRevisionDiff> {
+    try {
+      return await invoke<AgentRevisionDiff>('diff_agent_revisions', { fromRevisionId, toRevisionId });
+    } catch (error) {
+      console.error("Failed to diff agent revisions:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Restores an agent to an older revision (recorded as a new revision)
+   * @param revisionId - The revision to restore
+   * @returns Promise resolving to the restored agent
+   */
+  async restoreAgentRevision(revisionId: number): Promise<Agent> {
+    try {
+      return await invoke<Agent>('restore_agent_revision', { revisionId });
+    } catch (error) {
+      console.error("Failed to restore agent revision:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Renders a task template with an agent's variables without running it
+   * @param agentId - The agent whose variable defaults apply
+   * @param template - The task template
+   * @param variables - Optional variable values
+   * @returns Promise resolving to the rendered task
+   */
+  async renderAgentTask(agentId: number, template: string, variables?: Record<string, string>): Promise<string> {
+    return invoke<string>('render_agent_task', { agentId, template, variables });
+  },
+
+  /**
+   * Gets the changes an isolated run made relative to the commit its worktree was created from
+   * @param runId - The run ID
+   */
+  async getRunWorktreeDiff(runId: number): Promise<WorktreeDiff> {
+    try {
+      return await invoke<WorktreeDiff>('get_run_worktree_diff', { runId });
+    } catch (error) {
+      console.error("Failed to get run worktree diff:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Merges a finished run's branch into the project's current branch
+   * @param runId - The run ID
+   * @returns Promise resolving to the git merge output
+   */
+  async mergeRunWorktree(runId: number): Promise<string> {
+    try {
+      return await invoke<string>('merge_run_worktree', { runId });
+    } catch (error) {
+      console.error("Failed to merge run worktree:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Keeps a finished run's branch without merging it
+   * @param runId - The run ID
+   */
+  async keepRunWorktree(runId: number): Promise<void> {
+    try {
+      return await invoke<void>('keep_run_worktree', { runId });
+    } catch (error) {
+      console.error("Failed to keep run worktree:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a finished run's branch and its changes
+   * @param runId - The run ID
+   */
+  async discardRunWorktree(runId: number): Promise<void> {
+    try {
+      return await invoke<void>('discard_run_worktree', { runId });
+    } catch (error) {
+      console.error("Failed to discard run worktree:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Checks imported agents for newer versions at their source
+   * @param agentId - Optional agent ID; all imported agents are checked when omitted
+   */
+  async checkAgentUpdates(agentId?: number): Promise<AgentUpdateStatus[]> {
+    try {
+      return await invoke<AgentUpdateStatus[]>('check_agent_updates', { agentId });
+    } catch (error) {
+      console.error("Failed to check agent updates:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Compares an imported agent with the latest version at its source
+   * @param agentId - The agent ID
+   */
+  async getAgentUpdateDiff(agentId: number): Promise<AgentUpdateDiff> {
+    try {
+      return await invoke<AgentUpdateDiff>('get_agent_update_diff', { agentId });
+    } catch (error) {
+      console.error("Failed to get agent update diff:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Applies the latest version of an imported agent, preserving local edits.
+   * Fails on conflicting edits unless a resolution is given.
+   * @param agentId - The agent ID
+   * @param resolution - Optional resolution for fields changed on both sides
+   */
+  async applyAgentUpdate(agentId: number, resolution?: UpdateConflictResolution): Promise<Agent> {
+    try {
+      return await invoke<Agent>('apply_agent_update', { agentId, resolution });
+    } catch (error) {
+      console.error("Failed to apply agent update:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sets the MCP servers, bundled slash commands and minimum Claude Code version of an agent
+   * @param id - The agent ID
+   */
+  async updateAgentRequirements(
+    id: number,
+    requiredMcpServers: RequiredMcpServer[],
+    slashCommands: BundledSlashCommand[],
+    minClaudeVersion?: string
+  ): Promise<Agent> {
+    try {
+      return await invoke<Agent>('update_agent_requirements', { id, requiredMcpServers, slashCommands, minClaudeVersion });
+    } catch (error) {
+      console.error("Failed to update agent requirements:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the JSON schema of .claudia.json agent files
+   */
+  async getAgentExportSchema(): Promise<Record<string, any>> {
+    try {
+      return await invoke<Record<string, any>>('get_agent_export_schema');
+    } catch (error) {
+      console.error("Failed to get agent export schema:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists native subagents in ~/.claude/agents and, optionally, a project's .claude/agents
+   * @param projectPath - Optional project whose subagents are included
+   */
+  async listNativeSubagents(projectPath?: string): Promise<NativeSubagent[]> {
+    try {
+      return await invoke<NativeSubagent[]>('list_native_subagents', { projectPath });
+    } catch (error) {
+      console.error("Failed to list native subagents:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Imports a native subagent file as an agent, overwriting the linked agent if any
+   * @param filePath - Path to the subagent markdown file
+   */
+  async importNativeSubagent(filePath: string): Promise<Agent> {
+    try {
+      return await invoke<Agent>('import_native_subagent', { filePath });
+    } catch (error) {
+      console.error("Failed to import native subagent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Writes an agent as a native subagent file, overwriting the existing file if any
+   * @param agentId - The agent ID
+   * @param scope - "user" or "project"
+   * @param projectPath - Project directory, required for the project scope
+   */
+  async exportAgentToNative(
+    agentId: number,
+    scope: 'user' | 'project',
+    projectPath?: string
+  ): Promise<NativeSubagent> {
+    try {
+      return await invoke<NativeSubagent>('export_agent_to_native', { agentId, scope, projectPath });
+    } catch (error) {
+      console.error("Failed to export agent to native subagent:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Syncs linked subagents in both directions and reports conflicts and missing files
+   * @param projectPath - Optional project whose subagents are included
+   */
+  async syncNativeSubagents(projectPath?: string): Promise<SubagentSyncReport> {
+    try {
+      return await invoke<SubagentSyncReport>('sync_native_subagents', { projectPath });
+    } catch (error) {
+      console.error("Failed to sync native subagents:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists an agent's environment variables with secret values hidden
+   * @param agentId - The agent ID
+   */
+  async listAgentEnvVars(agentId: number): Promise<AgentEnvVar[]> {
+    try {
+      return await invoke<AgentEnvVar[]>('list_agent_env_vars', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent env vars:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates or replaces an environment variable of an agent
+   * @param agentId - The agent ID
+   * @param key - Variable name
+   * @param value - Variable value, encrypted at rest when secret
+   * @param isSecret - Whether the value is a secret
+   */
+  async setAgentEnvVar(agentId: number, key: string, value: string, isSecret: boolean): Promise<AgentEnvVar> {
+    try {
+      return await invoke<AgentEnvVar>('set_agent_env_var', { agentId, key, value, isSecret });
+    } catch (error) {
+      console.error("Failed to set agent env var:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes an environment variable
+   * @param id - The variable ID
+   */
+  async deleteAgentEnvVar(id: number): Promise<void> {
+    try {
+      return await invoke('delete_agent_env_var', { id });
+    } catch (error) {
+      console.error("Failed to delete agent env var:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Queues a run with the same revision, task, model and project as an earlier run
+   * @param runId - The run to repeat
+   * @param overrides - Optional changes, e.g. a different model
+   * @returns Promise resolving to the new run ID
+   */
+  async rerunAgentRun(runId: number, overrides?: RerunOverrides): Promise<number> {
+    try {
+      return await invoke<number>('rerun_agent_run', { runId, overrides });
+    } catch (error) {
+      console.error("Failed to rerun agent run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Queues a rerun of every finished run matching a filter
+   * @param filter - Runs to repeat, e.g. failed runs within the last 24 hours
+   * @param overrides - Optional changes applied to every rerun
+   * @returns Promise resolving to the new run IDs
+   */
+  async rerunAgentRuns(filter: AgentRunFilter, overrides?: RerunOverrides): Promise<number[]> {
+    try {
+      return await invoke<number[]>('rerun_agent_runs', { filter, overrides });
+    } catch (error) {
+      console.error("Failed to rerun agent runs:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Queues one run of an agent per project under a shared batch
+   * @param agentId - The agent ID
+   * @param projectPaths - Explicit project directories
+   * @param projectGlob - Optional glob of project directories, e.g. "~/code/*"
+   * @param task - The task, may contain {{variables}}
+   * @param model - Optional model override
+   * @param variables - Optional values for the task's {{variables}}
+   * @param budget - Optional per-run budget
+   * @param useWorktree - Run each project in an isolated git worktree
+   */
+  async executeAgentBatch(
+    agentId: number,
+    projectPaths: string[],
+    projectGlob: string | undefined,
+    task: string,
+    model?: string,
+    variables?: Record<string, string>,
+    budget?: RunBudget,
+    useWorktree?: boolean
+  ): Promise<AgentBatch> {
+    try {
+      return await invoke<AgentBatch>('execute_agent_batch', {
+        agentId,
+        projectPaths,
+        projectGlob,
+        task,
+        model,
+        variables,
+        budget,
+        useWorktree,
+      });
+    } catch (error) {
+      console.error("Failed to execute agent batch:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets a batch with its aggregate status, total cost and per-project runs
+   * @param id - The batch ID
+   */
+  async getAgentBatch(id: number): Promise<AgentBatch> {
+    try {
+      return await invoke<AgentBatch>('get_agent_batch', { id });
+    } catch (error) {
+      console.error("Failed to get agent batch:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists batches, newest first
+   * @param agentId - Optional agent to filter by
+   */
+  async listAgentBatches(agentId?: number): Promise<AgentBatch[]> {
+    try {
+      return await invoke<AgentBatch[]>('list_agent_batches', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent batches:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Cancels the queued runs of a batch and kills the running ones
+   * @param id - The batch ID
+   */
+  async cancelAgentBatch(id: number): Promise<AgentBatch> {
+    try {
+      return await invoke<AgentBatch>('cancel_agent_batch', { id });
+    } catch (error) {
+      console.error("Failed to cancel agent batch:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sets or clears the JSON schema an agent's final answer is validated against
+   * @param agentId - The agent ID
+   * @param outputSchema - JSON schema text, or undefined to clear it
+   */
+  async setAgentOutputSchema(agentId: number, outputSchema?: string): Promise<void> {
+    try {
+      return await invoke('set_agent_output_schema', { agentId, outputSchema });
+    } catch (error) {
+      console.error("Failed to set agent output schema:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists eval suites
+   * @param agentId - Optional agent to filter by
+   */
+  async listEvalSuites(agentId?: number): Promise<EvalSuite[]> {
+    try {
+      return await invoke<EvalSuite[]>('list_eval_suites', { agentId });
+    } catch (error) {
+      console.error("Failed to list eval suites:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets an eval suite with its cases
+   * @param id - The suite ID
+   */
+  async getEvalSuite(id: number): Promise<EvalSuite> {
+    try {
+      return await invoke<EvalSuite>('get_eval_suite', { id });
+    } catch (error) {
+      console.error("Failed to get eval suite:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates an eval suite for an agent
+   * @param agentId - The agent under test
+   * @param name - Suite name
+   * @param description - Optional description
+   * @param cases - Test cases
+   */
+  async createEvalSuite(
+    agentId: number,
+    name: string,
+    description: string | undefined,
+    cases: EvalCase[]
+  ): Promise<EvalSuite> {
+    try {
+      return await invoke<EvalSuite>('create_eval_suite', { agentId, name, description, cases });
+    } catch (error) {
+      console.error("Failed to create eval suite:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Updates an eval suite's name, description and cases
+   * @param id - The suite ID
+   * @param name - Suite name
+   * @param description - Optional description
+   * @param cases - Test cases
+   */
+  async updateEvalSuite(
+    id: number,
+    name: string,
+    description: string | undefined,
+    cases: EvalCase[]
+  ): Promise<EvalSuite> {
+    try {
+      return await invoke<EvalSuite>('update_eval_suite', { id, name, description, cases });
+    } catch (error) {
+      console.error("Failed to update eval suite:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes an eval suite; reports of its runs are kept
+   * @param id - The suite ID
+   */
+  async deleteEvalSuite(id: number): Promise<void> {
+    try {
+      return await invoke('delete_eval_suite', { id });
+    } catch (error) {
+      console.error("Failed to delete eval suite:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Runs every case of a suite against the agent's current revision
+   * @param suiteId - The suite ID
+   * @param model - Optional model overriding the cases' and agent's model
+   * @returns The eval run; listen to `eval-run-updated:{id}` and `eval-run-complete:{id}` for progress
+   */
+  async runEvalSuite(suiteId: number, model?: string): Promise<EvalRun> {
+    try {
+      return await invoke<EvalRun>('run_eval_suite', { suiteId, model });
+    } catch (error) {
+      console.error("Failed to run eval suite:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the pass/fail report of an eval run
+   * @param id - The eval run ID
+   */
+  async getEvalRun(id: number): Promise<EvalRun> {
+    try {
+      return await invoke<EvalRun>('get_eval_run', { id });
+    } catch (error) {
+      console.error("Failed to get eval run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists eval runs, newest first
+   * @param suiteId - Optional suite to filter by
+   * @param agentId - Optional agent to filter by
+   */
+  async listEvalRuns(suiteId?: number, agentId?: number): Promise<EvalRun[]> {
+    try {
+      return await invoke<EvalRun[]>('list_eval_runs', { suiteId, agentId });
+    } catch (error) {
+      console.error("Failed to list eval runs:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Cancels an eval run, dropping queued cases and killing running ones
+   * @param id - The eval run ID
+   */
+  async cancelEvalRun(id: number): Promise<EvalRun> {
+    try {
+      return await invoke<EvalRun>('cancel_eval_run', { id });
+    } catch (error) {
+      console.error("Failed to cancel eval run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Runs an agent on the same task under several models, each in its own worktree
+   * (git projects) or temporary copy of the project
+   * @param agentId - The agent ID
+   * @param projectPath - The project to run in
+   * @param task - Task, may reference the agent's task variables
+   * @param models - At least two different models
+   * @param variables - Optional values for task variables
+   * @param budget - Optional caps for each run
+   */
+  async compareAgentModels(
+    agentId: number,
+    projectPath: string,
+    task: string,
+    models: string[],
+    variables?: Record<string, string>,
+    budget?: RunBudget
+  ): Promise<AgentComparison> {
+    try {
+      return await invoke<AgentComparison>('compare_agent_models', {
+        agentId,
+        projectPath,
+        task,
+        models,
+        variables,
+        budget,
+      });
+    } catch (error) {
+      console.error("Failed to compare agent models:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets a comparison with the metrics, answer and changes of each model's run
+   * @param id - The comparison ID
+   */
+  async getAgentComparison(id: number): Promise<AgentComparison> {
+    try {
+      return await invoke<AgentComparison>('get_agent_comparison', { id });
+    } catch (error) {
+      console.error("Failed to get agent comparison:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists comparisons, newest first
+   * @param agentId - Optional agent to filter by
+   */
+  async listAgentComparisons(agentId?: number): Promise<AgentComparison[]> {
+    try {
+      return await invoke<AgentComparison[]>('list_agent_comparisons', { agentId });
+    } catch (error) {
+      console.error("Failed to list agent comparisons:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a finished comparison and its temporary project copies
+   * @param id - The comparison ID
+   */
+  async deleteAgentComparison(id: number): Promise<void> {
+    try {
+      return await invoke('delete_agent_comparison', { id });
+    } catch (error) {
+      console.error("Failed to delete agent comparison:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Rates, describes and tags a run; clearing every field removes the annotation
+   * @param runId - The run ID
+   * @param rating - 1 (bad) to 5 (good)
+   * @param notes - Free-form notes
+   * @param tags - Labels such as "hallucinated" or "perfect"
+   * @returns The updated run
+   */
+  async setAgentRunAnnotation(
+    runId: number,
+    rating: number | undefined,
+    notes: string | undefined,
+    tags: string[]
+  ): Promise<AgentRun> {
+    try {
+      return await invoke<AgentRun>('set_agent_run_annotation', { runId, rating, notes, tags });
+    } catch (error) {
+      console.error("Failed to annotate agent run:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Moves an agent into a folder, or out of all folders
+   * @param agentId - The agent ID
+   * @param folder - Folder path such as "Reviews/Security"; empty or undefined for none
+   * @returns The updated agent
+   */
+  async setAgentFolder(agentId: number, folder?: string): Promise<Agent> {
+    try {
+      return await invoke<Agent>('set_agent_folder', { agentId, folder });
+    } catch (error) {
+      console.error("Failed to set agent folder:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Replaces the tags of an agent
+   * @param agentId - The agent ID
+   * @param tags - The new tags
+   * @returns The updated agent
+   */
+  async setAgentTags(agentId: number, tags: string[]): Promise<Agent> {
+    try {
+      return await invoke<Agent>('set_agent_tags', { agentId, tags });
+    } catch (error) {
+      console.error("Failed to set agent tags:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Adds an agent to or removes it from the favourites
+   * @param agentId - The agent ID
+   * @param favorite - Whether the agent is a favourite
+   * @returns The updated agent
+   */
+  async setAgentFavorite(agentId: number, favorite: boolean): Promise<Agent> {
+    try {
+      return await invoke<Agent>('set_agent_favorite', { agentId, favorite });
+    } catch (error) {
+      console.error("Failed to set agent favourite:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists all folders that contain agents, sorted by name
+   */
+  async listAgentFolders(): Promise<string[]> {
+    try {
+      return await invoke<string[]>('list_agent_folders');
+    } catch (error) {
+      console.error("Failed to list agent folders:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists all tags used on agents, most used first
+   */
+  async listAgentTags(): Promise<string[]> {
+    try {
+      return await invoke<string[]>('list_agent_tags');
+    } catch (error) {
+      console.error("Failed to list agent tags:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists all tags used on runs, most used first
+   */
+  async listAgentRunTags(): Promise<string[]> {
+    try {
+      return await invoke<string[]>('list_agent_run_tags');
+    } catch (error) {
+      console.error("Failed to list agent run tags:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the success rate and average rating of an agent's runs, overall and per revision
+   * @param agentId - The agent ID
+   */
+  async getAgentRunStats(agentId: number): Promise<AgentRunStats> {
+    try {
+      return await invoke<AgentRunStats>('get_agent_run_stats', { agentId });
+    } catch (error) {
+      console.error("Failed to get agent run stats:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reverts the files a finished run changed to their state before the run
+   * @param runId - The run ID
+   */
+  async restoreAgentRunChanges(runId: number): Promise<RunRestoreResult> {
+    try {
+      return await invoke<RunRestoreResult>('restore_agent_run_changes', { runId });
+    } catch (error) {
+      console.error("Failed to restore agent run changes:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists all webhooks
+   */
+  async listWebhooks(): Promise<Webhook[]> {
+    try {
+      return await invoke<Webhook[]>('list_webhooks');
+    } catch (error) {
+      console.error("Failed to list webhooks:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates a webhook
+   * @param name - Display name
+   * @param url - http or https endpoint
+   * @param events - Events that trigger a delivery
+   * @param payloadTemplate - Optional body template with {{placeholders}}
+   * @param secret - Optional HMAC secret used to sign requests, encrypted at rest
+   */
+  async createWebhook(
+    name: string,
+    url: string,
+    events: WebhookEvent[],
+    payloadTemplate?: string,
+    secret?: string
+  ): Promise<Webhook> {
+    try {
+      return await invoke<Webhook>('create_webhook', { name, url, events, payloadTemplate, secret });
+    } catch (error) {
+      console.error("Failed to create webhook:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Updates a webhook
+   * @param id - The webhook ID
+   * @param secret - New HMAC secret; omit to keep the current one, pass "" to remove it
+   */
+  async updateWebhook(
+    id: number,
+    name: string,
+    url: string,
+    events: WebhookEvent[],
+    payloadTemplate: string | undefined,
+    enabled: boolean,
+    secret?: string
+  ): Promise<Webhook> {
+    try {
+      return await invoke<Webhook>('update_webhook', { id, name, url, events, payloadTemplate, enabled, secret });
+    } catch (error) {
+      console.error("Failed to update webhook:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a webhook and its delivery log
+   * @param id - The webhook ID
+   */
+  async deleteWebhook(id: number): Promise<void> {
+    try {
+      return await invoke('delete_webhook', { id });
+    } catch (error) {
+      console.error("Failed to delete webhook:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists recent webhook deliveries, newest first
+   * @param webhookId - Optional webhook to filter by
+   * @param limit - Maximum number of deliveries (default 50)
+   */
+  async listWebhookDeliveries(webhookId?: number, limit?: number): Promise<WebhookDelivery[]> {
+    try {
+      return await invoke<WebhookDelivery[]>('list_webhook_deliveries', { webhookId, limit });
+    } catch (error) {
+      console.error("Failed to list webhook deliveries:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sends a test event to a webhook once and returns the logged delivery
+   * @param id - The webhook ID
+   */
+  async testWebhook(id: number): Promise<WebhookDelivery> {
+    try {
+      return await invoke<WebhookDelivery>('test_webhook', { id });
+    } catch (error) {
+      console.error("Failed to test webhook:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the desktop notification preferences
+   */
+  async getNotificationPreferences(): Promise<NotificationPreferences> {
+    try {
+      return await invoke<NotificationPreferences>('get_notification_preferences');
+    } catch (error) {
+      console.error("Failed to get notification preferences:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the desktop notification preferences
+   * @param preferences - Which events raise a notification
+   */
+  async saveNotificationPreferences(preferences: NotificationPreferences): Promise<void> {
+    try {
+      return await invoke('save_notification_preferences', { preferences });
+    } catch (error) {
+      console.error("Failed to save notification preferences:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Shows a sample desktop notification
+   */
+  async sendTestNotification(): Promise<void> {
+    try {
+      return await invoke('send_test_notification');
+    } catch (error) {
+      console.error("Failed to send test notification:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Executes a new interactive Claude Code session with streaming output
+   */
+  async executeClaudeCode(projectPath: string, prompt: string, model: string): Promise<void> {
+    return invoke("execute_claude_code", { projectPath, prompt, model });
+  },
+
+  /**
+   * Continues an existing Claude Code conversation with streaming output
+   */
+  async continueClaudeCode(projectPath: string, prompt: string, model: string): Promise<void> {
+    return invoke("continue_claude_code", { projectPath, prompt, model });
+  },
+
+  /**
+   * Resumes an existing Claude Code session by ID with streaming output
+   */
+  async resumeClaudeCode(projectPath: string, sessionId: string, prompt: string, model: string): Promise<void> {
+    return invoke("resume_claude_code", { projectPath, sessionId, prompt, model });
+  },
+
+  /**
+   * Cancels the currently running Claude Code execution
+   * @param sessionId - Optional session ID to cancel a specific session
+   */
+  async cancelClaudeExecution(sessionId?: string): Promise<void> {
+    return invoke("cancel_claude_execution", { sessionId });
+  },
+
+  /**
+   * Cancels the automatic resume of a session stopped by a usage limit
+   * @param sessionId - The session waiting for its usage limit to reset
+   * @returns Promise resolving to false if no resume was scheduled
+   */
+  async cancelSessionResume(sessionId: string): Promise<boolean> {
+    return invoke("cancel_session_resume", { sessionId });
   },
 
   /**